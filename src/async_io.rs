@@ -0,0 +1,55 @@
+//! Loading and saving on tokio readers and writers, for use in async services.
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{file::GraphFile, Error, Frame, TfGraph};
+
+impl<N: Frame> TfGraph<N> {
+    /// Like `dump_json`, writing to an async writer. The JSON is built in memory, then written at once.
+    pub async fn dump_json_async(&self, writer: &mut (impl AsyncWrite + Unpin)) -> std::io::Result<()>
+    where N: serde::Serialize
+    {
+        let mut buf = Vec::new();
+        GraphFile::from_graph(self).write_pretty(&mut buf)?;
+        writer.write_all(&buf).await?;
+        writer.flush().await
+    }
+
+    /// Like `load_json`, reading an async reader to its end first.
+    pub async fn load_json_async(&mut self, reader: &mut (impl AsyncRead + Unpin)) -> Result<(), Error>
+    where N: serde::de::DeserializeOwned
+    {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).await?;
+        self.load_json(&mut buf.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{se3, Error, TfGraph};
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn async_roundtrip() {
+        let mut g = TfGraph::new();
+        g.add_tf("a".to_owned(), "b".to_owned(), se3::random()).unwrap();
+        g.add_tf("c".to_owned(), "b".to_owned(), se3::random()).unwrap();
+
+        let (mut writer, mut reader) = tokio::io::duplex(64);
+        let (written, loaded) = tokio::join!(
+            async {
+                let written = g.dump_json_async(&mut writer).await;
+                drop(writer);
+                written
+            },
+            async {
+                let mut g2 = TfGraph::new();
+                g2.load_json_async(&mut reader).await.map(|_| g2)
+            },
+        );
+        written.unwrap();
+        let g2 = loaded.unwrap();
+        assert!(g2.transforms().eq(g.transforms()));
+        assert!(matches!(TfGraph::<String>::new().load_json_async(&mut &b"{"[..]).await, Err(Error::Parse(_))));
+    }
+}
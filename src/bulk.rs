@@ -0,0 +1,142 @@
+//! Adding many transforms at once.
+
+use std::fmt;
+
+use petgraph::Direction;
+
+use crate::{Edge, Frame, InvalidName, TransformEdge, TransformGraph, Warning};
+
+/// Why `add_tfs` rejected a transform.
+///
+/// In JSON: `{"error": "cycle"}`, or `{"error": "invalid_name", "name": "/cam", "reason": "..."}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize), serde(tag = "error", rename_all = "snake_case"))]
+pub enum AddError {
+    /// The graph is frozen.
+    Frozen,
+    InvalidName(InvalidName),
+    /// Both names refer to the same frame.
+    SelfLoop,
+    /// The transform would make the graph cyclic, and cycles are forbidden.
+    Cycle,
+}
+
+impl fmt::Display for AddError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddError::Frozen => write!(f, "graph is frozen"),
+            AddError::InvalidName(e) => e.fmt(f),
+            AddError::SelfLoop => write!(f, "transform from a frame to itself"),
+            AddError::Cycle => write!(f, "transform would close a cycle"),
+        }
+    }
+}
+
+impl std::error::Error for AddError {}
+
+impl<N: Frame, E: TransformEdge> TransformGraph<N, E> {
+    /// Add many transforms, like `add_tf` for each in turn, returning the result of each.
+    ///
+    /// Rejected transforms are skipped, and the others are kept. Cycles are detected in constant time per
+    /// transform, so this is much faster than `add_tf` for large imports when cycles are forbidden.
+    pub fn add_tfs(&mut self, tfs: impl IntoIterator<Item = (N, N, E)>) -> Vec<Result<(), AddError>> {
+        if self.frozen {
+            return tfs.into_iter().map(|_| Err(AddError::Frozen)).collect();
+        }
+        let mut groups = UnionFind::new(self.g.node_count());
+        for e in self.g.edge_indices() {
+            let (a, b) = self.g.edge_endpoints(e).expect("edge exists");
+            groups.union(a.index(), b.index());
+        }
+
+        let results = tfs.into_iter().map(|(src, dst, tf)| {
+            for name in [src.name(), dst.name()] {
+                self.check_name(name).map_err(AddError::InvalidName)?;
+            }
+            if self.names.matches(src.name(), dst.name()) {
+                return Err(AddError::SelfLoop);
+            }
+            let [a, b] = [src, dst].map(|n| {
+                self.find_node(n.name()).unwrap_or_else(|| {
+                    groups.push();
+                    self.add_node(n)
+                })
+            });
+            let (x, y, tf) = self.oriented(a, b, tf);
+            if let Some((e, Direction::Outgoing)) = self.g.find_edge_undirected(x, y) {
+                self.g[e].tf = tf;
+                self.g[e].samples.poses.clear();
+                let (from, to) = self.edge_names(a, e);
+                self.warn(Warning::Overwritten { from, to });
+            } else if self.allow_cycles || groups.union(x.index(), y.index()) {
+                self.g.add_edge(x, y, Edge::new(tf));
+            } else {
+                return Err(AddError::Cycle);
+            }
+            Ok(())
+        }).collect();
+        self.index = None;
+        results
+    }
+}
+
+/// Disjoint sets of node indices, to tell whether two frames are connected.
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        Self { parent: (0..n).collect() }
+    }
+
+    /// Add a set with a single new index.
+    fn push(&mut self) {
+        self.parent.push(self.parent.len());
+    }
+
+    fn find(&mut self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            // Path halving
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    /// Merge the sets of `a` and `b`. Returns `false` if they were the same set already.
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parent[a] = b;
+        a != b
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{se3, InvalidName, TfGraph};
+    use super::AddError;
+
+    #[test]
+    fn add_tfs() {
+        let mut g = TfGraph::new();
+        g.add_tf("a".to_owned(), "b".to_owned(), se3::random()).unwrap();
+        let tf = se3::random();
+        let tfs = [("b", "c"), ("c", "a"), ("a", "b"), ("d", "d"), ("d", "/e"), ("d", "a"), ("d", "c")];
+        let results = g.add_tfs(tfs.map(|(a, b)| (a.to_owned(), b.to_owned(), tf)));
+        assert_eq!(results, [
+            Ok(()),
+            Err(AddError::Cycle),
+            Ok(()),
+            Err(AddError::SelfLoop),
+            Err(AddError::InvalidName(InvalidName { name: "/e".to_owned(), reason: "leading or trailing slash" })),
+            Ok(()),
+            Err(AddError::Cycle),
+        ]);
+        assert!(g.transforms().eq([("a", "b"), ("b", "c"), ("d", "a")]));
+        assert_eq!(g.query_tf("a", "b").unwrap().0, tf);
+
+        g.freeze();
+        assert_eq!(g.add_tfs([("x".to_owned(), "y".to_owned(), tf)]), [Err(AddError::Frozen)]);
+    }
+}
@@ -0,0 +1,68 @@
+//! Calibration of fixed transforms from measured motions.
+
+use nalgebra as na;
+
+use crate::{se3::{self, SE3}, Frame, TfGraph};
+
+/// Solve `A_i * X = X * B_i` for the fixed transform `X`, given pairs of relative motions `(A_i, B_i)`,
+/// with the method of Park and Martin.
+///
+/// If `X` maps points in frame `b` into frame `a` (the edge `b -> a`), then `A_i` and `B_i` are
+/// the motions of `a` and `b` between the same two instants, as transforms from the later frame
+/// pose into the earlier one. This is the hand-eye problem, with `a` the hand and `b` the eye.
+///
+/// Returns `None` unless at least two motions rotate about non-parallel axes.
+pub fn hand_eye(motions: &[(SE3, SE3)]) -> Option<SE3> {
+    // Rotation: R_X maps the rotation axes of B onto those of A.
+    let h: na::Matrix3<f64> = motions.iter()
+        .map(|(a, b)| b.rotation.scaled_axis() * a.rotation.scaled_axis().transpose())
+        .sum();
+    let rot = se3::fit_rotation(&h)?;
+
+    // Translation: (R_A - I) t_X = R_X t_B - t_A, in the least-squares sense.
+    let mut lhs = na::Matrix3::zeros();
+    let mut rhs = na::Vector3::zeros();
+    for (a, b) in motions {
+        let c = a.rotation.to_rotation_matrix().into_inner() - na::Matrix3::identity();
+        lhs += c.transpose() * c;
+        rhs += c.transpose() * (rot * b.translation.vector - a.translation.vector);
+    }
+    let t = lhs.cholesky()?.solve(&rhs);
+
+    Some(SE3::from_parts(t.into(), rot.into()))
+}
+
+impl<N: Frame> TfGraph<N> {
+    /// Solve for the transform `src -> dst` with `hand_eye`, and add it to the graph.
+    ///
+    /// `motions` are pairs of relative motions of `(dst, src)`. Returns the added transform in the graph's
+    /// `EdgeConvention`, or `None` if it could not be determined or would make the graph cyclic.
+    pub fn add_hand_eye(&mut self, src: N, dst: N, motions: &[(SE3, SE3)]) -> Option<SE3> {
+        let x = self.directed(hand_eye(motions)?);
+        self.add_tf(src, dst, x).ok()?;
+        Some(x)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+    use crate::{se3, TfGraph};
+
+    #[test]
+    fn hand_eye() {
+        let x = se3::random();
+        let motions: Vec<_> = (0..5).map(|_| {
+            let a = se3::random();
+            (a, x.inverse() * a * x)
+        }).collect();
+
+        let mut g = TfGraph::new();
+        let solved = g.add_hand_eye("cam".to_owned(), "gripper".to_owned(), &motions).unwrap();
+        assert_relative_eq!(solved, x, epsilon = 1e-9);
+        assert_relative_eq!(g.query_tf("cam", "gripper").unwrap().0, x, epsilon = 1e-9);
+
+        // A single motion leaves a rotation about its axis undetermined.
+        assert!(super::hand_eye(&motions[..1]).is_none());
+    }
+}
@@ -0,0 +1,64 @@
+//! Pinhole camera projection, for sanity checks of camera calibrations.
+
+use nalgebra as na;
+
+use crate::{Frame, TfGraph};
+
+/// Pinhole intrinsics, in pixels, without distortion.
+///
+/// Camera frames follow the optical convention (`rdf`): x right, y down, z forward along the optical axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pinhole {
+    pub fx: f64,
+    pub fy: f64,
+    pub cx: f64,
+    pub cy: f64,
+}
+
+impl Pinhole {
+    pub fn new(fx: f64, fy: f64, cx: f64, cy: f64) -> Self {
+        Self { fx, fy, cx, cy }
+    }
+
+    /// Pixel coordinates of a point in the camera frame, or `None` if it is not in front of the camera.
+    pub fn project(&self, p: &na::Point3<f64>) -> Option<na::Point2<f64>> {
+        if p.z <= 0.0 {
+            return None;
+        }
+        Some(na::Point2::new(self.fx * p.x / p.z + self.cx, self.fy * p.y / p.z + self.cy))
+    }
+}
+
+impl<N: Frame> TfGraph<N> {
+    /// Project point `p`, given in frame `world`, into the image of `camera`.
+    ///
+    /// Returns `None` if the frames are not connected, or if the point is not in front of the camera.
+    pub fn project(&self, world: &str, camera: &str, intrinsics: &Pinhole, p: &na::Point3<f64>) -> Option<na::Point2<f64>> {
+        intrinsics.project(&self.transform_point(*p, world, camera)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+    use nalgebra as na;
+    use crate::{se3, EdgeConvention, TfGraph};
+    use super::Pinhole;
+
+    #[test]
+    fn project() {
+        let k = Pinhole::new(500.0, 500.0, 320.0, 240.0);
+        let mut g = TfGraph::new();
+        // Camera 1 m behind the world origin, looking along world z.
+        g.add_tf("world".to_owned(), "cam".to_owned(), se3::from_array(&[0.0, 0.0, 1.0]).unwrap()).unwrap();
+
+        let px = g.project("world", "cam", &k, &na::Point3::new(0.2, -0.1, 1.0)).unwrap();
+        assert_relative_eq!(px, na::Point2::new(370.0, 215.0), epsilon = 1e-9);
+        assert!(g.project("world", "cam", &k, &na::Point3::new(0.0, 0.0, -2.0)).is_none());
+        assert!(g.project("world", "nowhere", &k, &na::Point3::origin()).is_none());
+
+        g.set_edge_convention(EdgeConvention::TargetToSource);
+        let again = g.project("world", "cam", &k, &na::Point3::new(0.2, -0.1, 1.0)).unwrap();
+        assert_relative_eq!(again, px, epsilon = 1e-9);
+    }
+}
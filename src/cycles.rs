@@ -0,0 +1,268 @@
+//! Queries for graphs with cycles allowed, where a transform may be reachable via several paths.
+
+use std::collections::VecDeque;
+
+use petgraph::{graph::{EdgeIndex, NodeIndex}, visit::EdgeRef};
+
+use crate::{se3::SE3, Frame, TfGraph, TransformEdge, TransformGraph};
+
+/// All paths between two frames, returned by `query_all_paths`.
+#[derive(Debug, Clone)]
+pub struct AllPaths<'a> {
+    /// Composed transform and frame path of each simple path, shortest first.
+    pub paths: Vec<(SE3, Vec<&'a str>)>,
+    /// Disagreement between each pair of paths.
+    pub disagreements: Vec<Disagreement>,
+}
+
+/// Difference between the transforms composed along two paths.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Disagreement {
+    /// Indices into `AllPaths::paths`.
+    pub pair: (usize, usize),
+    /// Distance between the translations.
+    pub translation: f64,
+    /// Angle of the relative rotation, in radians.
+    pub rotation: f64,
+}
+
+impl Disagreement {
+    pub fn between(pair: (usize, usize), a: &SE3, b: &SE3) -> Self {
+        Self {
+            pair,
+            translation: (a.translation.vector - b.translation.vector).norm(),
+            rotation: a.rotation.angle_to(&b.rotation),
+        }
+    }
+}
+
+impl AllPaths<'_> {
+    /// Mean `(translation, rotation)` disagreement of each path with all others.
+    ///
+    /// The path with the largest values is the most likely outlier.
+    pub fn mean_disagreement(&self) -> Vec<(f64, f64)> {
+        let n = self.paths.len();
+        let mut sums = vec![(0.0, 0.0); n];
+        for d in &self.disagreements {
+            for i in [d.pair.0, d.pair.1] {
+                sums[i].0 += d.translation;
+                sums[i].1 += d.rotation;
+            }
+        }
+        let others = (n.max(2) - 1) as f64;
+        sums.into_iter().map(|(t, r)| (t / others, r / others)).collect()
+    }
+}
+
+/// A transform suspected by `detect_outliers`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outlier<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    /// How much disabling the transform lowers the total loop residual.
+    pub reduction: f64,
+}
+
+impl<N: Frame> TfGraph<N> {
+    /// Rank transforms by how much disabling each one lowers the loop closure residuals, most suspect first.
+    ///
+    /// Each loop of enabled transforms should compose to the identity. The residual of a loop is by how many
+    /// times it exceeds the tolerance in translation or in rotation (radians), and zero for loops within
+    /// tolerance. Only transforms whose removal lowers the total are returned, so the result is empty if all
+    /// loops are consistent, or if the graph has no loops.
+    pub fn detect_outliers(&self, translation: f64, rotation: f64) -> Vec<Outlier<'_>> {
+        let total = self.loop_residual(None, translation, rotation);
+        if total == 0.0 {
+            return Vec::new();
+        }
+        let mut outliers: Vec<_> = self.g.edge_indices()
+            .filter(|&e| self.g[e].enabled)
+            .filter_map(|e| {
+                let reduction = total - self.loop_residual(Some(e), translation, rotation);
+                let (a, b) = self.g.edge_endpoints(e)?;
+                (reduction > 0.0).then(|| Outlier { from: self.g[a].name(), to: self.g[b].name(), reduction })
+            })
+            .collect();
+        outliers.sort_by(|a, b| b.reduction.total_cmp(&a.reduction));
+        outliers
+    }
+
+    /// Sum of the residuals of a cycle basis of the enabled edges other than `skip`: the loops closed by each
+    /// edge outside a breadth-first spanning forest.
+    fn loop_residual(&self, skip: Option<EdgeIndex>, translation: f64, rotation: f64) -> f64 {
+        let usable = |e: EdgeIndex| self.g[e].enabled && Some(e) != skip;
+        let mut parent: Vec<Option<(NodeIndex, EdgeIndex)>> = vec![None; self.g.node_count()];
+        let mut depth = vec![usize::MAX; self.g.node_count()];
+        for root in self.g.node_indices() {
+            if depth[root.index()] != usize::MAX {
+                continue;
+            }
+            depth[root.index()] = 0;
+            let mut queue = VecDeque::from([root]);
+            while let Some(node) = queue.pop_front() {
+                for e in self.g.edges(node).filter(|e| usable(e.id())) {
+                    let next = if e.source() == node { e.target() } else { e.source() };
+                    if depth[next.index()] == usize::MAX {
+                        depth[next.index()] = depth[node.index()] + 1;
+                        parent[next.index()] = Some((node, e.id()));
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        let mut in_tree = vec![false; self.g.edge_count()];
+        parent.iter().flatten().for_each(|&(_, e)| in_tree[e.index()] = true);
+        self.g.edge_indices().filter(|&e| usable(e) && !in_tree[e.index()]).map(|e| {
+            let (a, b) = self.g.edge_endpoints(e).unwrap();
+            // Walk up from both ends to the common ancestor, as (edge, node it is entered from) steps.
+            let (mut x, mut y) = (a, b);
+            let (mut up, mut down) = (Vec::new(), Vec::new());
+            while x != y {
+                if depth[x.index()] >= depth[y.index()] {
+                    let (p, edge) = parent[x.index()].unwrap();
+                    up.push((edge, x));
+                    x = p;
+                } else {
+                    let (p, edge) = parent[y.index()].unwrap();
+                    down.push((edge, p));
+                    y = p;
+                }
+            }
+            let through_tree = up.into_iter().chain(down.into_iter().rev())
+                .fold(SE3::identity(), |tf, (edge, from)| self.chain_edge(edge, from, &tf));
+            let direct = self.chain_edge(e, a, &SE3::identity());
+            let d = Disagreement::between((0, 0), &through_tree, &direct);
+            (d.translation / translation).max(d.rotation / rotation).max(1.0) - 1.0
+        }).sum()
+    }
+
+    /// Compose the transform along every simple path from `src` to `dst`, up to `limit` paths.
+    ///
+    /// Parallel edges count as separate paths, disabled edges are skipped.
+    /// Returns `None` if either frame does not exist.
+    pub fn query_all_paths(&self, src: &str, dst: &str, limit: usize) -> Option<AllPaths<'_>> {
+        let paths = self.all_paths(src, dst, limit)?;
+        let disagreements = (0..paths.len())
+            .flat_map(|i| (i + 1..paths.len()).map(move |j| (i, j)))
+            .map(|(i, j)| Disagreement::between((i, j), &paths[i].0, &paths[j].0))
+            .collect();
+        Some(AllPaths { paths, disagreements })
+    }
+}
+
+impl<N: Frame, E: TransformEdge> TransformGraph<N, E> {
+    /// Composed transforms along simple paths from `src` to `dst`, shortest first.
+    pub(crate) fn all_paths(&self, src: &str, dst: &str, limit: usize) -> Option<Vec<(E, Vec<&str>)>> {
+        let (src, dst) = (self.find_node(src)?, self.find_node(dst)?);
+        let mut found = Vec::new();
+        self.simple_paths(src, dst, &mut vec![src], &mut Vec::new(), &mut found, limit);
+        found.sort_by_key(|steps| steps.len());
+
+        Some(found.into_iter().map(|steps| {
+            let mut tf = E::identity();
+            let mut path = vec![self.g[src].name()];
+            for (edge, from) in steps {
+                let (a, b) = self.g.edge_endpoints(edge).unwrap();
+                path.push(self.g[if a == from { b } else { a }].name());
+                tf = self.chain_edge(edge, from, &tf);
+            }
+            (self.directed(tf), path)
+        }).collect())
+    }
+
+    /// Depth-first enumeration of simple paths, as `(edge, node the edge is entered from)` steps.
+    fn simple_paths(
+        &self,
+        node: NodeIndex,
+        dst: NodeIndex,
+        visited: &mut Vec<NodeIndex>,
+        steps: &mut Vec<(EdgeIndex, NodeIndex)>,
+        found: &mut Vec<Vec<(EdgeIndex, NodeIndex)>>,
+        limit: usize,
+    ) {
+        if found.len() >= limit {
+            return;
+        }
+        if node == dst {
+            found.push(steps.clone());
+            return;
+        }
+        for edge in self.g.edges(node).filter(|e| e.weight().enabled) {
+            let next = if edge.source() == node { edge.target() } else { edge.source() };
+            if visited.contains(&next) {
+                continue;
+            }
+            visited.push(next);
+            steps.push((edge.id(), node));
+            self.simple_paths(next, dst, visited, steps, found, limit);
+            steps.pop();
+            visited.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+    use crate::{se3, Error, TfGraph};
+
+    #[test]
+    fn outliers() {
+        let mut g = TfGraph::new();
+        g.set_allow_cycles(true).unwrap();
+        // Two squares sharing the edge b -> c, with diagonals b -> f and a -> e along the bottom.
+        let tf = |x: f64, y: f64| se3::from_array(&[x, y, 0.0]).unwrap();
+        let edges = [
+            ("a", "b", tf(1.0, 0.0)), ("b", "c", tf(0.0, 1.0)), ("a", "d", tf(0.0, 1.0)), ("d", "c", tf(1.0, 0.0)),
+            ("b", "e", tf(1.0, 0.0)), ("e", "f", tf(0.0, 1.0)), ("c", "f", tf(1.0, 0.0)),
+            ("b", "f", tf(1.0, 1.0)), ("a", "e", tf(2.0, 0.0)),
+        ];
+        for (from, to, tf) in edges {
+            g.add_tf(from.to_owned(), to.to_owned(), tf).unwrap();
+        }
+        assert!(g.detect_outliers(0.01, 0.01).is_empty());
+
+        // A bad measurement of e -> f.
+        g.add_tf("e".to_owned(), "f".to_owned(), tf(0.0, 1.2)).unwrap();
+        let outliers = g.detect_outliers(0.01, 0.01);
+        assert_eq!((outliers[0].from, outliers[0].to), ("e", "f"));
+        assert!(outliers[1..].iter().all(|o| o.reduction < outliers[0].reduction));
+        assert!(g.detect_outliers(0.5, 0.5).is_empty());
+    }
+
+    #[test]
+    fn all_paths() {
+        let mut g = TfGraph::new();
+        let ab = se3::from_array(&[1.0, 0.0, 0.0]).unwrap();
+        let bc = se3::from_array(&[0.0, 1.0, 0.0]).unwrap();
+        let ac = se3::from_array(&[1.0, 1.1, 0.0]).unwrap();
+        g.add_tf("a".to_owned(), "b".to_owned(), ab).unwrap();
+        g.add_tf("b".to_owned(), "c".to_owned(), bc).unwrap();
+        assert!(matches!(g.add_tf("a".to_owned(), "c".to_owned(), ac), Err(Error::Cycle)));
+        g.set_allow_cycles(true).unwrap();
+        g.add_tf("a".to_owned(), "c".to_owned(), ac).unwrap();
+        // Parallel edge
+        g.add_tf("c".to_owned(), "a".to_owned(), ac.inverse()).unwrap();
+        assert!(matches!(g.add_tf("a".to_owned(), "a".to_owned(), ab), Err(Error::SelfLoop)));
+
+        let all = g.query_all_paths("a", "c", 10).unwrap();
+        assert_eq!(all.paths.len(), 3);
+        assert_eq!(all.paths[0].1, ["a", "c"]);
+        assert_eq!(all.paths[2].1, ["a", "b", "c"]);
+        assert_relative_eq!(all.paths[2].0, bc * ab);
+        assert_eq!(all.disagreements.len(), 3);
+        assert_relative_eq!(all.disagreements[0].translation, 0.0, epsilon = 1e-12);
+        assert_relative_eq!(all.disagreements[1].translation, 0.1, epsilon = 1e-12);
+        let mean = all.mean_disagreement();
+        assert!(mean[2].0 > mean[0].0);
+
+        assert_eq!(g.query_all_paths("a", "c", 1).unwrap().paths.len(), 1);
+
+        assert_eq!(g.query_tf("a", "c").unwrap().1, ["a", "c"]);
+        g.set_cost("a", "c", 2.5).unwrap();
+        assert_eq!(g.query_tf("a", "c").unwrap().1, ["a", "b", "c"]);
+        assert!(g.set_cost("a", "c", -1.0).is_none());
+        assert!(g.set_allow_cycles(false).is_none());
+    }
+}
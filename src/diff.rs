@@ -0,0 +1,245 @@
+//! Differences between the transforms of two graphs, and patches applying them.
+
+use std::fmt;
+
+use petgraph::{graph::EdgeIndex, visit::EdgeRef};
+
+use crate::{EdgeView, Frame, TransformEdge, TransformGraph};
+#[cfg(feature = "json")]
+use crate::se3::SE3;
+
+/// A stored transform edge with its state, owning its frame names.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransformRecord<E> {
+    pub from: String,
+    pub to: String,
+    pub tf: E,
+    pub enabled: bool,
+    pub cost: f64,
+    pub calibrated: Option<u64>,
+}
+
+/// A difference between two graphs, keyed by the stored direction `from -> to` of an edge.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change<E> {
+    Added(TransformRecord<E>),
+    Removed { from: String, to: String },
+    /// The transform, enabled state, cost or calibration time changed. Holds the new edge.
+    Updated(TransformRecord<E>),
+}
+
+impl<N: Frame, E: TransformEdge + PartialEq + Clone> TransformGraph<N, E> {
+    /// The changes turning the transforms of `self` into those of `other`: removals first, then
+    /// updates and additions in the order of `other`.
+    ///
+    /// Frames without transforms and graph settings are not compared.
+    pub fn diff(&self, other: &Self) -> Vec<Change<E>> {
+        let mut changes: Vec<_> = self.edges()
+            .filter(|e| other.stored_edge(e.from, e.to).is_none())
+            .map(|e| Change::Removed { from: e.from.to_owned(), to: e.to.to_owned() })
+            .collect();
+        for e in other.edges() {
+            let record = TransformRecord {
+                from: e.from.to_owned(),
+                to: e.to.to_owned(),
+                tf: e.tf.clone(),
+                enabled: e.enabled,
+                cost: e.cost,
+                calibrated: e.calibrated,
+            };
+            match self.stored_edge(e.from, e.to) {
+                None => changes.push(Change::Added(record)),
+                Some(old) if (old.tf, old.enabled, old.cost, old.calibrated) != (e.tf, e.enabled, e.cost, e.calibrated) => {
+                    changes.push(Change::Updated(record));
+                }
+                Some(_) => {}
+            }
+        }
+        changes
+    }
+
+    /// The first edge stored as `from -> to`.
+    fn stored_edge(&self, from: &str, to: &str) -> Option<EdgeView<'_, E>> {
+        self.edges().find(|e| (e.from, e.to) == (from, to))
+    }
+}
+
+/// A patch that does not apply to a graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct PatchConflict {
+    /// Position of the failing change in the patch.
+    pub index: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for PatchConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "change {}: {}", self.index + 1, self.reason)
+    }
+}
+
+impl std::error::Error for PatchConflict {}
+
+impl<N: Frame + From<String> + Clone, E: TransformEdge + Clone> TransformGraph<N, E> {
+    /// Apply changes as returned by `diff`, in order: exactly the listed edges are added, removed or updated.
+    ///
+    /// Fails without changing the graph if an added edge already exists or would make the graph cyclic,
+    /// or if a removed or updated edge does not exist. Frames left without transforms are kept, and
+    /// updated transforms drop their timed samples, as with `add_tf`. A frozen graph rejects the first change.
+    pub fn apply_patch(&mut self, patch: &[Change<E>]) -> Result<(), PatchConflict> {
+        if self.frozen && !patch.is_empty() {
+            return Err(PatchConflict { index: 0, reason: "graph is frozen" });
+        }
+        let mut g = self.clone();
+        for (index, change) in patch.iter().enumerate() {
+            g.apply_change(change).map_err(|reason| PatchConflict { index, reason })?;
+        }
+        *self = g;
+        Ok(())
+    }
+
+    fn apply_change(&mut self, change: &Change<E>) -> Result<(), &'static str> {
+        self.index = None;
+        match change {
+            Change::Added(r) => {
+                // In either direction, as `add_tf` would replace it.
+                if let (Some(a), Some(b)) = (self.find_node(&r.from), self.find_node(&r.to)) {
+                    if self.g.edges_connecting(a, b).next().is_some() {
+                        return Err("transform already exists");
+                    }
+                }
+                self.check_record(r)?;
+                let a = self.find_or_add_node(r.from.clone().into());
+                let b = self.find_or_add_node(r.to.clone().into());
+                let edge = self.add_tf_between(a, b, r.tf.clone()).ok_or("transform would make the graph cyclic")?;
+                // The transform may have been stored inverted, see `set_canonical_edges`.
+                let edge = &mut self.g[edge];
+                (edge.enabled, edge.cost, edge.calibrated) = (r.enabled, r.cost, r.calibrated);
+            }
+            Change::Removed { from, to } => {
+                let edge = self.stored_edge_index(from, to).ok_or("no such transform")?;
+                self.g.remove_edge(edge);
+            }
+            Change::Updated(r) => {
+                let edge = self.stored_edge_index(&r.from, &r.to).ok_or("no such transform")?;
+                self.check_record(r)?;
+                let edge = &mut self.g[edge];
+                (edge.tf, edge.enabled, edge.cost, edge.calibrated) = (r.tf.clone(), r.enabled, r.cost, r.calibrated);
+                edge.samples.poses.clear();
+            }
+        }
+        Ok(())
+    }
+
+    fn check_record(&self, r: &TransformRecord<E>) -> Result<(), &'static str> {
+        self.check_name(&r.from).and(self.check_name(&r.to)).map_err(|e| e.reason)?;
+        if r.cost.is_nan() || r.cost < 0.0 {
+            return Err("invalid cost");
+        }
+        Ok(())
+    }
+
+    fn stored_edge_index(&self, from: &str, to: &str) -> Option<EdgeIndex> {
+        let (a, b) = (self.find_node(from)?, self.find_node(to)?);
+        self.g.edges_connecting(a, b).map(|e| e.id()).find(|&e| self.g.edge_endpoints(e) == Some((a, b)))
+    }
+}
+
+/// Write a patch as JSON: `{"changes": [{"op": "add", "from": ..., "to": ..., "tf": [...]}, ...]}`, with
+/// `add`, `remove` and `update` operations. Transforms are stored as `[tx, ty, tz, qx, qy, qz, qw]`.
+#[cfg(feature = "json")]
+pub fn write_patch(patch: &[Change<SE3>], writer: &mut impl std::io::Write) -> serde_json::Result<()> {
+    crate::file::PatchFile::from_changes(patch).write_pretty(writer)
+}
+
+/// Read a patch written by `write_patch`.
+#[cfg(feature = "json")]
+pub fn read_patch(reader: &mut impl std::io::Read) -> serde_json::Result<Vec<Change<SE3>>> {
+    Ok(serde_json::from_reader::<_, crate::file::PatchFile>(reader)?.into_changes())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{se3, TfGraph};
+    use super::{Change, PatchConflict, TransformRecord};
+
+    #[test]
+    fn diff() {
+        let (ab, bc) = (se3::random(), se3::random());
+        let mut old = TfGraph::new();
+        old.add_tf("a".to_owned(), "b".to_owned(), ab).unwrap();
+        old.add_tf("b".to_owned(), "c".to_owned(), bc).unwrap();
+        old.add_tf("x".to_owned(), "y".to_owned(), bc).unwrap();
+
+        let mut new = TfGraph::new();
+        new.add_tf("a".to_owned(), "b".to_owned(), ab).unwrap();
+        new.add_tf("c".to_owned(), "b".to_owned(), bc).unwrap();
+        new.add_tf("x".to_owned(), "y".to_owned(), bc).unwrap();
+        new.set_enabled("x", "y", false).unwrap();
+
+        let changes = old.diff(&new);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0], Change::Removed { from: "b".to_owned(), to: "c".to_owned() });
+        assert!(matches!(&changes[1], Change::Added(r) if (r.from.as_str(), r.to.as_str()) == ("c", "b")));
+        assert!(matches!(&changes[2], Change::Updated(r) if r.from == "x" && !r.enabled));
+        assert!(new.diff(&new).is_empty());
+    }
+
+    #[test]
+    fn apply_patch() {
+        let mut old = TfGraph::new();
+        old.add_tf("a".to_owned(), "b".to_owned(), se3::random()).unwrap();
+        old.add_tf("b".to_owned(), "c".to_owned(), se3::random()).unwrap();
+        let mut new = TfGraph::new();
+        new.add_tf("c".to_owned(), "b".to_owned(), se3::random()).unwrap();
+        new.add_tf("a".to_owned(), "b".to_owned(), se3::random()).unwrap();
+        new.add_tf("b".to_owned(), "d".to_owned(), se3::random()).unwrap();
+        new.set_cost("a", "b", 2.0).unwrap();
+
+        let patch = old.diff(&new);
+        let mut patched = old.clone();
+        patched.apply_patch(&patch).unwrap();
+        assert!(patched.diff(&new).is_empty());
+
+        // Applied twice, the removal of b -> c fails first.
+        assert_eq!(patched.apply_patch(&patch), Err(PatchConflict { index: 0, reason: "no such transform" }));
+        assert!(patched.diff(&new).is_empty());
+
+        let cyclic = [Change::Added(TransformRecord { from: "d".to_owned(), to: "a".to_owned(), tf: se3::random(), enabled: true, cost: 1.0, calibrated: None })];
+        assert_eq!(patched.apply_patch(&cyclic).unwrap_err().reason, "transform would make the graph cyclic");
+        let reversed = [Change::Added(TransformRecord { from: "b".to_owned(), to: "a".to_owned(), tf: se3::random(), enabled: true, cost: 1.0, calibrated: None })];
+        assert_eq!(patched.apply_patch(&reversed).unwrap_err().reason, "transform already exists");
+        let ab = patched.query_tf("a", "b").unwrap().0;
+        patched.set_canonical_edges(true);
+        assert_eq!(patched.apply_patch(&reversed).unwrap_err().reason, "transform already exists");
+        assert_eq!(patched.query_tf("a", "b").unwrap().0, ab);
+        patched.set_canonical_edges(false);
+
+        // Updating a timed transform makes it static, as `add_tf` does.
+        patched.add_tf_at("a".to_owned(), "b".to_owned(), se3::random(), 1.0).unwrap();
+        let tf = se3::random();
+        let update = [Change::Updated(TransformRecord { from: "a".to_owned(), to: "b".to_owned(), tf, enabled: true, cost: 1.0, calibrated: None })];
+        patched.apply_patch(&update).unwrap();
+        assert_eq!(patched.is_dynamic("a", "b"), Some(false));
+        assert_eq!(patched.query_tf_at("a", "b", 5.0).unwrap().0, tf);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn patch_json() {
+        let mut new = TfGraph::new();
+        // Exactly representable, so that the round trip is lossless.
+        new.add_tf("a".to_owned(), "b".to_owned(), se3::from_array(&[1.0, 2.0, 3.0]).unwrap()).unwrap();
+        new.add_tf("b".to_owned(), "c".to_owned(), se3::from_array(&[0.0, 0.0, 1.0, 0.0]).unwrap()).unwrap();
+        new.set_enabled("b", "c", false).unwrap();
+        let mut patch = TfGraph::new().diff(&new);
+        patch.push(Change::Removed { from: "x".to_owned(), to: "y".to_owned() });
+
+        let mut buf = Vec::new();
+        super::write_patch(&patch, &mut buf).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        assert!(text.contains(r#""op": "remove""#));
+        assert_eq!(super::read_patch(&mut text.as_bytes()).unwrap(), patch);
+    }
+}
@@ -0,0 +1,374 @@
+//! Graphviz DOT export, and import of the graphs it writes after editing them in a graph tool.
+
+use std::io;
+
+use crate::{se3::{self, DisplaySE3, ParseLiteralError, To7, SE3}, Error, Frame, TfGraph, Warning};
+
+impl<N: Frame> TfGraph<N> {
+    /// Write the graph in the Graphviz DOT language, e.g. to render it with `dot -Tsvg`.
+    ///
+    /// Edges point the way transforms were stored, and are labeled as in `DisplaySE3`. The exact transform
+    /// is kept in a `tf` attribute, for `load_dot`. Roots are drawn as boxes, and disabled transforms dashed.
+    pub fn dump_dot(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writeln!(writer, "digraph tf {{")?;
+        let roots: Vec<_> = self.roots().collect();
+        for frame in self.nodes() {
+            let shape = if roots.contains(&frame) { " [shape=box]" } else { "" };
+            writeln!(writer, "  {}{shape};", dot_id(frame))?;
+        }
+        for e in self.edges() {
+            let label = dot_id(&DisplaySE3(e.tf).to_string());
+            let tf = dot_id(&e.tf.to7().map(|x| x.to_string()).join(", "));
+            let style = if e.enabled { "" } else { ", style=dashed" };
+            // Drawn thicker, and the attribute is ignored when loading, like the samples themselves.
+            let dynamic = if e.dynamic { ", penwidth=2, dynamic=true" } else { "" };
+            writeln!(writer, "  {} -> {} [label={label}, tf={tf}{style}{dynamic}];", dot_id(e.from), dot_id(e.to))?;
+        }
+        writeln!(writer, "}}")
+    }
+}
+
+impl<N: Frame + From<String> + Clone> TfGraph<N> {
+    /// Replace the frames and transforms with those of a DOT graph, such as one written by `dump_dot`,
+    /// keeping the settings and revisions.
+    ///
+    /// Each edge needs a `tf` attribute in any form `se3::Literal` accepts, in the graph's edge convention.
+    /// Nodes with `shape=box` become roots, and edges with `style=dashed` are disabled. Other attributes
+    /// are ignored, and subgraphs, ports and chains like `a -> b -> c` are rejected. The graph is kept as
+    /// it was if loading fails.
+    pub fn load_dot(&mut self, reader: &mut impl io::Read) -> Result<(), Error> {
+        if self.frozen {
+            return Err(Error::Frozen);
+        }
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let dot = parse(&text)?;
+
+        let mut g = self.clone();
+        g.reset();
+        for (name, _) in &dot.nodes {
+            g.check_name(name)?;
+            g.find_or_add_node(N::from(name.clone()));
+        }
+        for edge in &dot.edges {
+            if edge.renormalized {
+                g.warn(Warning::Renormalized { from: edge.from.clone(), to: edge.to.clone() });
+            }
+            g.add_tf(N::from(edge.from.clone()), N::from(edge.to.clone()), edge.tf)?;
+            if !edge.enabled {
+                g.set_enabled(&edge.from, &edge.to, false);
+            }
+        }
+        for (name, _) in dot.nodes.iter().filter(|(_, root)| *root) {
+            g.set_root(name);
+        }
+        *self = g;
+        Ok(())
+    }
+}
+
+/// A quoted DOT identifier.
+fn dot_id(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[derive(Debug)]
+struct DotGraph {
+    /// Names in order of appearance, and whether each is a root.
+    nodes: Vec<(String, bool)>,
+    edges: Vec<DotEdge>,
+}
+
+#[derive(Debug)]
+struct DotEdge {
+    from: String,
+    to: String,
+    tf: SE3,
+    enabled: bool,
+    /// The rotation of `tf` was given as a quaternion that was not of unit length.
+    renormalized: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A name or number, which may be a keyword.
+    Id(String),
+    /// A quoted string, never a keyword.
+    Quoted(String),
+    /// `->` or `--`.
+    Edge,
+    Punct(char),
+}
+
+/// Split DOT source into tokens with their line numbers, skipping comments.
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, Error> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '#' => while chars.next_if(|&c| c != '\n').is_some() {},
+            '/' if chars.next_if_eq(&'/').is_some() => while chars.next_if(|&c| c != '\n').is_some() {},
+            '/' if chars.next_if_eq(&'*').is_some() => {
+                let mut prev = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if prev == '*' => break,
+                        Some(c) => {
+                            line += usize::from(c == '\n');
+                            prev = c;
+                        }
+                        None => return Err(parse_error(line, "unterminated comment")),
+                    }
+                }
+            }
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if chars.peek().is_some_and(|&c| c == '"' || c == '\\') => s.extend(chars.next()),
+                        Some(c) => {
+                            line += usize::from(c == '\n');
+                            s.push(c);
+                        }
+                        None => return Err(parse_error(line, "unterminated string")),
+                    }
+                }
+                tokens.push((line, Token::Quoted(s)));
+            }
+            '-' if chars.next_if(|&c| c == '>' || c == '-').is_some() => tokens.push((line, Token::Edge)),
+            '{' | '}' | '[' | ']' | ';' | ',' | '=' => tokens.push((line, Token::Punct(c))),
+            c if c.is_alphanumeric() || matches!(c, '_' | '.' | '-') || !c.is_ascii() => {
+                let mut s = c.to_string();
+                while let Some(c) = chars.next_if(|&c| c.is_alphanumeric() || matches!(c, '_' | '.') || !c.is_ascii()) {
+                    s.push(c);
+                }
+                tokens.push((line, Token::Id(s)));
+            }
+            c => return Err(parse_error(line, &format!("unexpected {c:?}"))),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Tokens being parsed, and the line of the last one taken.
+struct Tokens {
+    tokens: std::iter::Peekable<std::vec::IntoIter<(usize, Token)>>,
+    line: usize,
+}
+
+impl Tokens {
+    fn next(&mut self) -> Option<Token> {
+        let (line, token) = self.tokens.next()?;
+        self.line = line;
+        Some(token)
+    }
+
+    fn peek(&mut self) -> Option<&Token> {
+        self.tokens.peek().map(|(_, t)| t)
+    }
+
+    fn error(&self, message: &str) -> Error {
+        parse_error(self.line, message)
+    }
+
+    /// A node name, or a name or value that is not a keyword.
+    fn id(&mut self) -> Option<String> {
+        match self.next()? {
+            Token::Id(s) | Token::Quoted(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Any number of attribute lists, like `[a=1, b=2][c=3]`, as key and value pairs.
+    fn attributes(&mut self) -> Result<Vec<(String, String)>, Error> {
+        let mut attrs = Vec::new();
+        while self.peek() == Some(&Token::Punct('[')) {
+            self.next();
+            loop {
+                let key = match self.next() {
+                    Some(Token::Punct(']')) => break,
+                    Some(Token::Punct(',' | ';')) => continue,
+                    Some(Token::Id(s) | Token::Quoted(s)) => s,
+                    Some(_) => return Err(self.error("expected an attribute")),
+                    None => return Err(self.error("expected ]")),
+                };
+                if self.next() != Some(Token::Punct('=')) {
+                    return Err(self.error(&format!("expected a value for {key}")));
+                }
+                let value = self.id().ok_or_else(|| self.error(&format!("expected a value for {key}")))?;
+                attrs.push((key, value));
+            }
+        }
+        Ok(attrs)
+    }
+}
+
+fn is_keyword(token: &Token, keyword: &str) -> bool {
+    matches!(token, Token::Id(s) if s.eq_ignore_ascii_case(keyword))
+}
+
+/// Parse the subset of DOT that `load_dot` accepts.
+fn parse(text: &str) -> Result<DotGraph, Error> {
+    let mut tokens = Tokens { tokens: tokenize(text)?.into_iter().peekable(), line: 1 };
+    let mut header = tokens.next();
+    if header.as_ref().is_some_and(|t| is_keyword(t, "strict")) {
+        header = tokens.next();
+    }
+    if !header.is_some_and(|t| is_keyword(&t, "digraph") || is_keyword(&t, "graph")) {
+        return Err(tokens.error("expected digraph or graph"));
+    }
+    if matches!(tokens.peek(), Some(Token::Id(_) | Token::Quoted(_))) {
+        tokens.next();
+    }
+    if tokens.next() != Some(Token::Punct('{')) {
+        return Err(tokens.error("expected {"));
+    }
+
+    let mut graph = DotGraph { nodes: Vec::new(), edges: Vec::new() };
+    loop {
+        let name = match tokens.next() {
+            Some(Token::Punct('}')) => break,
+            Some(Token::Punct(';')) => continue,
+            Some(t) if is_keyword(&t, "subgraph") => return Err(tokens.error("subgraphs are not supported")),
+            Some(t) if ["graph", "node", "edge"].iter().any(|k| is_keyword(&t, k)) => {
+                tokens.attributes()?;
+                continue;
+            }
+            Some(Token::Id(s) | Token::Quoted(s)) => s,
+            Some(Token::Punct('{')) => return Err(tokens.error("subgraphs are not supported")),
+            Some(_) => return Err(tokens.error("expected a node")),
+            None => return Err(tokens.error("expected }")),
+        };
+        match tokens.peek() {
+            // A graph attribute, such as `rankdir=LR`
+            Some(Token::Punct('=')) => {
+                tokens.next();
+                tokens.id().ok_or_else(|| tokens.error(&format!("expected a value for {name}")))?;
+            }
+            Some(Token::Edge) => {
+                tokens.next();
+                if tokens.peek() == Some(&Token::Punct('{')) {
+                    return Err(tokens.error("subgraphs are not supported"));
+                }
+                let to = tokens.id().ok_or_else(|| tokens.error("expected a node"))?;
+                if tokens.peek() == Some(&Token::Edge) {
+                    return Err(tokens.error("chains of edges are not supported"));
+                }
+                let line = tokens.line;
+                let attrs = tokens.attributes()?;
+                let attr = |key: &str| attrs.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+                let tf = attr("tf").ok_or_else(|| parse_error(line, "edge without a tf attribute"))?;
+                let values = se3::literal_values(tf).map_err(|e| parse_error(line, &format!("invalid tf: {e}")))?;
+                let renormalized = se3::normalizes_rotation(&values);
+                let tf = se3::from_array(&values)
+                    .ok_or_else(|| parse_error(line, &format!("invalid tf: {}", ParseLiteralError::Invalid)))?;
+                let enabled = attr("style") != Some("dashed");
+                for name in [&name, &to] {
+                    add_node(&mut graph, name, false);
+                }
+                graph.edges.push(DotEdge { from: name, to, tf, enabled, renormalized });
+            }
+            _ => {
+                let attrs = tokens.attributes()?;
+                let root = attrs.iter().rev().find(|(k, _)| k == "shape").is_some_and(|(_, v)| v == "box");
+                add_node(&mut graph, &name, root);
+            }
+        }
+    }
+    Ok(graph)
+}
+
+/// Add a node, or mark an existing one as a root.
+fn add_node(graph: &mut DotGraph, name: &str, root: bool) {
+    match graph.nodes.iter_mut().find(|(n, _)| n == name) {
+        Some((_, was_root)) => *was_root |= root,
+        None => graph.nodes.push((name.to_owned(), root)),
+    }
+}
+
+fn parse_error(line: usize, message: &str) -> Error {
+    Error::Parse(format!("line {line}: {message}"))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{se3, Error, TfGraph, Warning};
+
+    #[test]
+    fn dump_dot() {
+        let mut g = TfGraph::new();
+        g.add_tf("cam".to_owned(), "base".to_owned(), se3::from_array(&[1.0, 2.0, 3.0]).unwrap()).unwrap();
+        g.add_tf("my \"imu\"".to_owned(), "base".to_owned(), se3::SE3::identity()).unwrap();
+        g.set_root("base").unwrap();
+        g.set_enabled("base", "my \"imu\"", false).unwrap();
+        g.add_tf_at("odom".to_owned(), "base".to_owned(), se3::SE3::identity(), 1.0).unwrap();
+        let mut buf = Vec::new();
+        g.dump_dot(&mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), [
+            "digraph tf {",
+            "  \"cam\";",
+            "  \"base\" [shape=box];",
+            "  \"my \\\"imu\\\"\";",
+            "  \"odom\";",
+            "  \"cam\" -> \"base\" [label=\"t=[1.00, 2.00, 3.00] rpy=[0.0°, 0.0°, 0.0°]\", tf=\"1, 2, 3, 0, 0, 0, 1\"];",
+            "  \"my \\\"imu\\\"\" -> \"base\" [label=\"t=[0.00, 0.00, 0.00] rpy=[0.0°, 0.0°, 0.0°]\", tf=\"0, 0, 0, 0, 0, 0, 1\", style=dashed];",
+            "  \"odom\" -> \"base\" [label=\"t=[0.00, 0.00, 0.00] rpy=[0.0°, 0.0°, 0.0°]\", tf=\"0, 0, 0, 0, 0, 0, 1\", penwidth=2, dynamic=true];",
+            "}\n",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn load_dot() {
+        let mut g = TfGraph::new();
+        g.add_tf("cam".to_owned(), "base".to_owned(), se3::random()).unwrap();
+        g.add_tf("imu \\ \"1\"".to_owned(), "base".to_owned(), se3::random()).unwrap();
+        g.add_tf("x".to_owned(), "y".to_owned(), se3::random()).unwrap();
+        g.set_root("base").unwrap();
+        g.set_enabled("imu \\ \"1\"", "base", false).unwrap();
+        let mut buf = Vec::new();
+        g.dump_dot(&mut buf).unwrap();
+
+        let mut loaded = TfGraph::new();
+        loaded.add_tf("old".to_owned(), "gone".to_owned(), se3::random()).unwrap();
+        loaded.load_dot(&mut buf.as_slice()).unwrap();
+        assert!(loaded.nodes().eq(g.nodes()));
+        assert!(loaded.edges().map(|e| (e.from, e.to, *e.tf, e.enabled)).eq(g.edges().map(|e| (e.from, e.to, *e.tf, e.enabled))));
+        assert!(loaded.roots().eq(["base"]));
+
+        // As edited by hand
+        let dot = r#"
+            /* Edited */ strict digraph {
+              rankdir = LR; node [shape=ellipse]
+              # A root
+              base [shape = box, color=red]
+              lidar -> base [tf="[0.5, 0, 1]"] [style=solid]
+              "cam" -> lidar [tf="0, 0, 0, 0, 0, 0, 1", label=x]; // Identity
+            }"#;
+        g.load_dot(&mut dot.as_bytes()).unwrap();
+        assert!(g.nodes().eq(["base", "lidar", "cam"]));
+        assert_eq!(g.query_tf("cam", "base").unwrap().0, se3::from_array(&[0.5, 0.0, 1.0]).unwrap());
+        assert!(g.roots().eq(["base"]));
+
+        g.set_collect_warnings(true);
+        g.load_dot(&mut "digraph { a -> b [tf=\"0, 0, 0, 0, 0, 0, 2\"] }".as_bytes()).unwrap();
+        assert_eq!(g.take_warnings(), [Warning::Renormalized { from: "a".to_owned(), to: "b".to_owned() }]);
+        assert_eq!(g.query_tf("a", "b").unwrap().0, se3::SE3::identity());
+
+        let parse_error = |dot: &str| match TfGraph::new().load_dot(&mut dot.as_bytes()) {
+            Err(Error::Parse(e)) => e,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(parse_error("digraph {\n a -> b\n}"), "line 2: edge without a tf attribute");
+        assert_eq!(parse_error("digraph { a -> b [tf=\"1, 2\"] }"), "line 1: invalid tf: expected 3, 4, 6, 7, 9, 12 or 16 values forming a valid transform");
+        assert_eq!(parse_error("digraph { a -> b -> c }"), "line 1: chains of edges are not supported");
+        assert_eq!(parse_error("digraph { subgraph s { a } }"), "line 1: subgraphs are not supported");
+        assert_eq!(parse_error("digraph { a [shape=box"), "line 1: expected ]");
+        assert_eq!(parse_error("graph \"unterminated {"), "line 1: unterminated string");
+        assert!(matches!(g.load_dot(&mut "digraph { a -> b [tf=\"1,2,3\"]; b -> a [tf=\"1,2,3\"] }".as_bytes()), Err(Error::Cycle)));
+        assert!(g.nodes().eq(["a", "b"]));
+    }
+}
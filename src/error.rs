@@ -0,0 +1,107 @@
+//! The error type of graph operations that can fail in more than one way.
+
+use std::{fmt, io};
+
+use crate::{AddError, InvalidName, QueryError};
+
+/// Why adding, querying or loading failed.
+///
+/// Methods with a single way to fail return `Option`, and some return a narrower error, such as
+/// `QueryError` or `AddError`; both convert into this with `?`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The graph is frozen.
+    Frozen,
+    InvalidName(InvalidName),
+    /// Both names refer to the same frame.
+    SelfLoop,
+    /// The transforms would make the graph cyclic, and cycles are forbidden.
+    Cycle,
+    /// There is no frame of this name.
+    UnknownFrame(String),
+    /// Both frames exist, but no path of enabled transforms connects them.
+    NotConnected,
+    /// Reading or writing failed.
+    Io(io::Error),
+    /// A file is not valid JSON, YAML or DOT, or not a graph.
+    Parse(String),
+    /// A timestamp is not finite.
+    InvalidTimestamp(f64),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Frozen => write!(f, "graph is frozen"),
+            Error::InvalidName(e) => e.fmt(f),
+            Error::SelfLoop => write!(f, "transform from a frame to itself"),
+            Error::Cycle => write!(f, "transforms would close a cycle"),
+            Error::UnknownFrame(frame) => write!(f, "unknown frame {frame:?}"),
+            Error::NotConnected => write!(f, "frames are not connected"),
+            Error::Io(e) => e.fmt(f),
+            Error::Parse(reason) => write!(f, "invalid graph file: {reason}"),
+            Error::InvalidTimestamp(stamp) => write!(f, "invalid timestamp {stamp}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::InvalidName(e) => Some(e),
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<InvalidName> for Error {
+    fn from(e: InvalidName) -> Self {
+        Error::InvalidName(e)
+    }
+}
+
+impl From<QueryError> for Error {
+    fn from(e: QueryError) -> Self {
+        match e {
+            QueryError::UnknownFrame(frame) => Error::UnknownFrame(frame),
+            QueryError::NotConnected => Error::NotConnected,
+        }
+    }
+}
+
+impl From<AddError> for Error {
+    fn from(e: AddError) -> Self {
+        match e {
+            AddError::Frozen => Error::Frozen,
+            AddError::InvalidName(e) => Error::InvalidName(e),
+            AddError::SelfLoop => Error::SelfLoop,
+            AddError::Cycle => Error::Cycle,
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        if e.is_io() {
+            Error::Io(e.into())
+        } else {
+            Error::Parse(e.to_string())
+        }
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl From<serde_yaml::Error> for Error {
+    fn from(e: serde_yaml::Error) -> Self {
+        Error::Parse(e.to_string())
+    }
+}
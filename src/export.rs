@@ -0,0 +1,65 @@
+//! Export of transforms for other tools.
+
+use std::io;
+
+use crate::{se3::To7, Frame, TfGraph};
+
+/// How `write_env` writes variables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnvStyle {
+    /// `NAME="value"` lines, as read by dotenv loaders and systemd's `EnvironmentFile`.
+    #[default]
+    DotEnv,
+    /// `export NAME="value"` lines, to be `source`d by a shell.
+    Shell,
+}
+
+/// Name of the variable holding the transform `from -> to`, e.g. `TF_BASE_TO_CAM`.
+///
+/// Letters are upper-cased, and other characters than ASCII letters and digits become `_`.
+pub fn env_var_name(from: &str, to: &str) -> String {
+    format!("TF_{from}_TO_{to}")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect()
+}
+
+impl<N: Frame> TfGraph<N> {
+    /// Write the transform from `root` to each frame reachable from it as an environment variable
+    /// (see `env_var_name`), with the value `"tx ty tz qx qy qz qw"`.
+    ///
+    /// Fails with `io::ErrorKind::NotFound` if `root` does not exist.
+    pub fn write_env(&self, root: &str, style: EnvStyle, writer: &mut impl io::Write) -> io::Result<()> {
+        let flat = self.flatten(root)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no frame {root}")))?;
+        let prefix = match style {
+            EnvStyle::DotEnv => "",
+            EnvStyle::Shell => "export ",
+        };
+        for (frame, tf) in &flat[1..] {
+            // Adding zero turns -0 into 0.
+            let value = tf.to7().map(|x| (x + 0.0).to_string()).join(" ");
+            writeln!(writer, "{prefix}{}=\"{value}\"", env_var_name(root, frame))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{se3, TfGraph};
+    use super::{env_var_name, EnvStyle};
+
+    #[test]
+    fn env() {
+        assert_eq!(env_var_name("base_link", "cam.front-1"), "TF_BASE_LINK_TO_CAM_FRONT_1");
+
+        let mut g = TfGraph::new();
+        g.add_tf("cam".to_owned(), "base".to_owned(), se3::from_array(&[1.0, 2.0, 3.0]).unwrap()).unwrap();
+        g.add_tf("x".to_owned(), "y".to_owned(), se3::random()).unwrap();
+        let mut buf = Vec::new();
+        g.write_env("base", EnvStyle::Shell, &mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "export TF_BASE_TO_CAM=\"-1 -2 -3 0 0 0 1\"\n");
+        assert!(g.write_env("z", EnvStyle::DotEnv, &mut Vec::new()).is_err());
+    }
+}
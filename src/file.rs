@@ -0,0 +1,356 @@
+//! Portable file format for transform graphs, and for patches.
+//!
+//! Unlike petgraph's own serialization, this does not depend on internal node/edge indices:
+//! a file is a list of frames and a list of `from -> to : tf` records, with the transform
+//! stored as `[tx, ty, tz, qx, qy, qz, qw]`.
+
+use std::{collections::BTreeMap, io, time::Duration};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use petgraph::graph::UnGraph;
+
+use crate::{diff::{self, Change}, revisions::Revision, se3::{self, SE3}, Edge, EdgeConvention, Error, Frame, Header, NameMatching, TfGraph, Warning};
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct GraphFile<N> {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    header: Option<Header>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    allow_cycles: bool,
+    /// Meaning of each `tf`. Older files always use the default.
+    #[serde(default, skip_serializing_if = "is_default_convention")]
+    convention: EdgeConvention,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    canonical_edges: bool,
+    #[serde(default, skip_serializing_if = "is_exact")]
+    name_matching: NameMatching,
+    /// Maximum age of calibrations in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_age: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    roots: Vec<String>,
+    /// Frames of each group.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    groups: BTreeMap<String, Vec<String>>,
+    frames: Vec<N>,
+    transforms: Vec<TransformRecord>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    revisions: Vec<RevisionRecord>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RevisionRecord {
+    message: String,
+    time: u64,
+    transforms: Vec<TransformRecord>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TransformRecord {
+    from: String,
+    to: String,
+    tf: StoredTf,
+    #[serde(default = "default_enabled", skip_serializing_if = "is_enabled")]
+    enabled: bool,
+    #[serde(default = "default_cost", skip_serializing_if = "is_default_cost")]
+    cost: f64,
+    /// Seconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    calibrated: Option<u64>,
+}
+
+/// A transform stored as `[tx, ty, tz, qx, qy, qz, qw]`, like `se3::compact`, remembering whether its
+/// rotation had to be normalized when read.
+#[derive(Clone, Copy)]
+struct StoredTf {
+    tf: SE3,
+    renormalized: bool,
+}
+
+impl From<SE3> for StoredTf {
+    fn from(tf: SE3) -> Self {
+        Self { tf, renormalized: false }
+    }
+}
+
+impl Serialize for StoredTf {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        se3::compact::serialize(&self.tf, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for StoredTf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let a = <[f64; 7]>::deserialize(deserializer)?;
+        let tf = se3::compact::checked(&a).map_err(de::Error::custom)?;
+        Ok(Self { tf, renormalized: se3::normalizes_rotation(&a) })
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn is_enabled(enabled: &bool) -> bool {
+    *enabled
+}
+
+fn is_default_convention(convention: &EdgeConvention) -> bool {
+    *convention == EdgeConvention::default()
+}
+
+fn is_exact(matching: &NameMatching) -> bool {
+    *matching == NameMatching::EXACT
+}
+
+fn default_cost() -> f64 {
+    1.0
+}
+
+fn is_default_cost(cost: &f64) -> bool {
+    *cost == 1.0
+}
+
+impl<'a, N: Frame> GraphFile<&'a N> {
+    pub fn from_graph(g: &'a TfGraph<N>) -> Self {
+        Self {
+            header: g.header().cloned(),
+            allow_cycles: g.allows_cycles(),
+            convention: g.edge_convention(),
+            canonical_edges: g.canonical_edges(),
+            name_matching: g.name_matching(),
+            max_age: g.max_age().map(|d| d.as_secs()),
+            roots: g.roots().map(str::to_owned).collect(),
+            groups: g.groups().map(|group| (group.to_owned(), g.group(group).into_iter().map(str::to_owned).collect())).collect(),
+            frames: g.frames().collect(),
+            transforms: g.edges().map(|e| TransformRecord {
+                from: e.from.to_owned(),
+                to: e.to.to_owned(),
+                tf: (*e.tf).into(),
+                enabled: e.enabled,
+                cost: e.cost,
+                calibrated: e.calibrated,
+            }).collect(),
+            revisions: g.revisions().iter().map(|r| RevisionRecord {
+                message: r.message.clone(),
+                time: r.time,
+                transforms: r.transforms.iter().map(TransformRecord::from).collect(),
+            }).collect(),
+        }
+    }
+}
+
+impl<N: Frame> GraphFile<N> {
+    /// Fails if a transform, root or group refers to an unlisted frame, or if the transforms would form a
+    /// cycle while neither `allow_cycles` nor the file allows it.
+    ///
+    /// The graph collects warnings about transforms that were normalized or listed twice.
+    pub fn into_graph(self, allow_cycles: bool) -> Result<TfGraph<N>, Error> {
+        let mut g = TfGraph::default();
+        g.set_collect_warnings(true);
+        g.set_allow_cycles(allow_cycles || self.allow_cycles);
+        g.set_edge_convention(self.convention);
+        g.set_canonical_edges(self.canonical_edges);
+        g.set_name_matching(self.name_matching).expect("the graph has no frames yet");
+        g.set_max_age(self.max_age.map(Duration::from_secs));
+        for frame in self.frames {
+            g.find_or_add_node(frame);
+        }
+        let find = |g: &TfGraph<N>, name: &str| g.find_node(name).ok_or_else(|| Error::UnknownFrame(name.to_owned()));
+        for t in self.transforms {
+            let (a, b) = (find(&g, &t.from)?, find(&g, &t.to)?);
+            let edges = g.g.edge_count();
+            let edge = g.add_tf_between(a, b, t.tf.tf).ok_or(Error::Cycle)?;
+            if t.tf.renormalized {
+                g.warn(Warning::Renormalized { from: t.from.clone(), to: t.to.clone() });
+            }
+            if g.g.edge_count() == edges {
+                g.warn(Warning::Overwritten { from: t.from, to: t.to });
+            }
+            let edge = &mut g.g[edge];
+            edge.enabled = t.enabled;
+            edge.cost = t.cost;
+            edge.calibrated = t.calibrated;
+        }
+        g.header = self.header;
+        g.roots = self.roots.iter().map(|r| find(&g, r)).collect::<Result<_, _>>()?;
+        for (group, frames) in &self.groups {
+            g.groups.insert(group.clone(), frames.iter().map(|f| find(&g, f)).collect::<Result<_, _>>()?);
+        }
+        g.revisions = self.revisions.into_iter().map(|r| Revision {
+            message: r.message,
+            time: r.time,
+            transforms: r.transforms.into_iter().map(Into::into).collect(),
+        }).collect();
+        Ok(g)
+    }
+}
+
+/// Read a graph saved by older versions, which used petgraph's own serialization.
+pub(crate) fn from_legacy<N: Frame + serde::de::DeserializeOwned>(
+    value: serde_json::Value,
+    allow_cycles: bool,
+) -> serde_json::Result<TfGraph<N>> {
+    let (nodes, edges) = UnGraph::<N, SE3>::deserialize(value)?.into_nodes_edges();
+    let mut g = TfGraph { allow_cycles, ..Default::default() };
+    for node in nodes {
+        g.add_node(node.weight);
+    }
+    for edge in edges {
+        g.g.add_edge(edge.source(), edge.target(), Edge::new(edge.weight));
+    }
+    Ok(g)
+}
+
+impl<N: Serialize> GraphFile<N> {
+    pub fn write_pretty(&self, writer: &mut impl io::Write) -> serde_json::Result<()> {
+        write_pretty(self, writer)
+    }
+}
+
+/// A list of changes to the transforms of a graph.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PatchFile {
+    changes: Vec<PatchOp>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum PatchOp {
+    Add(TransformRecord),
+    Remove { from: String, to: String },
+    Update(TransformRecord),
+}
+
+impl From<&diff::TransformRecord<SE3>> for TransformRecord {
+    fn from(r: &diff::TransformRecord<SE3>) -> Self {
+        Self { from: r.from.clone(), to: r.to.clone(), tf: r.tf.into(), enabled: r.enabled, cost: r.cost, calibrated: r.calibrated }
+    }
+}
+
+impl From<TransformRecord> for diff::TransformRecord<SE3> {
+    fn from(r: TransformRecord) -> Self {
+        Self { from: r.from, to: r.to, tf: r.tf.tf, enabled: r.enabled, cost: r.cost, calibrated: r.calibrated }
+    }
+}
+
+impl PatchFile {
+    pub fn from_changes(changes: &[Change<SE3>]) -> Self {
+        let changes = changes.iter().map(|c| match c {
+            Change::Added(r) => PatchOp::Add(r.into()),
+            Change::Removed { from, to } => PatchOp::Remove { from: from.clone(), to: to.clone() },
+            Change::Updated(r) => PatchOp::Update(r.into()),
+        }).collect();
+        Self { changes }
+    }
+
+    pub fn into_changes(self) -> Vec<Change<SE3>> {
+        self.changes.into_iter().map(|op| match op {
+            PatchOp::Add(r) => Change::Added(r.into()),
+            PatchOp::Remove { from, to } => Change::Removed { from, to },
+            PatchOp::Update(r) => Change::Updated(r.into()),
+        }).collect()
+    }
+
+    pub fn write_pretty(&self, writer: &mut impl io::Write) -> serde_json::Result<()> {
+        write_pretty(self, writer)
+    }
+}
+
+fn write_pretty(value: &impl Serialize, writer: &mut impl io::Write) -> serde_json::Result<()> {
+    let mut ser = serde_json::Serializer::with_formatter(writer, Formatter::default());
+    value.serialize(&mut ser)
+}
+
+/// Pretty formatter that keeps arrays nested inside records (the transforms) on one line.
+#[derive(Default)]
+struct Formatter {
+    depth: usize,
+    inline_at: Option<usize>,
+    has_value: bool,
+}
+
+impl Formatter {
+    const INLINE_DEPTH: usize = 3;
+
+    fn begin<W: ?Sized + io::Write>(&mut self, w: &mut W, open: &[u8], inline: bool) -> io::Result<()> {
+        if inline && self.inline_at.is_none() {
+            self.inline_at = Some(self.depth);
+        }
+        self.depth += 1;
+        self.has_value = false;
+        w.write_all(open)
+    }
+
+    fn end<W: ?Sized + io::Write>(&mut self, w: &mut W, close: &[u8]) -> io::Result<()> {
+        self.depth -= 1;
+        if self.inline_at.is_none() && self.has_value {
+            self.newline(w)?;
+        }
+        if self.inline_at == Some(self.depth) {
+            self.inline_at = None;
+        }
+        self.has_value = true;
+        w.write_all(close)
+    }
+
+    fn item<W: ?Sized + io::Write>(&mut self, w: &mut W, first: bool) -> io::Result<()> {
+        if self.inline_at.is_some() {
+            return w.write_all(if first { b"" } else { b", " });
+        }
+        if !first {
+            w.write_all(b",")?;
+        }
+        self.newline(w)
+    }
+
+    fn newline<W: ?Sized + io::Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(b"\n")?;
+        for _ in 0..self.depth {
+            w.write_all(b"  ")?;
+        }
+        Ok(())
+    }
+}
+
+impl serde_json::ser::Formatter for Formatter {
+    fn begin_array<W: ?Sized + io::Write>(&mut self, w: &mut W) -> io::Result<()> {
+        let inline = self.depth >= Self::INLINE_DEPTH;
+        self.begin(w, b"[", inline)
+    }
+
+    fn end_array<W: ?Sized + io::Write>(&mut self, w: &mut W) -> io::Result<()> {
+        self.end(w, b"]")
+    }
+
+    fn begin_array_value<W: ?Sized + io::Write>(&mut self, w: &mut W, first: bool) -> io::Result<()> {
+        self.item(w, first)
+    }
+
+    fn end_array_value<W: ?Sized + io::Write>(&mut self, _w: &mut W) -> io::Result<()> {
+        self.has_value = true;
+        Ok(())
+    }
+
+    fn begin_object<W: ?Sized + io::Write>(&mut self, w: &mut W) -> io::Result<()> {
+        self.begin(w, b"{", false)
+    }
+
+    fn end_object<W: ?Sized + io::Write>(&mut self, w: &mut W) -> io::Result<()> {
+        self.end(w, b"}")
+    }
+
+    fn begin_object_key<W: ?Sized + io::Write>(&mut self, w: &mut W, first: bool) -> io::Result<()> {
+        self.item(w, first)
+    }
+
+    fn begin_object_value<W: ?Sized + io::Write>(&mut self, w: &mut W) -> io::Result<()> {
+        w.write_all(b": ")
+    }
+
+    fn end_object_value<W: ?Sized + io::Write>(&mut self, _w: &mut W) -> io::Result<()> {
+        self.has_value = true;
+        Ok(())
+    }
+}
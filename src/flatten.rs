@@ -0,0 +1,182 @@
+//! Whole-graph traversals: transforms from one frame to many or all others, and between all pairs.
+
+use std::{cmp::Ordering, collections::{BinaryHeap, HashMap, VecDeque}};
+
+use petgraph::{graph::{EdgeIndex, NodeIndex}, visit::EdgeRef};
+
+use crate::{Frame, TransformEdge, TransformGraph};
+
+impl<N: Frame, E: TransformEdge> TransformGraph<N, E> {
+    /// Transforms from `root` to every frame reachable from it, `root` itself included.
+    ///
+    /// Frames come in order of increasing path cost. Returns `None` if `root` does not exist.
+    pub fn flatten(&self, root: &str) -> Option<Vec<(&str, E)>> {
+        let root = self.find_node(root)?;
+        Some(self.flatten_from(root).into_iter().map(|(ix, tf)| (self.g[ix].name(), self.directed(tf))).collect())
+    }
+
+    /// Frames that can be queried from `frame`, i.e. connected to it by enabled transforms, nearest first.
+    /// `frame` itself is not included.
+    ///
+    /// Unlike `flatten`, no transforms are composed. Returns `None` if `frame` does not exist.
+    pub fn reachable_from(&self, frame: &str) -> Option<Vec<&str>> {
+        let root = self.find_node(frame)?;
+        let mut seen = vec![false; self.g.node_count()];
+        seen[root.index()] = true;
+        let mut queue = VecDeque::from([root]);
+        let mut out = Vec::new();
+        while let Some(node) = queue.pop_front() {
+            for e in self.g.edges(node).filter(|e| e.weight().enabled) {
+                let next = if e.source() == node { e.target() } else { e.source() };
+                if !seen[next.index()] {
+                    seen[next.index()] = true;
+                    out.push(self.g[next].name());
+                    queue.push_back(next);
+                }
+            }
+        }
+        Some(out)
+    }
+
+    /// Transforms from `src` to each of `dsts`, in the same order, with a single traversal of the graph.
+    ///
+    /// An entry is `None` if that frame does not exist or is not connected. Returns `None` if `src` does not exist.
+    pub fn query_many(&self, src: &str, dsts: &[&str]) -> Option<Vec<Option<E>>> where E: Clone {
+        let tree: HashMap<NodeIndex, E> = self.flatten_from(self.find_node(src)?).into_iter().collect();
+        Some(dsts.iter().map(|dst| tree.get(&self.find_node(dst)?).cloned().map(|tf| self.directed(tf))).collect())
+    }
+
+    /// Transforms between all ordered pairs of distinct, connected frames, as `(src, dst, tf)`.
+    ///
+    /// This is quadratic in the number of frames.
+    #[cfg(not(feature = "parallel"))]
+    pub fn all_pairs(&self) -> Vec<(&str, &str, E)> {
+        self.g.node_indices().flat_map(|src| self.pairs_from(src)).collect()
+    }
+
+    /// Transforms between all ordered pairs of distinct, connected frames, as `(src, dst, tf)`.
+    ///
+    /// This is quadratic in the number of frames. Sources are processed in parallel.
+    #[cfg(feature = "parallel")]
+    pub fn all_pairs(&self) -> Vec<(&str, &str, E)> where N: Sync, E: Send + Sync {
+        use rayon::prelude::*;
+
+        let sources: Vec<_> = self.g.node_indices().collect();
+        sources.into_par_iter().flat_map_iter(|src| self.pairs_from(src)).collect()
+    }
+
+    fn pairs_from(&self, src: NodeIndex) -> impl Iterator<Item = (&str, &str, E)> {
+        self.flatten_from(src).into_iter()
+            .filter(move |&(dst, _)| dst != src)
+            .map(move |(dst, tf)| (self.g[src].name(), self.g[dst].name(), self.directed(tf)))
+    }
+
+    /// Lowest-cost path tree over enabled edges, with the cumulative transform from `root` to
+    /// each node, in the `SourceToTarget` convention. Every node comes after its parent in the tree.
+    pub(crate) fn flatten_from(&self, root: NodeIndex) -> Vec<(NodeIndex, E)> {
+        self.path_tree(root, E::identity(), |edge, parent, tf| self.chain_edge(edge, parent, tf))
+    }
+
+    /// Like `flatten_from`, with `chain` composing the value of a frame from that of its parent in the tree.
+    pub(crate) fn path_tree<T>(&self, root: NodeIndex, identity: T, chain: impl Fn(EdgeIndex, NodeIndex, &T) -> T) -> Vec<(NodeIndex, T)> {
+        let mut settled: HashMap<NodeIndex, usize> = HashMap::new(); // node -> index in `out`
+        let mut out: Vec<(NodeIndex, T)> = Vec::new();
+        let mut identity = Some(identity);
+        let mut heap = BinaryHeap::from([Candidate { cost: 0.0, node: root, via: None }]);
+
+        while let Some(Candidate { cost, node, via }) = heap.pop() {
+            if settled.contains_key(&node) {
+                continue;
+            }
+            let tf = match via {
+                None => identity.take().expect("root is settled once"),
+                Some((edge, parent)) => chain(edge, parent, &out[settled[&parent]].1),
+            };
+            settled.insert(node, out.len());
+            out.push((node, tf));
+
+            for e in self.g.edges(node).filter(|e| e.weight().enabled) {
+                let next = if e.source() == node { e.target() } else { e.source() };
+                if !settled.contains_key(&next) {
+                    heap.push(Candidate { cost: cost + e.weight().cost, node: next, via: Some((e.id(), node)) });
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// Heap entry for Dijkstra's algorithm, ordered so that the lowest cost is popped first.
+struct Candidate {
+    cost: f64,
+    node: NodeIndex,
+    /// Edge and parent node this candidate is reached from.
+    via: Option<(EdgeIndex, NodeIndex)>,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+    use itertools::Itertools;
+    use crate::{se3, TfGraph};
+
+    #[test]
+    fn flatten() {
+        let mut g = TfGraph::new();
+        for i in 1..64 {
+            let parent = (i - 1) / 2;
+            g.add_tf(i.to_string(), parent.to_string(), se3::random()).unwrap();
+        }
+        g.add_tf("x".to_owned(), "y".to_owned(), se3::random()).unwrap();
+
+        let flat = g.flatten("5").unwrap();
+        assert_eq!(flat.len(), 64);
+        assert_eq!(flat[0].0, "5");
+        for (frame, tf) in &flat {
+            assert_relative_eq!(*tf, g.query_tf("5", frame).unwrap().0, epsilon = 1e-9);
+        }
+        assert!(g.flatten("z").is_none());
+
+        let many = g.query_many("5", &["40", "z", "5", "y"]).unwrap();
+        assert_relative_eq!(many[0].unwrap(), g.query_tf("5", "40").unwrap().0, epsilon = 1e-9);
+        assert_eq!(many[1..], [None, Some(se3::SE3::identity()), None]);
+        assert!(g.query_many("z", &["5"]).is_none());
+
+        let reachable = g.reachable_from("5").unwrap();
+        assert_eq!(reachable.len(), 63);
+        // Neighbors come first
+        assert_eq!(reachable[..3].iter().sorted().collect_vec(), [&"11", &"12", &"2"]);
+        assert_eq!(g.reachable_from("x").unwrap(), ["y"]);
+        g.set_enabled("x", "y", false).unwrap();
+        assert!(g.reachable_from("x").unwrap().is_empty());
+        g.set_enabled("x", "y", true).unwrap();
+        assert!(g.reachable_from("z").is_none());
+
+        let pairs = g.all_pairs();
+        assert_eq!(pairs.len(), 64 * 63 + 2);
+        for (src, dst, tf) in pairs.iter().step_by(97) {
+            assert_relative_eq!(*tf, g.query_tf(src, dst).unwrap().0, epsilon = 1e-9);
+        }
+    }
+}
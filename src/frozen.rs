@@ -0,0 +1,129 @@
+//! An immutable copy of a graph, prepared for fast queries from many threads.
+
+use std::collections::HashMap;
+
+use petgraph::graph::NodeIndex;
+
+use crate::{index::RouteIndex, se3::SE3, EdgeConvention, Frame, NameMatching, QueryError, TransformEdge, TransformGraph};
+
+/// A read-only snapshot of a `TransformGraph` that only answers queries.
+///
+/// Frames are looked up by hash, and the transform of each frame into the root of its spanning tree is
+/// precomputed, so a query only walks the path to list its frames and composes two transforms. Results
+/// equal those of `TransformGraph::query_tf` up to rounding. If the graph allows cycles, routes follow a
+/// spanning tree of the enabled transforms, ignoring costs.
+///
+/// There is no interior mutability: share it between threads with `Arc`, without locking.
+#[derive(Debug, Clone)]
+pub struct FrozenGraph<N = String, E = SE3> {
+    frames: Vec<N>,
+    /// Normalized name to index into `frames`.
+    lookup: HashMap<String, NodeIndex>,
+    names: NameMatching,
+    convention: EdgeConvention,
+    tree: RouteIndex,
+    /// Transform from each frame into the root of its tree, in the `SourceToTarget` convention.
+    to_root: Vec<E>,
+}
+
+pub type FrozenTfGraph<N = String> = FrozenGraph<N, SE3>;
+
+impl<N: Frame + Clone, E: TransformEdge + Clone> From<&TransformGraph<N, E>> for FrozenGraph<N, E> {
+    fn from(g: &TransformGraph<N, E>) -> Self {
+        let tree = RouteIndex::build(&g.g);
+        let mut to_root = vec![E::identity(); g.g.node_count()];
+        for node in tree.top_down() {
+            if let Some(parent) = tree.parent(node) {
+                let to_parent = g.chain_edge(g.first_enabled_edge(node, parent), node, &E::identity());
+                to_root[node.index()] = to_root[parent.index()].compose(&to_parent);
+            }
+        }
+        Self {
+            frames: g.g.node_weights().cloned().collect(),
+            lookup: g.lookup.clone(),
+            names: g.names,
+            convention: g.convention,
+            tree,
+            to_root,
+        }
+    }
+}
+
+impl<N: Frame, E: TransformEdge> FrozenGraph<N, E> {
+    /// Like `TransformGraph::query_tf`.
+    pub fn query_tf(&self, src: &str, dst: &str) -> Option<(E, Vec<&str>)> {
+        self.try_query_tf(src, dst).ok()
+    }
+
+    /// Like `TransformGraph::try_query_tf`.
+    pub fn try_query_tf(&self, src: &str, dst: &str) -> Result<(E, Vec<&str>), QueryError> {
+        let (a, b) = (self.find(src)?, self.find(dst)?);
+        if a == b {
+            return Ok((E::identity(), vec![self.frames[a.index()].name()]));
+        }
+        let path = self.tree.path(a, b).ok_or(QueryError::NotConnected)?;
+        // src -> root -> dst
+        let tf = self.to_root[b.index()].inverse().compose(&self.to_root[a.index()]);
+        let tf = match self.convention {
+            EdgeConvention::SourceToTarget => tf,
+            EdgeConvention::TargetToSource => tf.inverse(),
+        };
+        Ok((tf, path.into_iter().map(|ix| self.frames[ix.index()].name()).collect()))
+    }
+
+    /// The frames on the path `query_tf` would take.
+    pub fn query_path(&self, src: &str, dst: &str) -> Option<Vec<&str>> {
+        let path = self.tree.path(self.find(src).ok()?, self.find(dst).ok()?)?;
+        Some(path.into_iter().map(|ix| self.frames[ix.index()].name()).collect())
+    }
+
+    /// Names of all frames.
+    pub fn nodes(&self) -> impl Iterator<Item = &str> {
+        self.frames.iter().map(|n| n.name())
+    }
+
+    pub fn frame(&self, name: &str) -> Option<&N> {
+        self.find(name).ok().map(|ix| &self.frames[ix.index()])
+    }
+
+    pub fn edge_convention(&self) -> EdgeConvention {
+        self.convention
+    }
+
+    fn find(&self, name: &str) -> Result<NodeIndex, QueryError> {
+        self.lookup.get(self.names.normalize(name).as_ref()).copied().ok_or_else(|| QueryError::UnknownFrame(name.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+
+    use crate::{se3, EdgeConvention, NameMatching, QueryError, TfGraph};
+    use super::FrozenTfGraph;
+
+    #[test]
+    fn frozen() {
+        fn send_sync<T: Send + Sync>() {}
+        send_sync::<FrozenTfGraph>();
+
+        let mut g = TfGraph::new();
+        for i in 1..64 {
+            g.add_tf(i.to_string(), ((i - 1) / 2).to_string(), se3::random()).unwrap();
+        }
+        g.add_tf("x".to_owned(), "y".to_owned(), se3::random()).unwrap();
+        g.set_edge_convention(EdgeConvention::TargetToSource);
+        g.set_name_matching(NameMatching::NORMALIZED).unwrap();
+
+        let frozen = FrozenTfGraph::from(&g);
+        for (a, b) in [("0", "63"), ("40", "41"), ("17", "17"), ("50", "5"), ("y", " X ")] {
+            let (tf, path) = g.query_tf(a, b).unwrap();
+            let (frozen_tf, frozen_path) = frozen.query_tf(a, b).unwrap();
+            assert_relative_eq!(frozen_tf, tf, epsilon = 1e-9);
+            assert_eq!(frozen_path, path);
+        }
+        assert_eq!(frozen.try_query_tf("x", "0").unwrap_err(), QueryError::NotConnected);
+        assert_eq!(frozen.try_query_tf("x", "z").unwrap_err(), QueryError::UnknownFrame("z".to_owned()));
+        assert_eq!(frozen.nodes().count(), 66);
+    }
+}
@@ -0,0 +1,165 @@
+//! Named groups of frames, e.g. `cameras` or `left_arm`, and operations on whole groups.
+
+use petgraph::graph::NodeIndex;
+
+use crate::{Frame, TransformEdge, TransformGraph};
+
+impl<N: Frame, E: TransformEdge> TransformGraph<N, E> {
+    /// Add `frame` to `group`, creating the group if needed. A frame can be in any number of groups.
+    ///
+    /// Returns `None` if there is no such frame, or if the graph is frozen.
+    pub fn tag(&mut self, frame: &str, group: &str) -> Option<()> {
+        let node = self.find_node(frame)?;
+        if self.frozen {
+            return None;
+        }
+        let members = self.groups.entry(group.to_owned()).or_default();
+        if !members.contains(&node) {
+            members.push(node);
+        }
+        Some(())
+    }
+
+    /// Remove `frame` from `group`, dropping the group once it is empty.
+    ///
+    /// Returns `None` if the frame is not in the group, or if the graph is frozen.
+    pub fn untag(&mut self, frame: &str, group: &str) -> Option<()> {
+        let node = self.find_node(frame)?;
+        let members = self.groups.get_mut(group)?;
+        let i = members.iter().position(|&n| n == node)?;
+        if self.frozen {
+            return None;
+        }
+        members.remove(i);
+        if members.is_empty() {
+            self.groups.remove(group);
+        }
+        Some(())
+    }
+
+    /// Names of all groups, sorted.
+    pub fn groups(&self) -> impl Iterator<Item = &str> {
+        self.groups.keys().map(String::as_str)
+    }
+
+    /// Frames in `group`, in the order they were tagged. Empty if there is no such group.
+    pub fn group(&self, group: &str) -> Vec<&str> {
+        self.groups.get(group).map_or_else(Vec::new, |members| members.iter().map(|&n| self.g[n].name()).collect())
+    }
+
+    /// Groups `frame` is in, sorted.
+    pub fn tags(&self, frame: &str) -> Vec<&str> {
+        let Some(node) = self.find_node(frame) else {
+            return Vec::new();
+        };
+        self.groups.iter().filter(|(_, members)| members.contains(&node)).map(|(group, _)| group.as_str()).collect()
+    }
+
+    /// Remove the frames of `group` and all their transforms. Returns the number of frames removed, or
+    /// `None` if there is no such group, or if the graph is frozen.
+    pub fn remove_group(&mut self, group: &str) -> Option<usize> {
+        if self.frozen {
+            return None;
+        }
+        let members = self.groups.get(group)?.clone();
+        self.remove_nodes(members.clone());
+        Some(members.len())
+    }
+
+    /// Remove `nodes` and their edges, keeping roots and groups pointing at the right frames.
+    pub(crate) fn remove_nodes(&mut self, mut nodes: Vec<NodeIndex>) {
+        // Removing a node moves the last node into its index, so remove from the back.
+        nodes.sort_unstable_by(|a, b| b.cmp(a));
+        nodes.dedup();
+        for node in nodes {
+            let last = NodeIndex::new(self.g.node_count() - 1);
+            let removed = self.g.remove_node(node).expect("node exists");
+            let key = self.names.normalize(removed.name());
+            if self.lookup.get(key.as_ref()) == Some(&node) {
+                self.lookup.remove(key.as_ref());
+            }
+            if node != last {
+                let moved = self.names.normalize(self.g[node].name()).into_owned();
+                self.lookup.entry(moved).and_modify(|ix| if *ix == last { *ix = node });
+            }
+            let lists = std::iter::once(&mut self.roots).chain(self.groups.values_mut());
+            for list in lists {
+                list.retain(|&n| n != node);
+                list.iter_mut().filter(|n| **n == last).for_each(|n| *n = node);
+            }
+        }
+        self.groups.retain(|_, members| !members.is_empty());
+        self.index = None;
+    }
+}
+
+impl<N: Frame + Clone, E: TransformEdge + Clone> TransformGraph<N, E> {
+    /// A copy of the graph with only the frames of `group` and the transforms between them, and without
+    /// revisions. The copy is not frozen, even if this graph is. Returns `None` if there is no such group.
+    pub fn group_subgraph(&self, group: &str) -> Option<Self> {
+        let members = self.groups.get(group)?;
+        Some(self.subgraph_of(|n| members.contains(&n)))
+    }
+
+    /// Like `group_subgraph`, with the frames whose names `keep` accepts.
+    pub fn subgraph(&self, keep: impl Fn(&str) -> bool) -> Self {
+        self.subgraph_of(|n| keep(self.g[n].name()))
+    }
+
+    fn subgraph_of(&self, keep: impl Fn(NodeIndex) -> bool) -> Self {
+        let mut g = self.clone();
+        g.revisions.clear();
+        g.frozen = false;
+        g.remove_nodes(self.g.node_indices().filter(|&n| !keep(n)).collect());
+        g
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{se3, TfGraph};
+
+    #[test]
+    fn groups() {
+        let mut g = TfGraph::new();
+        g.add_tf("cam_left".to_owned(), "base".to_owned(), se3::random()).unwrap();
+        g.add_tf("cam_right".to_owned(), "base".to_owned(), se3::random()).unwrap();
+        g.add_tf("lidar".to_owned(), "base".to_owned(), se3::random()).unwrap();
+        g.add_tf("old_cam".to_owned(), "lidar".to_owned(), se3::random()).unwrap();
+        g.set_root("base").unwrap();
+        for frame in ["cam_left", "cam_right", "old_cam"] {
+            g.tag(frame, "cameras").unwrap();
+        }
+        g.tag("old_cam", "old").unwrap();
+        g.tag("cam_right", "old").unwrap();
+        g.tag("base", "old").unwrap();
+        g.tag("lidar", "sensors").unwrap();
+        assert!(g.tag("radar", "old").is_none());
+        assert!(g.groups().eq(["cameras", "old", "sensors"]));
+        assert_eq!(g.tags("old_cam"), ["cameras", "old"]);
+
+        g.untag("base", "old").unwrap();
+        assert!(g.untag("base", "old").is_none());
+        let sub = g.group_subgraph("cameras").unwrap();
+        assert_eq!(sub.nodes().count(), 3);
+        assert_eq!(sub.group("cameras"), ["cam_left", "cam_right", "old_cam"]);
+        assert_eq!(sub.edges().count(), 0);
+        let sub = g.subgraph(|name| name != "cam_right");
+        let mut frames: Vec<_> = sub.nodes().collect();
+        frames.sort();
+        assert_eq!(frames, ["base", "cam_left", "lidar", "old_cam"]);
+        assert_eq!(sub.edges().count(), 3);
+        let mut frozen = g.clone();
+        frozen.freeze();
+        assert!(!frozen.group_subgraph("cameras").unwrap().is_frozen());
+
+        assert_eq!(g.remove_group("old"), Some(2));
+        assert!(g.nodes().eq(["cam_left", "base", "lidar"]));
+        assert!(g.groups().eq(["cameras", "sensors"]));
+        assert_eq!(g.group("cameras"), ["cam_left"]);
+        assert_eq!(g.group("sensors"), ["lidar"]);
+        assert!(g.roots().eq(["base"]));
+        assert!(g.query_tf("cam_left", "lidar").is_some());
+        assert!(g.remove_group("old").is_none());
+    }
+}
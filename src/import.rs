@@ -0,0 +1,245 @@
+//! Import transforms from foreign JSON files, described by a field mapping.
+//!
+//! A mapping locates each component with a [JSON pointer](https://www.rfc-editor.org/rfc/rfc6901),
+//! e.g. a vendor file
+//! ```json
+//! {"extrinsics": [{"sensor": "cam", "reference": "imu",
+//!                  "translation": {"x": 1, "y": 0, "z": 0},
+//!                  "rotation": {"roll": 0, "pitch": 0, "yaw": 90}}]}
+//! ```
+//! is read with the mapping
+//! ```json
+//! {"records": "/extrinsics",
+//!  "from": {"field": "/sensor"}, "to": {"field": "/reference"},
+//!  "translation": ["/translation/x", "/translation/y", "/translation/z"],
+//!  "rotation": {"euler": ["/rotation/roll", "/rotation/pitch", "/rotation/yaw"], "degrees": true}}
+//! ```
+
+use std::fmt;
+
+use nalgebra as na;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{se3::{self, SE3}, Warning};
+
+/// Transforms read from a foreign file, as `(from, to, tf)`.
+pub type Imported = Vec<(String, String, SE3)>;
+
+/// Where to find each part of a transform in a foreign file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldMapping {
+    /// Pointer to an array of records. Without it, the whole document is a single record.
+    #[serde(default)]
+    pub records: Option<String>,
+    pub from: Name,
+    pub to: Name,
+    /// Pointers to `x, y, z`, relative to a record.
+    pub translation: [String; 3],
+    pub rotation: Rotation,
+    /// Multiply translations by this, e.g. `0.001` for millimeters.
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    /// Set if the file stores the `to -> from` transform, e.g. the pose of `to` in `from`.
+    #[serde(default)]
+    pub inverse: bool,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+/// A frame name, either fixed or read from a record.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Name {
+    Field { field: String },
+    Literal(String),
+}
+
+/// Rotation convention and pointers to its components, relative to a record.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Rotation {
+    /// Components `x, y, z, w`.
+    Quaternion { quaternion: [String; 4] },
+    /// Roll, pitch, yaw about the fixed x, y, z axes, applied in that order.
+    Euler {
+        euler: [String; 3],
+        #[serde(default)]
+        degrees: bool,
+    },
+    /// Axis scaled by the angle in radians.
+    RotationVector { rotation_vector: [String; 3] },
+    /// A 3x3 matrix, either as nested rows or 9 numbers in row-major order.
+    Matrix { matrix: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportError {
+    /// Nothing at this pointer.
+    Missing(String),
+    /// The value at this pointer has the wrong type.
+    Invalid(String),
+    /// `records` does not point to an array.
+    NotAnArray,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(ptr) => write!(f, "no value at {ptr}"),
+            Self::Invalid(ptr) => write!(f, "unexpected value at {ptr}"),
+            Self::NotAnArray => write!(f, "records is not an array"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl FieldMapping {
+    /// Read all transforms from `doc`, as `(from, to, tf)`.
+    pub fn import(&self, doc: &Value) -> Result<Imported, ImportError> {
+        self.import_with_warnings(doc).map(|(tfs, _)| tfs)
+    }
+
+    /// Like `import`, also returning a `Warning::Renormalized` for each quaternion that was not of unit
+    /// length.
+    pub fn import_with_warnings(&self, doc: &Value) -> Result<(Imported, Vec<Warning>), ImportError> {
+        let records = match &self.records {
+            Some(ptr) => get(doc, ptr)?
+                .as_array()
+                .ok_or(ImportError::NotAnArray)?
+                .iter()
+                .map(|r| self.record(r))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => vec![self.record(doc)?],
+        };
+        let warnings = records.iter()
+            .filter(|(.., renormalized)| *renormalized)
+            .map(|(from, to, ..)| Warning::Renormalized { from: from.clone(), to: to.clone() })
+            .collect();
+        Ok((records.into_iter().map(|(from, to, tf, _)| (from, to, tf)).collect(), warnings))
+    }
+
+    /// The transform of a record, and whether its rotation was normalized.
+    fn record(&self, r: &Value) -> Result<(String, String, SE3, bool), ImportError> {
+        let mut renormalized = false;
+        let [x, y, z] = self.translation.each_ref().map(|p| number(r, p));
+        let translation = na::Translation3::new(x?, y?, z?).vector * self.scale;
+        let rotation = match &self.rotation {
+            Rotation::Quaternion { quaternion } => {
+                let [x, y, z, w] = quaternion.each_ref().map(|p| number(r, p));
+                let q = na::Quaternion::new(w?, x?, y?, z?);
+                if q.norm() == 0.0 {
+                    return Err(ImportError::Invalid(quaternion[3].clone()));
+                }
+                renormalized = !se3::has_unit_rotation(&q);
+                na::UnitQuaternion::from_quaternion(q)
+            }
+            Rotation::Euler { euler, degrees } => {
+                let [roll, pitch, yaw] = euler.each_ref().map(|p| number(r, p));
+                let unit = if *degrees { 1f64.to_radians() } else { 1.0 };
+                na::UnitQuaternion::from_euler_angles(roll? * unit, pitch? * unit, yaw? * unit)
+            }
+            Rotation::RotationVector { rotation_vector } => {
+                let [x, y, z] = rotation_vector.each_ref().map(|p| number(r, p));
+                na::UnitQuaternion::from_scaled_axis(na::Vector3::new(x?, y?, z?))
+            }
+            Rotation::Matrix { matrix } => {
+                let flat: Option<Vec<f64>> = match get(r, matrix)? {
+                    Value::Array(rows) if rows.iter().all(Value::is_array) => rows
+                        .iter()
+                        .flat_map(|row| row.as_array().unwrap())
+                        .map(Value::as_f64)
+                        .collect(),
+                    Value::Array(values) => values.iter().map(Value::as_f64).collect(),
+                    _ => None,
+                };
+                let rot = flat
+                    .filter(|a| a.len() == 9)
+                    .and_then(|a| se3::from_array(&a))
+                    .ok_or_else(|| ImportError::Invalid(matrix.clone()))?;
+                rot.rotation
+            }
+        };
+
+        let tf = SE3::from_parts(translation.into(), rotation);
+        Ok((self.name(r, &self.from)?, self.name(r, &self.to)?, if self.inverse { tf.inverse() } else { tf }, renormalized))
+    }
+
+    fn name(&self, r: &Value, name: &Name) -> Result<String, ImportError> {
+        match name {
+            Name::Literal(s) => Ok(s.clone()),
+            Name::Field { field } => get(r, field)?
+                .as_str()
+                .map(str::to_owned)
+                .ok_or_else(|| ImportError::Invalid(field.clone())),
+        }
+    }
+}
+
+fn get<'v>(v: &'v Value, ptr: &str) -> Result<&'v Value, ImportError> {
+    v.pointer(ptr).ok_or_else(|| ImportError::Missing(ptr.to_owned()))
+}
+
+fn number(v: &Value, ptr: &str) -> Result<f64, ImportError> {
+    get(v, ptr)?.as_f64().ok_or_else(|| ImportError::Invalid(ptr.to_owned()))
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+    use serde_json::json;
+    use super::*;
+
+    #[test]
+    fn import() {
+        let doc = json!({"extrinsics": [
+            {"sensor": "cam", "reference": "imu",
+             "translation": {"x": 1, "y": 0, "z": 0},
+             "rotation": {"roll": 0, "pitch": 0, "yaw": 90}},
+            {"sensor": "lidar", "reference": "imu",
+             "translation": {"x": 0, "y": 2000, "z": 0},
+             "rotation": {"roll": 0, "pitch": 0, "yaw": 0}},
+        ]});
+        let mapping: FieldMapping = serde_json::from_value(json!({
+            "records": "/extrinsics",
+            "from": {"field": "/sensor"},
+            "to": {"field": "/reference"},
+            "translation": ["/translation/x", "/translation/y", "/translation/z"],
+            "rotation": {"euler": ["/rotation/roll", "/rotation/pitch", "/rotation/yaw"], "degrees": true},
+        })).unwrap();
+
+        let tfs = mapping.import(&doc).unwrap();
+        assert_eq!(tfs.len(), 2);
+        assert_eq!((tfs[0].0.as_str(), tfs[0].1.as_str()), ("cam", "imu"));
+        let p = tfs[0].2 * na::Point3::new(1.0, 0.0, 0.0);
+        assert_relative_eq!(p, na::Point3::new(1.0, 1.0, 0.0), epsilon = 1e-12);
+
+        let mapping = FieldMapping { scale: 0.001, inverse: true, ..mapping };
+        let tfs = mapping.import(&doc).unwrap();
+        assert_relative_eq!(tfs[1].2.translation.vector, na::Vector3::new(0.0, -2.0, 0.0));
+
+        let single: FieldMapping = serde_json::from_value(json!({
+            "from": "cam",
+            "to": "base",
+            "translation": ["/t/0", "/t/1", "/t/2"],
+            "rotation": {"matrix": "/R"},
+        })).unwrap();
+        let doc = json!({"t": [1, 2, 3], "R": [[0, -1, 0], [1, 0, 0], [0, 0, 1]]});
+        let tfs = single.import(&doc).unwrap();
+        assert_eq!(tfs[0].0, "cam");
+        assert_relative_eq!(tfs[0].2.rotation.angle(), std::f64::consts::FRAC_PI_2, epsilon = 1e-12);
+
+        assert_eq!(single.import_with_warnings(&doc).unwrap().1, []);
+        let quaternion = FieldMapping { rotation: Rotation::Quaternion { quaternion: ["/q/0", "/q/1", "/q/2", "/q/3"].map(str::to_owned) }, ..single.clone() };
+        let (tfs, warnings) = quaternion.import_with_warnings(&json!({"t": [1, 2, 3], "q": [0, 0, 0, 2]})).unwrap();
+        assert_eq!(tfs[0].2, se3::from_array(&[1.0, 2.0, 3.0]).unwrap());
+        assert_eq!(warnings, [Warning::Renormalized { from: "cam".to_owned(), to: "base".to_owned() }]);
+
+        assert_eq!(single.import(&json!({"t": [1, 2], "R": []})), Err(ImportError::Missing("/t/2".to_owned())));
+        assert_eq!(single.import(&json!({"t": [1, 2, 3], "R": [1, 2]})), Err(ImportError::Invalid("/R".to_owned())));
+    }
+}
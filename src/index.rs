@@ -0,0 +1,137 @@
+//! Precomputed spanning trees for answering repeated queries without path search.
+
+use std::collections::VecDeque;
+
+use petgraph::{graph::NodeIndex, visit::EdgeRef};
+
+use crate::{Frame, TransformEdge, TransformGraph};
+
+/// A rooted spanning tree per connected component, over enabled edges.
+#[derive(Debug, Clone)]
+pub(crate) struct RouteIndex {
+    /// Indexed by node.
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    root: NodeIndex,
+    depth: usize,
+    parent: Option<NodeIndex>,
+}
+
+impl RouteIndex {
+    pub(crate) fn memory_footprint(&self) -> usize {
+        self.entries.capacity() * std::mem::size_of::<Entry>()
+    }
+
+    pub(crate) fn build<N, E>(g: &crate::G<N, E>) -> Self {
+        let placeholder = Entry { root: NodeIndex::end(), depth: 0, parent: None };
+        let mut entries = vec![placeholder; g.node_count()];
+        let mut queue = VecDeque::new();
+
+        for root in g.node_indices() {
+            if entries[root.index()].root != NodeIndex::end() {
+                continue;
+            }
+            entries[root.index()] = Entry { root, depth: 0, parent: None };
+            queue.push_back(root);
+            while let Some(node) = queue.pop_front() {
+                let depth = entries[node.index()].depth + 1;
+                for e in g.edges(node).filter(|e| e.weight().enabled) {
+                    let next = if e.source() == node { e.target() } else { e.source() };
+                    if entries[next.index()].root == NodeIndex::end() {
+                        entries[next.index()] = Entry { root, depth, parent: Some(node) };
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        Self { entries }
+    }
+
+    /// Nodes ordered by depth, so that parents come before their children.
+    pub(crate) fn top_down(&self) -> Vec<NodeIndex> {
+        let mut nodes: Vec<_> = (0..self.entries.len()).map(NodeIndex::new).collect();
+        nodes.sort_by_key(|n| self.entries[n.index()].depth);
+        nodes
+    }
+
+    pub(crate) fn parent(&self, node: NodeIndex) -> Option<NodeIndex> {
+        self.entries[node.index()].parent
+    }
+
+    /// Path from `src` to `dst` through the tree, walking up to the lowest common ancestor.
+    pub(crate) fn path(&self, src: NodeIndex, dst: NodeIndex) -> Option<Vec<NodeIndex>> {
+        let (mut a, mut b) = (src, dst);
+        if self.entries[a.index()].root != self.entries[b.index()].root {
+            return None;
+        }
+        let mut up = vec![a]; // src -> ancestor
+        let mut down = vec![b]; // dst -> ancestor
+        while a != b {
+            let (ea, eb) = (self.entries[a.index()], self.entries[b.index()]);
+            if ea.depth >= eb.depth {
+                a = ea.parent.unwrap();
+                up.push(a);
+            } else {
+                b = eb.parent.unwrap();
+                down.push(b);
+            }
+        }
+        down.pop(); // common ancestor is in both
+        up.extend(down.into_iter().rev());
+        Some(up)
+    }
+}
+
+impl<N: Frame, E: TransformEdge> TransformGraph<N, E> {
+    /// Precompute a spanning tree of each connected component, so that `query_tf` and
+    /// `query_tf_bounded` become lookups in O(path length) without searching the graph.
+    ///
+    /// The index is dropped by any change to the transforms. Results are identical to unindexed
+    /// queries, since the same edges are composed. Returns `None` if cycles are allowed, as the
+    /// tree would not respect edge costs.
+    pub fn build_index(&mut self) -> Option<()> {
+        if self.allow_cycles {
+            return None;
+        }
+        self.index = Some(RouteIndex::build(&self.g));
+        Some(())
+    }
+
+    pub fn is_indexed(&self) -> bool {
+        self.index.is_some()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{se3, TfGraph};
+
+    #[test]
+    fn index() {
+        let mut g = TfGraph::new();
+        for i in 1..256 {
+            let parent = (i - 1) / 2;
+            g.add_tf(i.to_string(), parent.to_string(), se3::random()).unwrap();
+        }
+        g.add_tf("x".to_owned(), "y".to_owned(), se3::random()).unwrap();
+
+        let queries = [("0", "255"), ("200", "201"), ("17", "17"), ("130", "5"), ("x", "y"), ("y", "3")];
+        let expected: Vec<_> = queries.iter().map(|(a, b)| g.query_tf(a, b).map(|(tf, p)| (tf, p.join(",")))).collect();
+        g.build_index().unwrap();
+        assert!(g.is_indexed());
+        for ((a, b), exp) in queries.iter().zip(expected) {
+            assert_eq!(g.query_tf(a, b).map(|(tf, p)| (tf, p.join(","))), exp);
+        }
+
+        g.set_enabled("1", "0", false).unwrap();
+        assert!(!g.is_indexed());
+        g.build_index().unwrap();
+        assert!(g.query_tf("0", "255").is_none());
+        g.set_allow_cycles(true).unwrap();
+        assert!(g.build_index().is_none());
+    }
+}
@@ -1,125 +1,791 @@
+#[cfg(feature = "json")]
 use std::io;
+use std::{collections::{BTreeMap, HashMap}, time::Duration};
 
 use itertools::Itertools;
+use nalgebra::Point3;
 use petgraph::{
-    algo::{astar, is_cyclic_undirected}, graph::{NodeIndex, UnGraph}, visit::EdgeRef, Direction
+    algo::{astar, has_path_connecting, is_cyclic_undirected}, graph::{EdgeIndex, NodeIndex, UnGraph}, visit::{EdgeFiltered, EdgeRef}, Direction
 };
 use se3::SE3; // tuple_windows
+use trajectory::{Extrapolation, Interpolation, Trajectory};
+pub use transform::TransformEdge;
+pub use names::{validate_name, InvalidName, NameMatching, NameValidator};
+pub use bulk::AddError;
+pub use error::Error;
+pub use frozen::{FrozenGraph, FrozenTfGraph};
+pub use warning::Warning;
+#[cfg(feature = "json")]
+use serde::Deserialize;
 
 pub mod se3;
+pub mod transform;
+pub mod cycles;
+pub mod calib;
+pub mod diff;
+pub mod revisions;
+pub mod roots;
+pub mod trajectory;
+#[cfg(feature = "camera")]
+pub mod camera;
+pub mod report;
+pub mod export;
+#[cfg(feature = "mat")]
+pub mod mat;
+mod bulk;
+mod dot;
+mod flatten;
+mod frozen;
+mod groups;
+mod index;
+mod names;
+pub mod stats;
+pub mod error;
+pub mod warning;
+mod staleness;
+mod timed;
+#[cfg(feature = "json")]
+mod file;
+#[cfg(feature = "json")]
+pub mod import;
+#[cfg(feature = "tokio")]
+mod async_io;
+#[cfg(feature = "yaml")]
+mod yaml;
 
-#[derive(Debug, Default)]
-pub struct TfGraph {
-    g: G, // we might want to use GraphMap and HashMap<String, int> here.
-                             // To find a node, we have to iterate through all nodes. Or use some external map/set.
+/// A frame stored as a graph node. Frames are identified by their names.
+///
+/// Implement this for a custom struct to store per-frame data directly in the graph.
+pub trait Frame {
+    fn name(&self) -> &str;
 }
 
-type G = UnGraph<String, SE3>;
+impl Frame for String {
+    fn name(&self) -> &str {
+        self
+    }
+}
+
+/// A graph of frames (`N`) connected by transforms (`E`).
+#[derive(Debug, Clone)]
+pub struct TransformGraph<N = String, E = SE3> {
+    g: G<N, E>,
+    /// Normalized frame name to node, kept in sync with `g`.
+    lookup: HashMap<String, NodeIndex>,
+    allow_cycles: bool,
+    convention: EdgeConvention,
+    /// Store edges from the frame whose name sorts first, see `set_canonical_edges`.
+    canonical_edges: bool,
+    names: NameMatching,
+    validator: NameValidator,
+    /// Cleared on every change to the edges.
+    index: Option<index::RouteIndex>,
+    revisions: Vec<revisions::Revision<E>>,
+    /// Calibrations older than this are stale.
+    max_age: Option<Duration>,
+    /// How long timed samples are kept, see `set_buffer_duration`.
+    buffer_duration: Duration,
+    /// Lookup of timed samples, copied into every `Edge::samples`.
+    interpolation: Interpolation,
+    extrapolation: Extrapolation,
+    /// At most one per connected group of frames, unless groups were linked.
+    roots: Vec<NodeIndex>,
+    /// Named groups of frames, see `tag`.
+    groups: BTreeMap<String, Vec<NodeIndex>>,
+    header: Option<Header>,
+    frozen: bool,
+    /// Kept until taken, see `set_collect_warnings`.
+    warnings: Option<Vec<Warning>>,
+}
+
+/// What the transform of an edge `a -> b : T` means. Queries follow the same convention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, Deserialize), serde(rename_all = "snake_case"))]
+pub enum EdgeConvention {
+    /// `T` maps points expressed in `a` into `b`: `p_b = T * p_a`. Equivalently, `T` is the pose of `a` in `b`.
+    #[default]
+    SourceToTarget,
+    /// `T` maps points expressed in `b` into `a`: `p_a = T * p_b`. Equivalently, `T` is the pose of `b` in `a`,
+    /// as in tf2's `lookupTransform(a, b)`.
+    TargetToSource,
+}
+
+/// Graph of rigid transforms.
+pub type TfGraph<N = String> = TransformGraph<N, SE3>;
+
+type G<N, E> = UnGraph<N, Edge<E>>;
+
+/// Edge weight: the transform plus per-edge state.
+#[derive(Debug, Clone)]
+struct Edge<E> {
+    tf: E,
+    /// Disabled edges are kept, but skipped by path search.
+    enabled: bool,
+    /// Path search minimizes the total cost.
+    cost: f64,
+    /// Seconds since the Unix epoch.
+    calibrated: Option<u64>,
+    /// Timed values of `tf`, always in the `SourceToTarget` sense of the stored direction. Not saved.
+    samples: Trajectory,
+    /// Interpolation of `samples` chosen for this edge, instead of the graph's, see `set_edge_interpolation`.
+    interpolation: Option<Interpolation>,
+}
+
+impl<E> Edge<E> {
+    fn new(tf: E) -> Self {
+        Self { tf, enabled: true, cost: 1.0, calibrated: None, samples: Trajectory::default(), interpolation: None }
+    }
+}
+
+impl<N, E> Default for TransformGraph<N, E> {
+    fn default() -> Self {
+        Self { g: G::default(), lookup: HashMap::new(), allow_cycles: false, convention: EdgeConvention::default(), canonical_edges: false, names: NameMatching::default(), validator: validate_name, index: None, revisions: Vec::new(), max_age: None, buffer_duration: timed::DEFAULT_BUFFER_DURATION, interpolation: Interpolation::Linear, extrapolation: Extrapolation::Error, roots: Vec::new(), groups: BTreeMap::new(), header: None, frozen: false, warnings: None }
+    }
+}
 
 impl TfGraph {
-    /// Create an empty graph
+    /// Create an empty graph of named frames. Use `default()` for other frame types.
     pub fn new() -> Self {
         Self::default()
     }
+}
+
+impl<N: Frame, E: TransformEdge> TransformGraph<N, E> {
+    /// Allow or forbid cycles (default: forbidden).
+    ///
+    /// With cycles, transforms between two frames may be over-determined, and `query_tf` uses the
+    /// path with the lowest total cost (see `set_cost`). See `query_all_paths` for comparing the alternatives.
+    ///
+    /// Returns `None` when forbidding cycles on a graph that already has one.
+    pub fn set_allow_cycles(&mut self, allow: bool) -> Option<()> {
+        if self.frozen || (!allow && is_cyclic_undirected(&self.g)) {
+            return None;
+        }
+        self.allow_cycles = allow;
+        self.index = None;
+        Some(())
+    }
+
+    pub fn allows_cycles(&self) -> bool {
+        self.allow_cycles
+    }
+
+    /// Change the meaning of transforms added to and returned by the graph (default: `SourceToTarget`).
+    ///
+    /// Existing transforms are converted, so that queries give the same physical result in the new convention.
+    ///
+    /// Returns `None` if the graph is frozen.
+    pub fn set_edge_convention(&mut self, convention: EdgeConvention) -> Option<()> {
+        if self.frozen {
+            return None;
+        }
+        if convention != self.convention {
+            for e in self.g.edge_weights_mut() {
+                e.tf = e.tf.inverse();
+                e.samples.poses.iter_mut().for_each(|(_, tf)| *tf = tf.inverse());
+            }
+            self.convention = convention;
+        }
+        Some(())
+    }
+
+    pub fn edge_convention(&self) -> EdgeConvention {
+        self.convention
+    }
+
+    /// Store every transform from the frame whose name sorts first to the other, inverting transforms
+    /// added the other way round (default: off, transforms keep the direction they were added in).
+    ///
+    /// Saved files and diffs then do not depend on the direction transforms were entered in, and adding
+    /// `b -> a` updates an existing `a -> b`. Turning it on reorients existing transforms. Queries are not
+    /// affected. Returns `None` if the graph is frozen.
+    pub fn set_canonical_edges(&mut self, canonical: bool) -> Option<()> {
+        if self.frozen {
+            return None;
+        }
+        self.canonical_edges = canonical;
+        if canonical {
+            // Edges cannot be reversed in place; re-adding them in order keeps all indices.
+            let (nodes, edges) = std::mem::take(&mut self.g).into_nodes_edges();
+            for node in nodes {
+                self.g.add_node(node.weight);
+            }
+            for edge in edges {
+                let (a, b) = (edge.source(), edge.target());
+                let Edge { tf, enabled, cost, calibrated, mut samples, interpolation } = edge.weight;
+                let (a2, b2, tf) = self.oriented(a, b, tf);
+                if a2 != a {
+                    samples.poses.iter_mut().for_each(|(_, tf)| *tf = tf.inverse());
+                }
+                self.g.add_edge(a2, b2, Edge { tf, enabled, cost, calibrated, samples, interpolation });
+            }
+            self.index = None;
+        }
+        Some(())
+    }
+
+    pub fn canonical_edges(&self) -> bool {
+        self.canonical_edges
+    }
+
+    /// The edge `a -> b : tf` in the direction it is stored in.
+    fn oriented(&self, a: NodeIndex, b: NodeIndex, tf: E) -> (NodeIndex, NodeIndex, E) {
+        if self.canonical_edges && self.g[b].name() < self.g[a].name() {
+            (b, a, tf.inverse())
+        } else {
+            (a, b, tf)
+        }
+    }
+
+    /// Convert between `SourceToTarget` and the graph's convention, either way.
+    fn directed(&self, tf: E) -> E {
+        match self.convention {
+            EdgeConvention::SourceToTarget => tf,
+            EdgeConvention::TargetToSource => tf.inverse(),
+        }
+    }
 
     /// Add a transform edge to the graph.
     ///
-    /// Returns `None` if the new edge would make the graph cyclic. Self loops are cyclic.
+    /// Fails with `Error::Cycle` if the new edge would make the graph cyclic, unless cycles are allowed.
+    /// Self loops are always rejected, as are frame names refused by the name validator (see `check_name`),
+    /// and any change to a frozen graph.
     ///
-    /// Updates existing tf edge.
-    pub fn add_tf(&mut self, src: String, dst: String, tf: SE3) -> Option<()> {
-        // The invariant must hold: Graph is not cyclic.
-        debug_assert!(!is_cyclic_undirected(&self.g));
+    /// Updates existing tf edge, keeping its enabled state, and warns with `Warning::Overwritten`. If a frame with the same name already exists, the existing frame is kept.
+    /// Updating a timed transform (see `add_tf_at`) drops its samples, so that it is static again.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(src = src.name(), dst = dst.name())))]
+    pub fn add_tf(&mut self, src: N, dst: N, tf: E) -> Result<(), Error> {
+        let edges = self.g.edge_count();
+        let (a, edge) = self.add_tf_edge(src, dst, tf)?;
+        if self.g.edge_count() == edges {
+            self.g[edge].samples.poses.clear();
+            let (from, to) = self.edge_names(a, edge);
+            self.warn(Warning::Overwritten { from, to });
+        }
+        Ok(())
+    }
 
+    /// `add_tf`, returning the source node and the edge that holds the transform.
+    fn add_tf_edge(&mut self, src: N, dst: N, tf: E) -> Result<(NodeIndex, EdgeIndex), Error> {
+        if self.frozen {
+            return Err(Error::Frozen);
+        }
+        for name in [src.name(), dst.name()] {
+            if let Err(e) = self.check_name(name) {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("{e}");
+                return Err(e.into());
+            }
+        }
+        if self.names.matches(src.name(), dst.name()) {
+            return Err(Error::SelfLoop);
+        }
         let a = self.find_or_add_node(src);
         let b = self.find_or_add_node(dst);
+        self.add_tf_between(a, b, tf).map(|edge| (a, edge)).ok_or(Error::Cycle)
+    }
+
+    /// Whether `add_tf(src, dst, _)` would succeed, without changing the graph.
+    pub fn can_add_tf(&self, src: &str, dst: &str) -> bool {
+        if self.check_name(src).is_err() || self.check_name(dst).is_err() {
+            return false;
+        }
+        match (self.find_node(src), self.find_node(dst)) {
+            (Some(a), Some(b)) => self.can_add_between(a, b),
+            _ => !self.names.matches(src, dst),
+        }
+    }
+
+    fn can_add_between(&self, a: NodeIndex, b: NodeIndex) -> bool {
+        a != b
+            && (self.allow_cycles
+                // Updating an existing edge keeps the graph as it is.
+                || matches!(self.g.find_edge_undirected(a, b), Some((_, Direction::Outgoing)))
+                // Otherwise, the graph can only become cyclic if both nodes are already connected.
+                || !has_path_connecting(&self.g, a, b, None))
+    }
+
+    fn add_tf_between(&mut self, a: NodeIndex, b: NodeIndex, tf: E) -> Option<EdgeIndex> {
+        // The invariant must hold: Graph is not cyclic.
+        debug_assert!(self.allow_cycles || !is_cyclic_undirected(&self.g));
+        self.index = None;
+        let (a, b, tf) = self.oriented(a, b, tf);
+        if !self.can_add_between(a, b) {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("rejected cyclic transform");
+            return None;
+        }
 
         //let edge_new = self.g.update_edge(a, b, tf);
         // update_edge() will update b->a edge as well (for undirected). This is not what we want.
         let edge_new =
             if let Some((eid, Direction::Outgoing /* Only update if direction matches */)) = self.g.find_edge_undirected(a, b) {
-                self.g[eid] = tf;
+                self.g[eid].tf = tf;
                 eid
             } else {
-                self.g.add_edge(a, b, tf)
+                self.g.add_edge(a, b, Edge::new(tf))
             };
-        if is_cyclic_undirected(&self.g) {
-            // Graph can only become cyclic when both nodes are pre-existing.
-            // So we only need to delete the new edge.
-            self.g.remove_edge(edge_new);
-            return None;
+
+        Some(edge_new)
+    }
+
+    /// The transform from `src` to `dst`, composed along the cheapest path of enabled transforms, and
+    /// the frames on that path.
+    ///
+    /// A frame to itself (see `is_same_frame`) gives the identity and a path of that frame alone,
+    /// without searching the graph.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn query_tf(&self, src: &str, dst: &str) -> Option<(E, Vec<&str>)> {
+        let path_nodes = self.find_path(src, dst)?;
+        #[cfg(feature = "tracing")]
+        for (from, to, calibrated) in self.stale_edges(&path_nodes) {
+            tracing::warn!(calibrated, "stale calibration of {from} -> {to}");
         }
+        Some(self.compose_path(path_nodes))
+    }
 
-        Some(())
+    /// Like `query_tf`, but tells why no transform was found.
+    pub fn try_query_tf(&self, src: &str, dst: &str) -> Result<(E, Vec<&str>), QueryError> {
+        if let Some(frame) = [src, dst].into_iter().find(|f| self.find_node(f).is_none()) {
+            return Err(QueryError::UnknownFrame(frame.to_owned()));
+        }
+        self.query_tf(src, dst).ok_or(QueryError::NotConnected)
+    }
+
+    /// The frames on the path `query_tf` would take, without composing any transforms.
+    pub fn query_path(&self, src: &str, dst: &str) -> Option<Vec<&str>> {
+        let path_nodes = self.find_path(src, dst)?;
+        Some(path_nodes.into_iter().map(|ix| self.g[ix].name()).collect())
+    }
+
+    /// The transform of each hop on the path `query_tf` would take, as `(from, to, tf)`, oriented along the path.
+    ///
+    /// With the default `EdgeConvention`, the result of `query_tf` is the composition `tf_n * ... * tf_1`.
+    pub fn query_chain(&self, src: &str, dst: &str) -> Option<Vec<(&str, &str, E)>> {
+        let path_nodes = self.find_path(src, dst)?;
+        Some(path_nodes.iter().tuple_windows().map(|(&a, &b)| {
+            let tf = self.chain_edge(self.first_enabled_edge(a, b), a, &E::identity());
+            (self.g[a].name(), self.g[b].name(), self.directed(tf))
+        }).collect())
     }
 
-    pub fn query_tf(&self, src: &str, dst: &str) -> Option<(SE3, Vec<&str>)> {
-        let (Some(src), Some(dst)) = (self.find_node(src), self.find_node(dst)) else {
+    /// Like `query_tf`, but fails if the path has more than `max_hops` edges.
+    pub fn query_tf_bounded(&self, src: &str, dst: &str, max_hops: usize) -> Option<(E, Vec<&str>)> {
+        let path_nodes = self.find_path(src, dst)?;
+        if path_nodes.len() - 1 > max_hops {
             return None;
+        }
+        Some(self.compose_path(path_nodes))
+    }
+
+    /// Like `query_tf`, but never routes through any of the `avoid` frames.
+    ///
+    /// Fails if `src` or `dst` is to be avoided.
+    pub fn query_tf_avoiding(&self, src: &str, dst: &str, avoid: &[&str]) -> Option<(E, Vec<&str>)> {
+        let avoid: Vec<_> = avoid.iter().filter_map(|s| self.find_node(s)).collect();
+        let path_nodes = self.route(self.find_node(src)?, self.find_node(dst)?, |ix| !avoid.contains(&ix))?;
+        Some(self.compose_path(path_nodes))
+    }
+
+    /// Compose `src -> via` and `via -> dst`, forcing the chain through `via`.
+    ///
+    /// The returned path may visit frames twice if `via` is off the direct route.
+    pub fn query_tf_via(&self, src: &str, via: &str, dst: &str) -> Option<(E, Vec<&str>)> {
+        let (first, mut path) = self.query_tf(src, via)?;
+        let (second, path2) = self.query_tf(via, dst)?;
+        path.extend_from_slice(&path2[1..]);
+        let tf = match self.convention {
+            EdgeConvention::SourceToTarget => second.compose(&first),
+            EdgeConvention::TargetToSource => first.compose(&second),
         };
+        Some((tf, path))
+    }
+
+    fn find_path(&self, src: &str, dst: &str) -> Option<Vec<NodeIndex>> {
+        let (src, dst) = (self.find_node(src)?, self.find_node(dst)?);
+        // Fast path: a frame to itself needs no search.
+        if src == dst {
+            return Some(vec![src]);
+        }
+        match &self.index {
+            Some(index) => index.path(src, dst),
+            None => self.route(src, dst, |_| true),
+        }
+    }
+
+    /// Find a path over enabled edges, only visiting nodes for which `allow` is true.
+    fn route(&self, src: NodeIndex, dst: NodeIndex, allow: impl Fn(NodeIndex) -> bool) -> Option<Vec<NodeIndex>> {
+        if !allow(src) || !allow(dst) {
+            return None;
+        }
+
+        let g = EdgeFiltered::from_fn(&self.g, |e| {
+            e.weight().enabled && allow(e.source()) && allow(e.target())
+        });
+        let (_, path_nodes) = astar(&g, src, |i| i == dst, |e| e.weight().cost, |_| 0.0)?;
+        Some(path_nodes)
+    }
 
-        let (_, path_nodes) = astar(&self.g, src, |i| i == dst, |_| 1, |_| 0)?;
+    fn compose_path(&self, path_nodes: Vec<NodeIndex>) -> (E, Vec<&str>) {
         // If src == dst, path contains 1 node, so tf is identity.
-        let mut tf = SE3::identity();
+        let mut tf = E::identity();
         for (&a, &b) in path_nodes.iter().tuple_windows() {
             // or array_windows
-            let (edge, dir) = self.g.find_edge_undirected(a, b).unwrap();
-            let lhs = match dir {
-                Direction::Outgoing => &self.g[edge],
-                Direction::Incoming => &self.g[edge].inverse(),
-            };
-            tf = lhs * tf;
+            tf = self.chain_edge(self.first_enabled_edge(a, b), a, &tf);
+        }
+
+        (self.directed(tf), path_nodes.into_iter().map(|ix| self.g[ix].name()).collect())
+    }
+
+    /// There may be parallel edges in cyclic mode. Paths use the first enabled one.
+    fn first_enabled_edge(&self, a: NodeIndex, b: NodeIndex) -> EdgeIndex {
+        self.g.edges_connecting(a, b).find(|e| e.weight().enabled).unwrap().id()
+    }
+
+    /// Chain `edge` after `tf`, traversing the edge starting from node `from`.
+    ///
+    /// Both `tf` and the result are in the `SourceToTarget` convention.
+    fn chain_edge(&self, edge: EdgeIndex, from: NodeIndex, tf: &E) -> E {
+        self.chain_tf(edge, &self.g[edge].tf, from, tf)
+    }
+
+    /// Like `chain_edge`, with `edge_tf` in place of the transform stored on `edge`.
+    fn chain_tf(&self, edge: EdgeIndex, edge_tf: &E, from: NodeIndex, tf: &E) -> E {
+        // Edge references do not tell the stored direction for undirected graphs.
+        let forward = self.g.edge_endpoints(edge).unwrap().0 == from;
+        if forward == (self.convention == EdgeConvention::SourceToTarget) {
+            edge_tf.compose(tf)
+        } else {
+            edge_tf.inverse().compose(tf)
+        }
+    }
+
+    /// Enable or disable the edges between two frames, in either direction.
+    ///
+    /// Disabled edges are kept in the graph (and in saved files) but are skipped by path search.
+    /// Returns `None` if there is no such edge.
+    pub fn set_enabled(&mut self, a: &str, b: &str, enabled: bool) -> Option<()> {
+        let (a, b) = (self.find_node(a)?, self.find_node(b)?);
+        self.update_edges(a, b, |e| e.enabled = enabled)
+    }
+
+    /// Set the routing cost of the edges between two frames, in either direction (default: 1).
+    ///
+    /// Only matters if cycles are allowed: path search picks the route with the lowest total cost,
+    /// so e.g. a trusted calibration chain can be preferred.
+    /// Returns `None` if there is no such edge, or the cost is negative or NaN.
+    pub fn set_cost(&mut self, a: &str, b: &str, cost: f64) -> Option<()> {
+        if cost.is_nan() || cost < 0.0 {
+            return None;
+        }
+        let (a, b) = (self.find_node(a)?, self.find_node(b)?);
+        self.update_edges(a, b, |e| e.cost = cost)
+    }
+
+    fn update_edges(&mut self, a: NodeIndex, b: NodeIndex, f: impl Fn(&mut Edge<E>)) -> Option<()> {
+        let edges: Vec<_> = self.g.edges_connecting(a, b).map(|e| e.id()).collect();
+        if edges.is_empty() || self.frozen {
+            return None;
+        }
+        self.index = None;
+        for e in edges {
+            f(&mut self.g[e]);
         }
+        Some(())
+    }
 
-        Some((tf, path_nodes.into_iter().map(|ix| self.g[ix].as_str()).collect()))
+    /// Remove the transforms between two frames, in either direction, keeping both frames.
+    ///
+    /// Returns whether anything was removed; never for a frozen graph.
+    pub fn remove_tf(&mut self, a: &str, b: &str) -> bool {
+        let (Some(a), Some(b)) = (self.find_node(a), self.find_node(b)) else {
+            return false;
+        };
+        let mut edges: Vec<_> = self.g.edges_connecting(a, b).map(|e| e.id()).collect();
+        if edges.is_empty() || self.frozen {
+            return false;
+        }
+        // Removing an edge moves the last edge into its index, so remove from the back.
+        edges.sort_unstable_by(|x, y| y.cmp(x));
+        for e in edges {
+            self.g.remove_edge(e);
+        }
+        self.index = None;
+        true
     }
 
-    pub fn reset(&mut self) {
+    /// Remove a frame and all its transforms. It is also no longer a root or in any group.
+    ///
+    /// Returns whether anything was removed; never for a frozen graph.
+    pub fn remove_frame(&mut self, name: &str) -> bool {
+        let Some(node) = self.find_node(name).filter(|_| !self.frozen) else {
+            return false;
+        };
+        self.remove_nodes(vec![node]);
+        true
+    }
+
+    /// Remove all frames and transforms, keeping the settings and revisions.
+    ///
+    /// Returns `None` if the graph is frozen.
+    pub fn reset(&mut self) -> Option<()> {
+        if self.frozen {
+            return None;
+        }
         self.g.clear();
+        self.lookup.clear();
+        self.roots.clear();
+        self.groups.clear();
+        self.index = None;
+        Some(())
     }
 
-    fn find_node(&self, s: &str) -> Option<NodeIndex> {
-        self.g.node_indices().find(|ix| self.g[*ix] == s)
+    /// Make the graph read-only, for good: all methods that would change its frames, transforms or
+    /// revisions fail instead, returning `None` or an error. Queries and the route index still work.
+    ///
+    /// `set_max_age`, `set_buffer_duration` and `set_name_validator` still apply, as they only affect
+    /// queries and checks.
+    /// Clones of a frozen graph are frozen as well.
+    pub fn freeze(&mut self) {
+        self.frozen = true;
     }
 
-    fn find_or_add_node(&mut self, s: String) -> NodeIndex {
-        self.find_node(&s).unwrap_or_else(|| self.g.add_node(s))
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
     }
 
-    pub fn dump_json(&self, writer: &mut impl io::Write) -> Result<(), impl std::error::Error> {
-        serde_json::to_writer_pretty(writer, &self.g)
+    /// Provenance of the graph, saved at the start of its file.
+    pub fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
     }
 
-    pub fn load_json(&mut self, reader: &mut impl io::Read) -> Result<(), ()>  {
-        let g: G = serde_json::from_reader(reader).map_err(|_|())?;
-        if is_cyclic_undirected(&g) {
-            Err(())
+    /// Returns `None` if the graph is frozen.
+    pub fn set_header(&mut self, header: Option<Header>) -> Option<()> {
+        if self.frozen {
+            return None;
         }
-        else {
-            self.g = g;
-            Ok(())
+        self.header = header;
+        Some(())
+    }
+
+    fn find_node(&self, s: &str) -> Option<NodeIndex> {
+        self.lookup.get(self.names.normalize(s).as_ref()).copied()
+    }
+
+    fn find_or_add_node(&mut self, n: N) -> NodeIndex {
+        self.find_node(n.name()).unwrap_or_else(|| self.add_node(n))
+    }
+
+    /// Add a frame without checking for an existing one. If there is one, lookups keep finding it.
+    fn add_node(&mut self, n: N) -> NodeIndex {
+        let key = self.names.normalize(n.name()).into_owned();
+        let ix = self.g.add_node(n);
+        self.lookup.entry(key).or_insert(ix);
+        ix
+    }
+
+    /// Rebuild the name lookup, e.g. after the name matching changed.
+    fn rebuild_lookup(&mut self) {
+        self.lookup.clear();
+        for ix in self.g.node_indices() {
+            self.lookup.entry(self.names.normalize(self.g[ix].name()).into_owned()).or_insert(ix);
         }
     }
 
+    /// Names of all frames.
     pub fn nodes(&self) -> impl Iterator<Item = &str> {
-        self.g.node_weights().map(|s| s.as_str())
+        self.g.node_weights().map(|n| n.name())
+    }
+
+    pub fn frames(&self) -> impl Iterator<Item = &N> {
+        self.g.node_weights()
+    }
+
+    /// Look up a frame by name.
+    pub fn frame(&self, name: &str) -> Option<&N> {
+        self.find_node(name).map(|ix| &self.g[ix])
+    }
+
+    /// Look up a frame by name for changing it. Returns `None` if the graph is frozen.
+    ///
+    /// The name of the frame must not be changed, as frames are looked up by the name they were added with.
+    pub fn frame_mut(&mut self, name: &str) -> Option<&mut N> {
+        if self.frozen {
+            return None;
+        }
+        self.find_node(name).map(|ix| &mut self.g[ix])
     }
 
     pub fn transforms(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.edges().map(|e| (e.from, e.to))
+    }
+
+    /// Iterate over all stored transform edges, in their stored direction.
+    pub fn edges(&self) -> impl Iterator<Item = EdgeView<'_, E>> {
         self.g.edge_references()
-        .map(|r| (self.g[r.source()].as_str(), self.g[r.target()].as_str()))
+        .map(|r| EdgeView {
+            from: self.g[r.source()].name(),
+            to: self.g[r.target()].name(),
+            tf: &r.weight().tf,
+            enabled: r.weight().enabled,
+            cost: r.weight().cost,
+            calibrated: r.weight().calibrated,
+            dynamic: !r.weight().samples.poses.is_empty(),
+        })
     }
 }
 
-#[allow(dead_code)]
-pub mod error {
+impl<N: Frame> TfGraph<N> {
+    /// Put every transform, those of revisions included, in the canonical form of `se3::canonical`, so
+    /// that repeatedly saving and loading a graph does not accumulate drift. Returns `None` if the graph
+    /// is frozen.
+    pub fn normalize(&mut self, epsilon: f64) -> Option<()> {
+        if self.frozen {
+            return None;
+        }
+        let revisions = self.revisions.iter_mut().flat_map(|r| r.transforms.iter_mut().map(|t| &mut t.tf));
+        for tf in self.g.edge_weights_mut().map(|e| &mut e.tf).chain(revisions) {
+            *tf = se3::canonical(tf, epsilon);
+        }
+        self.index = None;
+        Some(())
+    }
+
+    /// Express point `p`, given in frame `from`, in frame `to`. Returns `None` if the frames are not connected.
+    pub fn transform_point(&self, p: Point3<f64>, from: &str, to: &str) -> Option<Point3<f64>> {
+        let (tf, _) = self.query_tf(from, to)?;
+        Some(self.directed(tf) * p)
+    }
+
+    /// Like `transform_point` for many points, querying the transform once.
+    pub fn transform_points(&self, points: &[Point3<f64>], from: &str, to: &str) -> Option<Vec<Point3<f64>>> {
+        let (tf, _) = self.query_tf(from, to)?;
+        let tf = self.directed(tf);
+        Some(points.iter().map(|p| tf * p).collect())
+    }
+
+    #[cfg(feature = "json")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn dump_json(&self, writer: &mut impl io::Write) -> Result<(), impl std::error::Error>
+    where N: serde::Serialize
+    {
+        file::GraphFile::from_graph(self).write_pretty(writer)
+    }
+
+    /// Replace the graph with one saved by `dump_json`, or by older versions.
+    ///
+    /// The graph is kept as it was if reading fails, if the file is not a graph, or if its transforms
+    /// are cyclic while neither this graph nor the file allows cycles.
+    #[cfg(feature = "json")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn load_json(&mut self, reader: &mut impl io::Read) -> Result<(), Error>
+    where N: serde::de::DeserializeOwned
+    {
+        if self.frozen {
+            return Err(Error::Frozen);
+        }
+        let value: serde_json::Value = serde_json::from_reader(reader)?;
+        let loaded = match file::GraphFile::deserialize(&value) {
+            Ok(file) => file.into_graph(self.allow_cycles)?,
+            // Files saved by older versions contain petgraph's own serialization.
+            Err(e) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("not a graph file, trying legacy format");
+                // Report why it is not a current graph file, which is the likelier intent.
+                file::from_legacy(value, self.allow_cycles).map_err(|_| Error::from(e))?
+            }
+        };
+        self.replace_with_loaded(loaded)
+    }
 
-    pub enum Error {
-        //Input,
-        Io,
-        Cycle,
+    /// Replace the graph with a loaded one, unless it is cyclic and should not be.
+    ///
+    /// Settings that files do not store, such as the buffer duration, interpolation, extrapolation and
+    /// name validator, are kept.
+    #[cfg(feature = "json")]
+    fn replace_with_loaded(&mut self, mut loaded: Self) -> Result<(), Error> {
+        if !loaded.allow_cycles && is_cyclic_undirected(&loaded.g) {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("rejected cyclic graph");
+            Err(Error::Cycle)
+        }
+        else {
+            // Warnings are collected as this graph says, not as the loaded one does.
+            let loading = loaded.warnings.take();
+            let (interpolation, extrapolation) = (self.interpolation, self.extrapolation);
+            *self = Self {
+                warnings: self.warnings.take(),
+                buffer_duration: self.buffer_duration,
+                validator: self.validator,
+                ..loaded
+            };
+            self.set_interpolation(interpolation);
+            self.set_extrapolation(extrapolation);
+            for warning in loading.into_iter().flatten() {
+                self.warn(warning);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Why `try_query_tf` found no transform.
+///
+/// In JSON: `{"error": "unknown_frame", "frame": "cam"}` or `{"error": "not_connected"}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, Deserialize), serde(tag = "error", content = "frame", rename_all = "snake_case"))]
+pub enum QueryError {
+    /// There is no frame of this name. If both frames are unknown, this is the source.
+    UnknownFrame(String),
+    /// Both frames exist, but no path of enabled transforms connects them.
+    NotConnected,
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryError::UnknownFrame(frame) => write!(f, "unknown frame {frame:?}"),
+            QueryError::NotConnected => write!(f, "frames are not connected"),
+        }
     }
+}
+
+impl std::error::Error for QueryError {}
+
+/// Where a graph comes from. All fields are optional, and kept as they are by load and save.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, Deserialize), serde(default))]
+pub struct Header {
+    /// Program that created the graph, e.g. `tfgen 0.1.0`.
+    #[cfg_attr(feature = "json", serde(skip_serializing_if = "Option::is_none"))]
+    pub tool: Option<String>,
+    /// Seconds since the Unix epoch.
+    #[cfg_attr(feature = "json", serde(skip_serializing_if = "Option::is_none"))]
+    pub created: Option<u64>,
+    #[cfg_attr(feature = "json", serde(skip_serializing_if = "Option::is_none"))]
+    pub author: Option<String>,
+    #[cfg_attr(feature = "json", serde(skip_serializing_if = "Option::is_none"))]
+    pub description: Option<String>,
+}
 
+/// A transform edge, as it was added with `from -> to : tf`.
+///
+/// With the default `EdgeConvention`, `tf` maps points expressed in the `from` frame into the `to` frame:
+/// `p_to = tf * p_from`. In any case, this is the same convention as the result of `query_tf(from, to)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct EdgeView<'a, E = SE3> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub tf: &'a E,
+    /// Whether path search may use this edge.
+    pub enabled: bool,
+    /// Routing cost used by path search.
+    pub cost: f64,
+    /// When the transform was calibrated, in seconds since the Unix epoch, if known.
+    pub calibrated: Option<u64>,
+    /// Whether the transform holds timed samples (see `add_tf_at`), rather than being static.
+    pub dynamic: bool,
 }
 
 #[cfg(test)]
@@ -140,15 +806,287 @@ mod test {
 
         assert!(g.nodes().eq(["a", "b", "c", "x", "y"].into_iter()));
         assert!(g.transforms().eq([("a", "b"), ("a", "c"), ("x", "y")].into_iter()));
+        let e = g.edges().next().unwrap();
+        assert_eq!((e.from, e.to), ("a", "b"));
+        assert_relative_eq!(*e.tf, ab);
 
         // detect cycles
-        assert!(g.add_tf("b".to_owned(), "c".to_owned(), bc.clone()).is_none());
+        assert!(!g.can_add_tf("b", "c"));
+        assert!(matches!(g.add_tf("b".to_owned(), "c".to_owned(), bc), Err(Error::Cycle)));
+        assert!(!g.can_add_tf("b", "a") && !g.can_add_tf("z", "z"));
+        assert!(g.can_add_tf("a", "b") && g.can_add_tf("b", "x") && g.can_add_tf("z", "a"));
 
         let (bc_q, bc_path) = g.query_tf("b", "c").unwrap();
         assert_relative_eq!(bc_q, bc);
         assert_eq!(bc_path, ["b", "a", "c"]);
+        assert_eq!(g.query_path("b", "c").unwrap(), bc_path);
+        assert!(g.query_path("a", "x").is_none());
+        let chain = g.query_chain("b", "c").unwrap();
+        assert_eq!(chain.iter().map(|&(a, b, _)| (a, b)).collect_vec(), [("b", "a"), ("a", "c")]);
+        assert_relative_eq!(chain[0].2, ab.inverse());
+        assert_relative_eq!(chain[1].2 * chain[0].2, bc);
+        assert!(g.query_chain("b", "b").unwrap().is_empty());
+        // A frame to itself
+        assert_eq!(g.query_tf("y", "y").unwrap(), (SE3::identity(), vec!["y"]));
+        assert!(g.query_tf("z", "z").is_none());
         // Not connected
         assert!(g.query_tf("a", "x").is_none());
+        assert_eq!(g.try_query_tf("a", "x").unwrap_err(), QueryError::NotConnected);
+        assert_eq!(g.try_query_tf("a", "z").unwrap_err(), QueryError::UnknownFrame("z".to_owned()));
+        assert_eq!(g.try_query_tf("b", "c").unwrap().1, bc_path);
+
+        assert!(g.query_tf_bounded("b", "c", 2).is_some());
+        assert!(g.query_tf_bounded("b", "c", 1).is_none());
+        assert!(g.query_tf_bounded("b", "b", 0).is_some());
+
+        assert!(g.query_tf_avoiding("b", "c", &["x", "unknown"]).is_some());
+        assert!(g.query_tf_avoiding("b", "c", &["a"]).is_none());
+        assert!(g.query_tf_avoiding("b", "c", &["c"]).is_none());
+
+        let (bc_via, path) = g.query_tf_via("b", "c", "a").unwrap();
+        assert_relative_eq!(bc_via, ab.inverse(), epsilon = 1e-12);
+        assert_eq!(path, ["b", "a", "c", "a"]);
+        assert!(g.query_tf_via("b", "x", "c").is_none());
+
+        g.set_enabled("c", "a", false).unwrap();
+        assert!(g.query_tf("b", "c").is_none());
+        assert!(!g.edges().nth(1).unwrap().enabled);
+        g.set_enabled("a", "c", true).unwrap();
+        assert!(g.query_tf("b", "c").is_some());
+        assert!(g.set_enabled("b", "c", false).is_none());
+    }
+
+    #[test]
+    fn remove() {
+        let mut g = TfGraph::new();
+        g.set_allow_cycles(true).unwrap();
+        for (a, b) in [("a", "b"), ("b", "a"), ("b", "c"), ("c", "d"), ("d", "a")] {
+            g.add_tf(a.to_owned(), b.to_owned(), se3::random()).unwrap();
+        }
+        g.set_root("c").unwrap();
+        g.tag("d", "tail").unwrap();
+
+        assert!(g.remove_tf("a", "b"));
+        assert!(!g.remove_tf("b", "a") && !g.remove_tf("a", "c") && !g.remove_tf("a", "z"));
+        assert!(g.transforms().sorted().eq([("b", "c"), ("c", "d"), ("d", "a")]));
+        assert_eq!(g.query_path("a", "b").unwrap(), ["a", "d", "c", "b"]);
+
+        assert!(g.remove_frame("c"));
+        assert!(!g.remove_frame("c"));
+        assert!(g.nodes().eq(["a", "b", "d"]));
+        assert_eq!(g.frame("d").unwrap(), "d");
+        assert!(g.transforms().eq([("d", "a")]));
+        assert_eq!(g.roots().count(), 0);
+        assert_eq!(g.group("tail"), ["d"]);
+        assert!(g.query_tf("a", "b").is_none());
+
+        g.freeze();
+        assert!(!g.remove_tf("d", "a") && !g.remove_frame("d"));
+    }
+
+    #[test]
+    fn edge_convention() {
+        let (ab, cb) = (se3::random(), se3::random());
+        let mut g = TfGraph::new();
+        g.set_edge_convention(EdgeConvention::TargetToSource);
+        // The pose of b in a, and of b in c.
+        g.add_tf("a".to_owned(), "b".to_owned(), ab).unwrap();
+        g.add_tf("c".to_owned(), "b".to_owned(), cb).unwrap();
+        assert_relative_eq!(g.query_tf("a", "b").unwrap().0, ab, epsilon = 1e-12);
+        // p_a = ab * p_b = ab * cb^-1 * p_c
+        let ac = ab * cb.inverse();
+        assert_relative_eq!(g.query_tf("a", "c").unwrap().0, ac, epsilon = 1e-12);
+        assert_relative_eq!(g.query_tf_via("a", "b", "c").unwrap().0, ac, epsilon = 1e-12);
+        assert_relative_eq!(g.query_many("a", &["c"]).unwrap()[0].unwrap(), ac, epsilon = 1e-12);
+
+        // Switching converts the stored transforms
+        g.set_edge_convention(EdgeConvention::SourceToTarget);
+        assert_relative_eq!(*g.edges().next().unwrap().tf, ab.inverse(), epsilon = 1e-12);
+        assert_relative_eq!(g.query_tf("a", "c").unwrap().0, ac.inverse(), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn canonical_edges() {
+        let (cb, ab) = (se3::random(), se3::random());
+        let mut g = TfGraph::new();
+        g.add_tf("c".to_owned(), "b".to_owned(), cb).unwrap();
+        g.add_tf("a".to_owned(), "b".to_owned(), ab).unwrap();
+        let (ac, _) = g.query_tf("a", "c").unwrap();
+
+        g.set_canonical_edges(true).unwrap();
+        assert!(g.transforms().eq([("b", "c"), ("a", "b")]));
+        assert_relative_eq!(*g.edges().next().unwrap().tf, cb.inverse(), epsilon = 1e-12);
+        assert_relative_eq!(g.query_tf("a", "c").unwrap().0, ac, epsilon = 1e-12);
+
+        // Adding the other way round updates the stored edge.
+        let ba = se3::random();
+        g.add_tf("b".to_owned(), "a".to_owned(), ba).unwrap();
+        assert_eq!(g.edges().count(), 2);
+        assert_relative_eq!(*g.edges().nth(1).unwrap().tf, ba.inverse(), epsilon = 1e-12);
+        assert!(matches!(g.add_tfs([("d".to_owned(), "c".to_owned(), cb)])[..], [Ok(())]));
+        assert!(g.transforms().eq([("b", "c"), ("a", "b"), ("c", "d")]));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_errors() {
+        let unknown = QueryError::UnknownFrame("cam".to_owned());
+        let json = serde_json::to_string(&unknown).unwrap();
+        assert_eq!(json, r#"{"error":"unknown_frame","frame":"cam"}"#);
+        assert_eq!(serde_json::from_str::<QueryError>(&json).unwrap(), unknown);
+        assert_eq!(serde_json::to_string(&QueryError::NotConnected).unwrap(), r#"{"error":"not_connected"}"#);
+
+        let invalid = AddError::InvalidName(InvalidName { name: "/cam".to_owned(), reason: "leading or trailing slash" });
+        assert_eq!(serde_json::to_string(&invalid).unwrap(), r#"{"error":"invalid_name","name":"/cam","reason":"leading or trailing slash"}"#);
+        assert_eq!(serde_json::to_string(&AddError::Cycle).unwrap(), r#"{"error":"cycle"}"#);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_roundtrip() {
+        let mut g = TfGraph::new();
+        let ab = from_array(&[1.0, 2.0, 3.0, 0.0, 0.0, 0.0, 1.0]).unwrap();
+        g.add_tf("a".to_owned(), "b".to_owned(), ab).unwrap();
+        g.add_tf("c".to_owned(), "b".to_owned(), se3::random()).unwrap();
+
+        let mut buf = Vec::new();
+        g.dump_json(&mut buf).unwrap();
+        let s = String::from_utf8(buf).unwrap();
+        assert!(s.contains(r#""tf": [1.0, 2.0, 3.0, 0.0, 0.0, 0.0, 1.0]"#));
+
+        let mut g2 = TfGraph::new();
+        g2.load_json(&mut s.as_bytes()).unwrap();
+        assert!(g2.nodes().eq(g.nodes()));
+        assert!(g2.transforms().eq(g.transforms()));
+        assert_relative_eq!(g2.query_tf("a", "c").unwrap().0, g.query_tf("a", "c").unwrap().0, epsilon = 1e-12);
+
+        g.set_edge_convention(EdgeConvention::TargetToSource);
+        let mut buf = Vec::new();
+        g.dump_json(&mut buf).unwrap();
+        let s = String::from_utf8(buf).unwrap();
+        assert!(s.contains(r#""convention": "target_to_source""#));
+        g2.load_json(&mut s.as_bytes()).unwrap();
+        assert_eq!(g2.edge_convention(), EdgeConvention::TargetToSource);
+        assert_relative_eq!(g2.query_tf("a", "c").unwrap().0, g.query_tf("a", "c").unwrap().0, epsilon = 1e-12);
+
+        g.set_calibrated("a", "b", Some(1709294400)).unwrap();
+        g.set_root("b").unwrap();
+        g.tag("a", "sensors").unwrap();
+        g.set_header(Some(Header { author: Some("me".to_owned()), created: Some(1709294400), ..Default::default() }));
+        g.set_max_age(Some(std::time::Duration::from_secs(90 * 86400)));
+        g.commit("first");
+        let mut buf = Vec::new();
+        g.dump_json(&mut buf).unwrap();
+        g2.load_json(&mut buf.as_slice()).unwrap();
+        assert_eq!(g2.max_age(), g.max_age());
+        assert!(g2.roots().eq(["b"]));
+        assert_eq!(g2.group("sensors"), ["a"]);
+        assert_eq!(g2.header(), g.header());
+        assert!(g2.edges().any(|e| e.calibrated == Some(1709294400)));
+        assert_eq!(g2.revisions()[0].transforms[0].calibrated, Some(1709294400));
+        assert_eq!(g2.revisions().len(), 1);
+        assert_eq!(g2.revisions()[0].message, "first");
+        assert!(g2.revisions()[0].transforms.iter().map(|t| (&t.from, &t.to)).eq(g.revisions()[0].transforms.iter().map(|t| (&t.from, &t.to))));
+
+        g.set_canonical_edges(true).unwrap();
+        let mut buf = Vec::new();
+        g.dump_json(&mut buf).unwrap();
+        g2.load_json(&mut buf.as_slice()).unwrap();
+        assert!(g2.canonical_edges());
+        assert!(g2.transforms().eq([("a", "b"), ("b", "c")]));
+
+        // normalized graphs survive a round trip unchanged
+        g.normalize(1e-12).unwrap();
+        let mut buf = Vec::new();
+        g.dump_json(&mut buf).unwrap();
+        g2.load_json(&mut buf.as_slice()).unwrap();
+        let mut again = Vec::new();
+        g2.dump_json(&mut again).unwrap();
+        assert_eq!(String::from_utf8(again).unwrap(), String::from_utf8(buf).unwrap());
+
+        // cyclic files are rejected
+        let cyclic = r#"{"frames": ["a", "b"], "transforms": [
+            {"from": "a", "to": "b", "tf": [0, 0, 0, 0, 0, 0, 1]},
+            {"from": "b", "to": "a", "tf": [0, 0, 0, 0, 0, 0, 1]}]}"#;
+        assert!(matches!(g2.load_json(&mut cyclic.as_bytes()), Err(Error::Cycle)));
+        let unlisted = r#"{"frames": ["a"], "transforms": [{"from": "a", "to": "b", "tf": [0, 0, 0, 0, 0, 0, 1]}]}"#;
+        assert!(matches!(g2.load_json(&mut unlisted.as_bytes()), Err(Error::UnknownFrame(frame)) if frame == "b"));
+        assert!(matches!(g2.load_json(&mut "{".as_bytes()), Err(Error::Parse(_))));
+        assert!(g2.nodes().eq(g.nodes()));
+    }
+
+    #[test]
+    fn freeze() {
+        let mut g = TfGraph::new();
+        g.add_tf("a".to_owned(), "b".to_owned(), se3::random()).unwrap();
+        g.freeze();
+        assert!(g.is_frozen());
+        assert!(matches!(g.add_tf("b".to_owned(), "c".to_owned(), se3::random()), Err(Error::Frozen)));
+        assert!(g.set_enabled("a", "b", false).is_none());
+        assert!(g.set_edge_convention(EdgeConvention::TargetToSource).is_none());
+        assert!(g.reset().is_none());
+        assert!(g.commit("x").is_none());
+        assert!(g.frame_mut("a").is_none());
+        assert!(g.query_tf("a", "b").is_some());
+        assert!(g.clone().set_cost("a", "b", 2.0).is_none());
+    }
+
+    #[test]
+    fn custom_frame() {
+        #[derive(Debug)]
+        struct Sensor {
+            name: String,
+            id: u32,
+        }
+        impl Frame for Sensor {
+            fn name(&self) -> &str {
+                &self.name
+            }
+        }
+        let sensor = |name: &str, id| Sensor { name: name.to_owned(), id };
+
+        let mut g = TfGraph::default();
+        g.add_tf(sensor("cam", 1), sensor("imu", 2), SE3::identity()).unwrap();
+        g.add_tf(sensor("lidar", 3), sensor("imu", 4), SE3::identity()).unwrap();
+        assert_eq!(g.frame("imu").unwrap().id, 2);
+        g.frame_mut("imu").unwrap().id = 5;
+        assert!(g.frames().map(|s| s.id).eq([1, 5, 3]));
+        assert_eq!(g.query_tf("cam", "lidar").unwrap().1, ["cam", "imu", "lidar"]);
+    }
+
+    #[test]
+    fn similarity_edges() {
+        use nalgebra::{Similarity3, Vector3};
+
+        let mut g = TransformGraph::<String, Similarity3<f64>>::default();
+        let ab = Similarity3::from_scaling(2.0);
+        let cb = Similarity3::new(Vector3::new(1.0, 0.0, 0.0), Vector3::zeros(), 4.0);
+        g.add_tf("a".to_owned(), "b".to_owned(), ab).unwrap();
+        g.add_tf("c".to_owned(), "b".to_owned(), cb).unwrap();
+
+        let (ac, _) = g.query_tf("a", "c").unwrap();
+        assert_relative_eq!(ac, cb.inverse() * ab);
+        assert_relative_eq!(ac.scaling(), 0.5);
+    }
+
+    #[test]
+    fn transform_points() {
+        let mut g = TfGraph::new();
+        let cam_to_base = se3::random();
+        g.add_tf("cam".to_owned(), "base".to_owned(), cam_to_base).unwrap();
+        g.add_tf("x".to_owned(), "y".to_owned(), se3::random()).unwrap();
+        let points = [Point3::new(1.0, 2.0, 3.0), Point3::origin()];
+
+        let p = g.transform_point(points[0], "cam", "base").unwrap();
+        assert_relative_eq!(p, cam_to_base * points[0], epsilon = 1e-12);
+        assert_relative_eq!(g.transform_point(p, "base", "cam").unwrap(), points[0], epsilon = 1e-12);
+        assert!(g.transform_point(p, "cam", "x").is_none());
+
+        g.set_edge_convention(EdgeConvention::TargetToSource);
+        let moved = g.transform_points(&points, "cam", "base").unwrap();
+        assert_relative_eq!(moved[0], p, epsilon = 1e-12);
+        assert_relative_eq!(moved[1], Point3::from(cam_to_base.translation.vector), epsilon = 1e-12);
+        assert!(g.transform_points(&points, "cam", "nowhere").is_none());
     }
 
     #[test]
@@ -1,80 +1,1892 @@
-use std::{fs::File, io::stdin, process::ExitCode, str::FromStr};
+use std::{borrow::Cow, collections::BTreeMap, sync::mpsc::{self, Receiver, Sender}, fs::File, hint::black_box, io::{stdin, stdout, IsTerminal, Write}, path::{Path, PathBuf}, process::ExitCode, str::FromStr, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+use clap::{ArgAction, CommandFactory, Parser, Subcommand, ValueHint};
 use itertools::Itertools;
+use tracing::{debug, level_filters::LevelFilter};
+use tracing_subscriber::EnvFilter;
 use tfgen::{
-    se3::{self, To7, SE3},
-    TfGraph,
+    camera::Pinhole,
+    diff::{self, Change},
+    export::EnvStyle,
+    roots::Issue,
+    report::{Layout, Plane},
+    import::{FieldMapping, Imported},
+    se3::{self, convention::{self, Convention}, To7, SE3},
+    trajectory::{Extrapolation, Interpolation, Side, Timeline, Trajectory},
+    EdgeConvention, EdgeView, NameMatching, QueryError, TfGraph, Warning,
 };
 use nalgebra as na;
 use owo_colors::OwoColorize;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use regex::Regex;
+
+/// A query re-run after every command, printed when its result changes.
+struct Watch {
+    from: String,
+    to: String,
+    last: Option<(SE3, Vec<String>)>,
+}
+
+/// Classes of errors, reported through the exit code when reading commands from a pipe or file.
+///
+/// The first error determines the exit code. Code 2 is used by clap for invalid arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Failure {
+    /// A line could not be parsed.
+    Parse = 3,
+    /// A query or edge operation named frames or transforms that do not exist.
+    NotFound = 4,
+    /// A transform or setting was rejected by the graph.
+    Rejected = 5,
+    /// A file could not be read, parsed or written.
+    Io = 6,
+}
+
+impl From<Failure> for ExitCode {
+    fn from(f: Failure) -> Self {
+        ExitCode::from(f as u8)
+    }
+}
+
+/// Build and query graphs of rigid transforms.
+///
+/// Without a subcommand, starts an interactive session.
+#[derive(Parser)]
+#[command(version, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Load a transform graph before starting the interactive session
+    #[arg(short, long, value_hint = ValueHint::FilePath)]
+    file: Option<PathBuf>,
+    /// Log more details to stderr (-v: info, -vv: debug, -vvv: trace). RUST_LOG takes precedence.
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+    /// Only log errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Reject all commands that change the graph or write it back, e.g. to inspect a deployed calibration
+    #[arg(long)]
+    read_only: bool,
+}
+
+impl Cli {
+    fn log_level(&self) -> LevelFilter {
+        match (self.quiet, self.verbose) {
+            (true, _) => LevelFilter::ERROR,
+            (false, 0) => LevelFilter::WARN,
+            (false, 1) => LevelFilter::INFO,
+            (false, 2) => LevelFilter::DEBUG,
+            (false, _) => LevelFilter::TRACE,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print a shell completion script to stdout
+    Completions {
+        shell: clap_complete::Shell,
+    },
+    /// Start an interactive session with a small example rig: base_link, imu, lidar and two cameras
+    Demo,
+    /// Query the transform of each `src,dst` row of a CSV file, and write one result row per pair
+    QueryBatch {
+        /// CSV file of `src,dst` rows, without a header. Blank lines and lines starting with `#` are skipped
+        #[arg(value_hint = ValueHint::FilePath)]
+        pairs: PathBuf,
+        /// Transform graph to query
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+        /// Output CSV file (default: stdout)
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        out: Option<PathBuf>,
+    },
+}
 
 fn main() -> ExitCode {
+    let cli = Cli::parse();
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::builder().with_default_directive(cli.log_level().into()).from_env_lossy())
+        .with_writer(std::io::stderr)
+        .without_time()
+        .init();
+
+    match cli.command {
+        None => repl(cli.file, cli.read_only, false),
+        Some(Command::Demo) => repl(None, false, true),
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(shell, &mut Cli::command(), "tfgen", &mut stdout());
+            ExitCode::SUCCESS
+        }
+        Some(Command::QueryBatch { pairs, file, out }) => query_batch(&pairs, &file, out.as_deref()),
+    }
+}
+
+/// Write `src,dst,x,y,z,qx,qy,qz,qw,error` rows, with an empty transform and the error for pairs that could not be queried.
+///
+/// Lines that are not pairs are reported and skipped. Exits with the failure of the first bad line or pair, if any.
+fn query_batch(pairs: &Path, file: &Path, out: Option<&Path>) -> ExitCode {
     let mut g = TfGraph::new();
+    // Not `load`, which prints to stdout.
+    if let Err(e) = load_file(&mut g, file) {
+        eprintln!("Could not load {}: {e}", file.display());
+        return Failure::Io.into();
+    }
+    // Fails if cycles are allowed, in which case queries search the graph.
+    let _ = g.build_index();
+    let text = match std::fs::read_to_string(pairs) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Could not read {}: {e}", pairs.display());
+            return Failure::Io.into();
+        }
+    };
+    let mut writer: Box<dyn Write> = match out.map(File::create).transpose() {
+        Ok(Some(fd)) => Box::new(std::io::BufWriter::new(fd)),
+        Ok(None) => Box::new(stdout().lock()),
+        Err(e) => {
+            eprintln!("Could not write {}: {e}", out.unwrap().display());
+            return Failure::Io.into();
+        }
+    };
+
+    let mut failure = None;
+    let mut rows = vec!["src,dst,x,y,z,qx,qy,qz,qw,error".to_owned()];
+    for (i, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty() && !l.trim_start().starts_with('#')) {
+        let Some((src, dst)) = line.split_once(',').map(|(s, d)| (s.trim(), d.trim())) else {
+            eprintln!("{}:{}: expected src,dst", pairs.display(), i + 1);
+            failure.get_or_insert(Failure::Parse);
+            continue;
+        };
+        let row = match g.try_query_tf(src, dst) {
+            Ok((tf, _)) => format!("{src},{dst},{},", tf.to7().iter().join(",")),
+            Err(e) => {
+                failure.get_or_insert(Failure::NotFound);
+                let error = match e {
+                    QueryError::UnknownFrame(_) => "unknown frame",
+                    QueryError::NotConnected => "not connected",
+                };
+                format!("{src},{dst},,,,,,,,{error}")
+            }
+        };
+        rows.push(row);
+    }
+    let written = rows.iter().try_for_each(|row| writeln!(writer, "{row}")).and_then(|()| writer.flush());
+    if let Err(e) = written {
+        eprintln!("Could not write results: {e}");
+        return Failure::Io.into();
+    }
+    failure.map_or(ExitCode::SUCCESS, Into::into)
+}
+
+/// With `demo`, start with the example rig instead of an empty graph.
+fn repl(file: Option<PathBuf>, read_only: bool, demo: bool) -> ExitCode {
+    let mut g = TfGraph::new();
+    g.set_collect_warnings(true);
+    let mut watches: Vec<Watch> = Vec::new();
+    let mut vars: BTreeMap<String, SE3> = BTreeMap::new();
+    let mut units = Units::default();
+    let mut matrix = MatrixFormat::default();
+    let mut cameras: BTreeMap<String, Pinhole> = BTreeMap::new();
+    let mut aliases = aliases_file().map(|file| read_aliases(&file)).unwrap_or_default();
+    let mut history: Vec<String> = Vec::new();
+    // Name of the file last loaded or saved
+    let mut name: Option<String> = None;
+    // Changed since last load/save
+    let mut dirty = false;
+    if let Some(file) = file {
+        let file = file.to_string_lossy().into_owned();
+        if !load(&mut g, &file) {
+            return Failure::Io.into();
+        }
+        name = Some(file);
+    }
+    if demo {
+        g = example_rig();
+        g.set_collect_warnings(true);
+        print_example_intro(&g);
+    }
+    if read_only {
+        g.freeze();
+    }
     println!("{}", "Enter a command. h for help.".blue());
 
-    for line in stdin().lines() {
-        let line = line.expect("Error reading input");
+    let interactive = stdin().is_terminal();
+    let mut failure: Option<Failure> = None;
+    let (sender, events) = mpsc::channel();
+    read_lines(sender.clone());
+    // The watched file and its watcher, which stops when dropped
+    let mut watched: Option<(String, RecommendedWatcher)> = None;
+    // Nothing was printed since the last prompt
+    let mut prompted = false;
+    loop {
+        report_warnings(g.take_warnings());
+        if interactive && !prompted {
+            print_prompt(&g, name.as_deref(), dirty);
+            prompted = true;
+        }
+        let line = match events.recv().expect("input thread stopped") {
+            Event::Line(line) => line,
+            Event::FileChanged => {
+                if let Some((file, _)) = &watched {
+                    if reload(&mut g, file, dirty, &units) {
+                        name = Some(file.clone());
+                        dirty = false;
+                        prompted = false;
+                        report_watches(&g, &mut watches, &units, matrix);
+                    }
+                }
+                continue;
+            }
+        };
+        prompted = false;
+        let Some(line) = line else {
+            // Only the input thread can send events now, so that `recv` fails rather than waits once it stops.
+            drop(watched);
+            drop(sender);
+            if interactive && dirty {
+                println!();
+                confirm_quit(&mut g, name.as_deref(), &events);
+            }
+            break;
+        };
+        let line = line.trim_end_matches(['\n', '\r']);
         // allow blank lines
         if line.is_empty() {
             continue;
         }
+        let line = match recall(line, &history) {
+            Some(Some(entry)) => {
+                println!("{entry}");
+                entry.to_owned()
+            }
+            Some(None) => {
+                eprintln!("{}", "No such command in history!".bright_red());
+                failure.get_or_insert(Failure::NotFound);
+                continue;
+            }
+            None => line.to_owned(),
+        };
+        if line.trim() != "history" {
+            history.push(line.clone());
+        }
+        let line = expand_alias(&line, &aliases);
         let Some(input) = parse_input(&line) else {
+            debug!(%line, "could not parse input");
             eprintln!("{}", "Invalid input!".bright_red());
+            failure.get_or_insert(Failure::Parse);
             continue;
         };
+        debug!(?input, "parsed input");
+        if read_only && input.writes() {
+            eprintln!("{}", "Read-only mode: the graph cannot be changed or saved!".bright_red());
+            failure.get_or_insert(Failure::Rejected);
+            continue;
+        }
 
         match input {
-            Input::Quit => break,
+            Input::Quit => {
+                if !interactive || !dirty || confirm_quit(&mut g, name.as_deref(), &events) {
+                    break;
+                }
+            }
+            Input::ForceQuit => break,
             Input::Reset => {
                 g.reset();
+                dirty = true;
                 println!("{}", "Graph was reset.".blue());
             }
             Input::Help => print_help(),
-            Input::Add { from, to, tf } =>
-                if g.add_tf(from, to, tf).is_none() {
-                    eprintln!("{}", "Could not add cyclic transform".bright_red());
+            Input::Units { length, angle } => {
+                units.length = length.unwrap_or(units.length);
+                units.angle = angle.unwrap_or(units.angle);
+                println!("Units: {}, {}", units.length, units.angle);
+            }
+            Input::Alias { name, command: Some(command) } => {
+                aliases.insert(name, command);
+            }
+            Input::Alias { name, command: None } => {
+                if aliases.remove(&name).is_none() {
+                    eprintln!("Unknown alias {}!", name.bold());
+                    failure.get_or_insert(Failure::NotFound);
+                }
+            }
+            Input::History => {
+                for (i, entry) in history.iter().enumerate().skip(history.len().saturating_sub(HISTORY_SHOWN)) {
+                    println!("{:>5}  {entry}", i + 1);
+                }
+            }
+            Input::Aliases => {
+                for (name, command) in &aliases {
+                    println!("{} = {command}", name.bold());
+                }
+            }
+            Input::MatchNames(matching) => {
+                if let Some(matching) = matching {
+                    if g.set_name_matching(matching).is_none() {
+                        eprintln!("{}", "Some frames would have the same name!".bright_red());
+                        failure.get_or_insert(Failure::Rejected);
+                        continue;
+                    }
+                    dirty = true;
+                }
+                if g.name_matching() == NameMatching::EXACT {
+                    println!("Frame names are matched exactly");
+                } else {
+                    println!("Frame names are matched ignoring case and extra whitespace");
+                }
+            }
+            Input::Matrix(format) => {
+                matrix = format;
+            }
+            Input::MaxAge(max_age) => {
+                if max_age != g.max_age() {
+                    g.set_max_age(max_age);
+                    dirty = true;
+                }
+            }
+            Input::Buffer(duration) => {
+                if let Some(duration) = duration {
+                    g.set_buffer_duration(duration);
+                }
+                println!("Timed samples are kept for {} s", g.buffer_duration().as_secs_f64());
+            }
+            Input::Interpolation(interpolation) => {
+                if let Some(interpolation) = interpolation {
+                    g.set_interpolation(interpolation);
+                }
+                match g.interpolation() {
+                    Interpolation::Linear => println!("Timed transforms are interpolated linearly"),
+                    Interpolation::Cubic => println!("Timed transforms are interpolated with cubic splines"),
+                }
+            }
+            Input::Extrapolation(extrapolation) => {
+                if let Some(extrapolation) = extrapolation {
+                    g.set_extrapolation(extrapolation);
+                }
+                match g.extrapolation() {
+                    Extrapolation::Error => println!("Queries outside the samples of a timed transform fail"),
+                    Extrapolation::ClampToNearest => println!("Queries outside the samples of a timed transform use the nearest sample"),
+                    Extrapolation::ExtrapolateUpTo(limit) => {
+                        println!("Timed transforms are extrapolated up to {} s beyond their samples", limit.as_secs_f64())
+                    }
+                }
+            }
+            Input::Calibrated { from, to, time } => {
+                if g.set_calibrated(&from, &to, time).is_some() {
+                    dirty = true;
+                } else {
+                    eprintln!("No transform between {} and {}!", from.bold().green(), to.bold().green());
+                    failure.get_or_insert(Failure::NotFound);
+                }
+            }
+            Input::Direction(convention) => {
+                if let Some(convention) = convention {
+                    if convention != g.edge_convention() {
+                        g.set_edge_convention(convention);
+                        dirty = true;
+                    }
+                }
+                match g.edge_convention() {
+                    EdgeConvention::SourceToTarget => println!("a -> b : T maps points from a into b (pose of a in b)"),
+                    EdgeConvention::TargetToSource => println!("a -> b : T maps points from b into a (pose of b in a)"),
+                }
+            }
+            Input::Orientation(canonical) => {
+                if let Some(canonical) = canonical {
+                    if canonical != g.canonical_edges() {
+                        g.set_canonical_edges(canonical);
+                        dirty = true;
+                    }
                 }
-            Input::Query { from, to } => {
-                if let Some((tf, path)) = g.query_tf(&from, &to) {
-                    let mat: na::Matrix4<f64> = na::convert(tf);
-                    println!("Transform from {} to {}: (Path: {})", from.bold().green(), to.bold().green(), path.join(" -> "));
-                    println!("{mat}[x,y,z, qx,qy,qz,qw]: {:?}", tf.to7());
+                if g.canonical_edges() {
+                    println!("Transforms are stored from the frame whose name sorts first");
                 } else {
+                    println!("Transforms are stored in the direction they were added in");
+                }
+            }
+            Input::Add { from, to, tf, conv } => {
+                let renormalized = matches!(&tf, TfArg::Values(values) if se3::normalizes_rotation(values));
+                let tf = match tf {
+                    TfArg::Values(values) => units.se3_from(&values).expect("validated by the parser"),
+                    TfArg::Random => se3::random(),
+                    TfArg::Var(var) => {
+                        let Some(&tf) = vars.get(&var) else {
+                            eprintln!("Unknown variable {}!", var.bold());
+                            failure.get_or_insert(Failure::NotFound);
+                            continue;
+                        };
+                        tf
+                    }
+                };
+                let tf = conv.map_or(tf, |conv| convention::convert(&tf, conv, Convention::Flu));
+                let warning = renormalized.then(|| Warning::Renormalized { from: from.clone(), to: to.clone() });
+                if add_or_report(&mut g, from, to, tf) {
+                    report_warnings(warning);
+                    dirty = true;
+                } else {
+                    failure.get_or_insert(Failure::Rejected);
+                }
+            }
+            Input::SetEnabled { from, to, enabled } => {
+                if g.set_enabled(&from, &to, enabled).is_none() {
                     eprintln!("No transform between {} and {}!", from.bold().green(), to.bold().green());
+                    failure.get_or_insert(Failure::NotFound);
+                } else {
+                    dirty = true;
+                }
+            }
+            Input::SetCost { from, to, cost } => {
+                if g.set_cost(&from, &to, cost).is_none() {
+                    eprintln!("Could not set cost of transform between {} and {}!", from.bold().green(), to.bold().green());
+                    failure.get_or_insert(Failure::Rejected);
+                } else {
+                    dirty = true;
+                }
+            }
+            Input::Query { from, to, output } => match g.try_query_tf(&from, &to) {
+                Ok((tf, path)) => match output {
+                    QueryOutput::Transform => print_tf(&from, &to, &tf, &path, &units, matrix),
+                    QueryOutput::Add => println!("{}", add_line(&from, &to, &tf, &units)),
+                    QueryOutput::Ros => println!("{}", ros_line(&from, &to, &tf, g.edge_convention())),
+                    QueryOutput::Json => println!("{}", serde_json::json!({ "from": from, "to": to, "tf": tf.to7(), "path": path })),
+                },
+                Err(e) if output == QueryOutput::Json => {
+                    println!("{}", serde_json::to_string(&e).expect("serializable"));
+                    failure.get_or_insert(Failure::NotFound);
+                }
+                Err(e) => {
+                    report_query_error(&g, &from, &to, e);
+                    failure.get_or_insert(Failure::NotFound);
+                }
+            },
+            Input::QueryAt { from, to, stamp } => match g.query_tf_at(&from, &to, stamp) {
+                Some((tf, path)) => print_tf(&from, &to, &tf, &path, &units, matrix),
+                None => {
+                    match g.try_query_tf(&from, &to) {
+                        Ok(_) => eprintln!("No samples of {} -> {} at {stamp}!", from.bold().green(), to.bold().green()),
+                        Err(e) => report_query_error(&g, &from, &to, e),
+                    }
+                    failure.get_or_insert(Failure::NotFound);
+                }
+            },
+            Input::QueryMatching { from, to, output } => {
+                let mut found = 0;
+                for src in g.nodes().filter(|n| glob_match(&from, n)) {
+                    for (dst, tf) in g.flatten(src).expect("frame exists") {
+                        if dst != src && glob_match(&to, dst) {
+                            match output {
+                                // One line per transform, as there may be many.
+                                QueryOutput::Transform | QueryOutput::Add => println!("{}", add_line(src, dst, &tf, &units)),
+                                QueryOutput::Ros => println!("{}", ros_line(src, dst, &tf, g.edge_convention())),
+                                QueryOutput::Json => println!("{}", serde_json::json!({ "from": src, "to": dst, "tf": tf.to7() })),
+                            }
+                            found += 1;
+                        }
+                    }
+                }
+                if found == 0 {
+                    eprintln!("No transforms between {} and {}!", from.bold().green(), to.bold().green());
+                    failure.get_or_insert(Failure::NotFound);
+                }
+            }
+            Input::Let { name, from, to } => match g.try_query_tf(&from, &to) {
+                Ok((tf, path)) => {
+                    print_tf(&from, &to, &tf, &path, &units, matrix);
+                    vars.insert(name, tf);
+                }
+                Err(e) => {
+                    report_query_error(&g, &from, &to, e);
+                    failure.get_or_insert(Failure::NotFound);
+                }
+            },
+            Input::Watch { from, to } => {
+                let last = query_owned(&g, &from, &to);
+                match &last {
+                    Some((tf, path)) => print_tf(&from, &to, tf, path, &units, matrix),
+                    None => println!("No transform between {} and {} yet.", from.bold().green(), to.bold().green()),
+                }
+                watches.retain(|w| (&w.from, &w.to) != (&from, &to));
+                watches.push(Watch { from, to, last });
+            }
+            Input::Unwatch { from, to } => {
+                let n = watches.len();
+                watches.retain(|w| (&w.from, &w.to) != (&from, &to));
+                if watches.len() == n {
+                    eprintln!("{} -> {} is not watched!", from.bold().green(), to.bold().green());
+                    failure.get_or_insert(Failure::NotFound);
+                }
+            }
+            Input::Bench { from, to, iterations } => {
+                if let Err(e) = g.try_query_tf(&from, &to) {
+                    report_query_error(&g, &from, &to, e);
+                    failure.get_or_insert(Failure::NotFound);
+                    continue;
+                }
+                let start = Instant::now();
+                for _ in 0..iterations {
+                    black_box(g.query_tf(black_box(&from), black_box(&to)));
+                }
+                let elapsed = start.elapsed();
+                println!("{iterations} queries in {elapsed:.2?}: {:.2?} per query, {:.0} queries/s",
+                    elapsed / iterations, iterations as f64 / elapsed.as_secs_f64());
+            }
+            Input::WatchFile(Some(file)) => {
+                let Some(file) = expand_or_report(&file) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                match watch_file(&file, sender.clone()) {
+                    Ok(watcher) if load(&mut g, &file) => {
+                        println!("Watching {file} for changes.");
+                        watched = Some((file.clone(), watcher));
+                        name = Some(file);
+                        dirty = false;
+                    }
+                    Ok(_) => {
+                        failure.get_or_insert(Failure::Io);
+                    }
+                    Err(e) => {
+                        eprintln!("Could not watch {file}: {e}");
+                        failure.get_or_insert(Failure::Io);
+                    }
+                }
+            }
+            Input::WatchFile(None) => {
+                if let Some((file, _)) = watched.take() {
+                    println!("Stopped watching {file}.");
+                }
+            }
+            Input::Tag { frames, group, tag } => {
+                for frame in frames {
+                    if g.frame(&frame).is_none() {
+                        eprintln!("Unknown frame {}!", frame.bold().green());
+                        failure.get_or_insert(Failure::NotFound);
+                    } else if tag {
+                        g.tag(&frame, &group).expect("frame exists");
+                        dirty = true;
+                    } else if g.untag(&frame, &group).is_some() {
+                        dirty = true;
+                    } else {
+                        eprintln!("{} is not in group {}!", frame.bold().green(), group.bold());
+                        failure.get_or_insert(Failure::NotFound);
+                    }
+                }
+            }
+            Input::DetectOutliers(tolerance) => {
+                let (translation, rotation) = match tolerance {
+                    Some((t, r)) => (t * units.length.meters(), r * units.angle.radians()),
+                    None => (0.01, 1f64.to_radians()),
+                };
+                let outliers = g.detect_outliers(translation, rotation);
+                if outliers.is_empty() {
+                    println!("All loops close within {} {} and {} {}.", translation / units.length.meters(), units.length, rotation / units.angle.radians(), units.angle);
+                }
+                for o in outliers {
+                    println!("  {} -> {}: loop residual lower by {:.2}", o.from, o.to, o.reduction);
+                }
+            }
+            Input::Groups => {
+                for group in g.groups() {
+                    println!("{} {}", format!("{group}:").blue().bold(), g.group(group).join(", "));
+                }
+            }
+            Input::RemoveGroup(group) => match g.remove_group(&group) {
+                Some(n) => {
+                    println!("Removed {n} frames of {group}.");
+                    dirty = true;
+                }
+                None => {
+                    eprintln!("Unknown group {}!", group.bold());
+                    failure.get_or_insert(Failure::NotFound);
+                }
+            },
+            Input::RemoveTf { from, to } => {
+                if g.remove_tf(&from, &to) {
+                    dirty = true;
+                } else {
+                    eprintln!("No transform between {} and {}!", from.bold().green(), to.bold().green());
+                    failure.get_or_insert(Failure::NotFound);
+                }
+            }
+            Input::RemoveFrame(frame) => {
+                if g.remove_frame(&frame) {
+                    dirty = true;
+                } else {
+                    eprintln!("Unknown frame {}!", frame.bold().green());
+                    failure.get_or_insert(Failure::NotFound);
+                }
+            }
+            Input::RemoveMatching(pattern) => {
+                let Some(pattern) = Pattern::new(&pattern) else {
+                    eprintln!("Invalid pattern {}!", pattern.bold());
+                    failure.get_or_insert(Failure::Parse);
+                    continue;
+                };
+                let frames = g.nodes().filter(|n| pattern.matches(n)).map(str::to_owned).collect_vec();
+                if frames.is_empty() {
+                    eprintln!("{}", "No frames match!".bright_red());
+                    failure.get_or_insert(Failure::NotFound);
+                    continue;
+                }
+                for frame in &frames {
+                    g.remove_frame(frame);
+                }
+                println!("Removed {} frames.", frames.len());
+                dirty = true;
+            }
+            Input::ExportMatching { pattern, file } => {
+                let Some(pattern) = Pattern::new(&pattern) else {
+                    eprintln!("Invalid pattern {}!", pattern.bold());
+                    failure.get_or_insert(Failure::Parse);
+                    continue;
+                };
+                let mut sub = g.subgraph(|n| pattern.matches(n));
+                if sub.nodes().next().is_none() {
+                    eprintln!("{}", "No frames match!".bright_red());
+                    failure.get_or_insert(Failure::NotFound);
+                    continue;
+                }
+                let Some(file) = expand_or_report(&file) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                if !save(&mut sub, &file) {
+                    failure.get_or_insert(Failure::Io);
+                }
+            }
+            Input::ExportGroup { group, file } => {
+                let Some(mut sub) = g.group_subgraph(&group) else {
+                    eprintln!("Unknown group {}!", group.bold());
+                    failure.get_or_insert(Failure::NotFound);
+                    continue;
+                };
+                let Some(file) = expand_or_report(&file) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                if !save(&mut sub, &file) {
+                    failure.get_or_insert(Failure::Io);
+                }
+            }
+            Input::Example => {
+                g = example_rig();
+                g.set_collect_warnings(true);
+                name = None;
+                dirty = false;
+                print_example_intro(&g);
+            }
+            Input::Load(file) => {
+                let Some(file) = expand_or_report(&file) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                if load(&mut g, &file) {
+                    name = Some(file);
+                    dirty = false;
+                } else {
+                    failure.get_or_insert(Failure::Io);
+                }
+            }
+            Input::ExportEnv { root, file, style } => {
+                if g.frame(&root).is_none() {
+                    eprintln!("Unknown frame {}!", root.bold().green());
+                    failure.get_or_insert(Failure::NotFound);
+                    continue;
+                }
+                let Some(file) = expand_or_report(&file) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                let written = File::create(&file).and_then(|fd| {
+                    let mut writer = std::io::BufWriter::new(fd);
+                    g.write_env(&root, style, &mut writer)?;
+                    writer.flush()
+                });
+                match written {
+                    Ok(()) => println!("Transforms from {} were written to {file}.", root.bold().green()),
+                    Err(e) => {
+                        eprintln!("Could not write {file}: {e}");
+                        failure.get_or_insert(Failure::Io);
+                    }
+                }
+            }
+            Input::ExportMat(file) => {
+                let Some(file) = expand_or_report(&file) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                let written = File::create(&file).and_then(|fd| {
+                    let mut writer = std::io::BufWriter::new(fd);
+                    g.write_mat("transforms", &mut writer)?;
+                    writer.flush()
+                });
+                match written {
+                    Ok(()) => println!("Transforms were written to {file}."),
+                    Err(e) => {
+                        eprintln!("Could not write {file}: {e}");
+                        failure.get_or_insert(Failure::Io);
+                    }
+                }
+            }
+            Input::Dot(file) => {
+                let Some(file) = expand_or_report(&file) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                let written = File::create(&file).and_then(|fd| {
+                    let mut writer = std::io::BufWriter::new(fd);
+                    g.dump_dot(&mut writer)?;
+                    writer.flush()
+                });
+                match written {
+                    Ok(()) => println!("The graph was written to {file}."),
+                    Err(e) => {
+                        eprintln!("Could not write {file}: {e}");
+                        failure.get_or_insert(Failure::Io);
+                    }
+                }
+            }
+            Input::LoadDot(file) => {
+                let Some(file) = expand_or_report(&file) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                match File::open(&file).map_err(tfgen::Error::from).and_then(|mut fd| g.load_dot(&mut fd)) {
+                    Ok(()) => {
+                        println!("Loaded transforms from {file}.");
+                        dirty = true;
+                    }
+                    Err(e) => {
+                        eprintln!("Could not load {file}: {e}");
+                        failure.get_or_insert(Failure::Io);
+                    }
+                }
+            }
+            Input::SetRoot { frame, root } => {
+                let done = if root { g.set_root(&frame) } else { g.unset_root(&frame) };
+                if done.is_some() {
+                    dirty = true;
+                } else if root {
+                    eprintln!("Unknown frame {}!", frame.bold().green());
+                    failure.get_or_insert(Failure::NotFound);
+                } else {
+                    eprintln!("{} is not a root!", frame.bold().green());
+                    failure.get_or_insert(Failure::NotFound);
+                }
+            }
+            Input::Validate => {
+                let issues = g.validate();
+                for issue in &issues {
+                    match issue {
+                        Issue::NoRoot(frames) => println!("No root among {}", frames.join(", ")),
+                        Issue::SeveralRoots(roots) => println!("Several roots of connected frames: {}", roots.join(", ")),
+                        Issue::Unreachable { frame, root } => println!("{frame} is connected to root {root} only by disabled transforms"),
+                        Issue::Stale { from, to, calibrated } => println!("{from} -> {to} was calibrated on {}", &format_time(*calibrated)[..10]),
+                    }
+                }
+                if issues.is_empty() {
+                    println!("No issues found.");
+                } else {
+                    failure.get_or_insert(Failure::Rejected);
+                }
+            }
+            Input::Tree(root) => {
+                let roots: Vec<String> = match root {
+                    Some(root) => vec![root],
+                    None => g.roots().map(str::to_owned).collect(),
+                };
+                if roots.is_empty() {
+                    println!("No roots. Designate one with set-root.");
+                }
+                for root in roots {
+                    let Some(tree) = g.tree(&root) else {
+                        eprintln!("Unknown frame {}!", root.bold().green());
+                        failure.get_or_insert(Failure::NotFound);
+                        continue;
+                    };
+                    for (depth, frame) in tree {
+                        println!("{}{frame}", "  ".repeat(depth));
+                    }
+                }
+            }
+            Input::Commit(message) => {
+                let rev = g.commit(message).expect("not frozen");
+                println!("Committed revision {}.", rev + 1);
+                dirty = true;
+            }
+            Input::Log => {
+                for (i, rev) in g.revisions().iter().enumerate() {
+                    println!("{:>5}  {}  {} ({} transforms)", i + 1, format_time(rev.time), rev.message.bold(), rev.transforms.len());
+                }
+            }
+            Input::Checkout(rev) => {
+                if g.checkout(rev - 1).is_some() {
+                    println!("Checked out revision {rev}.");
+                    dirty = true;
+                } else {
+                    eprintln!("Could not check out revision {rev}!");
+                    failure.get_or_insert(Failure::NotFound);
+                }
+            }
+            Input::Diff { file, patch } => {
+                let Some(file) = expand_or_report(&file) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                let mut other = TfGraph::new();
+                if !load(&mut other, &file) {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                }
+                let changes = g.diff(&other);
+                if changes.is_empty() {
+                    println!("No differences.");
+                }
+                print_changes(&changes, &units);
+                if let Some(patch) = patch {
+                    let Some(patch) = expand_or_report(&patch) else {
+                        failure.get_or_insert(Failure::Io);
+                        continue;
+                    };
+                    let written = File::create(&patch).map_err(serde_json::Error::io).and_then(|fd| {
+                        let mut writer = std::io::BufWriter::new(fd);
+                        diff::write_patch(&changes, &mut writer)?;
+                        writer.flush().map_err(serde_json::Error::io)
+                    });
+                    match written {
+                        Ok(()) => println!("Patch was written to {patch}."),
+                        Err(e) => {
+                            eprintln!("Could not write {patch}: {e}");
+                            failure.get_or_insert(Failure::Io);
+                        }
+                    }
+                }
+            }
+            Input::ApplyPatch(file) => {
+                let Some(file) = expand_or_report(&file) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                let read = File::open(&file).map_err(serde_json::Error::io)
+                    .and_then(|fd| diff::read_patch(&mut std::io::BufReader::new(fd)));
+                let changes = match read {
+                    Ok(changes) => changes,
+                    Err(e) => {
+                        eprintln!("Could not read {file}: {e}");
+                        failure.get_or_insert(Failure::Io);
+                        continue;
+                    }
+                };
+                match g.apply_patch(&changes) {
+                    Ok(()) => {
+                        print_changes(&changes, &units);
+                        println!("Applied {} changes from {file}.", changes.len());
+                        dirty |= !changes.is_empty();
+                    }
+                    Err(e) => {
+                        eprintln!("Could not apply {file}, nothing was changed: {e}");
+                        failure.get_or_insert(Failure::Rejected);
+                    }
+                }
+            }
+            Input::Import { mapping, file, conv } => {
+                let (Some(mapping), Some(file)) = (expand_or_report(&mapping), expand_or_report(&file)) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                let Some((tfs, warnings)) = import(&mapping, &file) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                report_warnings(warnings);
+                let pairs = tfs.iter().map(|(from, to, _)| (from.clone(), to.clone())).collect_vec();
+                let tfs = tfs.into_iter().map(|(from, to, tf)| (from, to, conv.map_or(tf, |conv| convention::convert(&tf, conv, Convention::Flu))));
+                let results = g.add_tfs(tfs);
+                for ((from, to), e) in pairs.iter().zip(&results).filter_map(|(p, r)| Some((p, r.as_ref().err()?))) {
+                    eprintln!("Could not add {} -> {}: {e}", from.bold().green(), to.bold().green());
+                    failure.get_or_insert(Failure::Rejected);
+                }
+                let added = results.iter().filter(|r| r.is_ok()).count();
+                dirty |= added > 0;
+                println!("Imported {added} of {} transforms from {file}.", results.len());
+            }
+            Input::Fit { from, to, src_file, dst_file } => {
+                let (Some(src), Some(dst)) = (read_points(&src_file, &units), read_points(&dst_file, &units)) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                let Some(tf) = se3::fit_from_points(&src, &dst) else {
+                    eprintln!("Could not fit a transform: need at least 3 non-collinear corresponding points.");
+                    failure.get_or_insert(Failure::Rejected);
+                    continue;
+                };
+                let rms = (src.iter().zip(&dst).map(|(s, d)| (tf * s - d).norm_squared()).sum::<f64>() / src.len() as f64).sqrt();
+                print_tf(&from, &to, &tf, &[&from, &to], &units, matrix);
+                println!("RMS error over {} points: {} {}", src.len(), rms / units.length.meters(), units.length);
+                if add_or_report(&mut g, from, to, tf) {
+                    dirty = true;
+                } else {
+                    failure.get_or_insert(Failure::Rejected);
+                }
+            }
+            Input::Intrinsics { frame, fx, fy, cx, cy } => {
+                cameras.insert(frame, Pinhole::new(fx, fy, cx, cy));
+            }
+            Input::Project { world, camera, point } => {
+                let Some(intrinsics) = cameras.get(&camera) else {
+                    eprintln!("No intrinsics for {}!", camera.bold().green());
+                    failure.get_or_insert(Failure::NotFound);
+                    continue;
+                };
+                if let Err(e) = g.try_query_tf(&world, &camera) {
+                    report_query_error(&g, &world, &camera, e);
+                    failure.get_or_insert(Failure::NotFound);
+                    continue;
                 }
+                let p = na::Point3::from(na::Vector3::from(point) * units.length.meters());
+                match g.project(&world, &camera, intrinsics, &p) {
+                    Some(px) => println!("[u, v]: [{}, {}]", px.x, px.y),
+                    None => {
+                        eprintln!("Point is behind {}!", camera.bold().green());
+                        failure.get_or_insert(Failure::Rejected);
+                    }
+                }
+            }
+            Input::TransformTrajectory { input, output, from, to, side } => {
+                let (tf, _) = match g.try_query_tf(&from, &to) {
+                    Ok(found) => found,
+                    Err(e) => {
+                        report_query_error(&g, &from, &to, e);
+                        failure.get_or_insert(Failure::NotFound);
+                        continue;
+                    }
+                };
+                let Some(traj) = read_trajectory(&input) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                if write_trajectory(&traj.transformed(&tf, side), &output) {
+                    println!("Wrote {} poses to {output}.", traj.poses.len());
+                } else {
+                    failure.get_or_insert(Failure::Io);
+                }
+            }
+            Input::Replay { file, from, to } => {
+                let Some(traj) = read_trajectory(&file) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                for &(stamp, tf) in &traj.poses {
+                    if let Err(e) = g.add_tf_at(from.clone(), to.clone(), tf, stamp) {
+                        eprintln!("{}", e.to_string().bright_red());
+                        failure.get_or_insert(Failure::Rejected);
+                        break;
+                    }
+                    dirty = true;
+                }
+                if let Some((start, end)) = g.sample_span(&from, &to) {
+                    println!("{} -> {} has samples from {start} to {end}.", from.bold().green(), to.bold().green());
+                }
+            }
+            Input::Resample { input, output, at, cubic } => {
+                let Some(traj) = read_trajectory(&input) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                let traj = if cubic { traj.with_interpolation(Interpolation::Cubic) } else { traj };
+                let resampled = match at {
+                    SampleTimes::Rate(rate) => traj.resample_at_rate(rate),
+                    SampleTimes::File(file) => {
+                        let Some(times) = read_timestamps(&file) else {
+                            failure.get_or_insert(Failure::Io);
+                            continue;
+                        };
+                        traj.resample(times)
+                    }
+                };
+                if write_trajectory(&resampled, &output) {
+                    println!("Wrote {} poses to {output}.", resampled.poses.len());
+                } else {
+                    failure.get_or_insert(Failure::Io);
+                }
+            }
+            Input::Trim { input, output, start, end } => {
+                let Some(traj) = read_trajectory(&input) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                let trimmed = traj.trimmed(start, end);
+                if write_trajectory(&trimmed, &output) {
+                    println!("Wrote {} of {} poses to {output}.", trimmed.poses.len(), traj.poses.len());
+                } else {
+                    failure.get_or_insert(Failure::Io);
+                }
+            }
+            Input::Shift { input, output, offset } => {
+                let Some(traj) = read_trajectory(&input) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                let offset = match offset {
+                    ClockOffset::Seconds(seconds) => seconds,
+                    ClockOffset::MatchTo { reference, max } => {
+                        let Some(reference) = read_trajectory(&reference) else {
+                            failure.get_or_insert(Failure::Io);
+                            continue;
+                        };
+                        let Some(offset) = reference.clock_offset(&traj, max) else {
+                            eprintln!("The trajectories do not overlap enough to estimate an offset!");
+                            failure.get_or_insert(Failure::NotFound);
+                            continue;
+                        };
+                        println!("{} {offset:+.4} s", "Estimated offset:".blue().bold());
+                        offset
+                    }
+                };
+                if write_trajectory(&traj.shifted(offset), &output) {
+                    println!("Wrote {} poses to {output}.", traj.poses.len());
+                } else {
+                    failure.get_or_insert(Failure::Io);
+                }
+            }
+            Input::Decimate { input, output, translation, rotation } => {
+                let Some(traj) = read_trajectory(&input) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                let decimated = traj.decimated(translation * units.length.meters(), rotation * units.angle.radians());
+                if write_trajectory(&decimated, &output) {
+                    println!("Wrote {} of {} poses to {output}.", decimated.poses.len(), traj.poses.len());
+                } else {
+                    failure.get_or_insert(Failure::Io);
+                }
+            }
+            Input::Split { input, output, span } => {
+                let Some(traj) = read_trajectory(&input) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                for (i, part) in traj.split_every(span).iter().enumerate() {
+                    let file = numbered_file(&output, i + 1);
+                    if !write_trajectory(part, &file) {
+                        failure.get_or_insert(Failure::Io);
+                        break;
+                    }
+                    println!("Wrote {} poses to {file}.", part.poses.len());
+                }
+            }
+            Input::Report { file, layout } => {
+                if let Layout::Physical { root, .. } = &layout {
+                    if g.frame(root).is_none() {
+                        eprintln!("Unknown frame {}!", root.bold().green());
+                        failure.get_or_insert(Failure::NotFound);
+                        continue;
+                    }
+                }
+                let Some(file) = expand_or_report(&file) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                let written = File::create(&file).and_then(|fd| {
+                    let mut writer = std::io::BufWriter::new(fd);
+                    g.dump_svg_with(&mut writer, &layout)?;
+                    writer.flush()
+                });
+                match written {
+                    Ok(()) => println!("Report was written to {file}."),
+                    Err(e) => {
+                        eprintln!("Could not write {file}: {e}");
+                        failure.get_or_insert(Failure::Io);
+                    }
+                }
+            }
+            Input::Save { file, create_dirs, normalize } => {
+                let Some(file) = expand_or_report(&file) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                if create_dirs {
+                    if let Some(dir) = Path::new(&file).parent().filter(|d| !d.as_os_str().is_empty()) {
+                        if let Err(e) = std::fs::create_dir_all(dir) {
+                            eprintln!("Could not create {}: {e}", dir.display());
+                            failure.get_or_insert(Failure::Io);
+                            continue;
+                        }
+                    }
+                }
+                if normalize {
+                    // Well below any calibration's precision, but above rounding noise.
+                    g.normalize(1e-12);
+                }
+                if save(&mut g, &file) {
+                    name = Some(file);
+                    dirty = false;
+                } else {
+                    failure.get_or_insert(Failure::Io);
+                }
+            }
+            Input::Frames(pattern) => {
+                let Some(pattern) = Pattern::new(&pattern) else {
+                    eprintln!("Invalid pattern {}!", pattern.bold());
+                    failure.get_or_insert(Failure::Parse);
+                    continue;
+                };
+                let frames = g.nodes().filter(|n| pattern.matches(n)).collect_vec();
+                println!("{} {}", format!("Frames ({}):", frames.len()).blue().bold(), frames.join(", "));
+            }
+            Input::Reachable(frame) => match g.reachable_from(&frame) {
+                Some(frames) if frames.is_empty() => println!("No frames are connected to {}.", frame.bold().green()),
+                Some(frames) => println!("{} {}", format!("Connected to {frame} ({}):", frames.len()).blue().bold(), frames.join(", ")),
+                None => {
+                    eprintln!("Unknown frame {}!", frame.bold().green());
+                    failure.get_or_insert(Failure::NotFound);
+                }
+            },
+            Input::Stats => {
+                let disabled = g.edges().filter(|e| !e.enabled).count();
+                println!("{} {}", "Frames:".blue().bold(), g.nodes().count());
+                println!("{} {} ({disabled} disabled)", "Transforms:".blue().bold(), g.edges().count());
+                let memory = g.memory_footprint();
+                println!(
+                    "{} {:.1} KiB (frames {:.1}, transforms {:.1}, index {:.1})",
+                    "Memory:".blue().bold(),
+                    memory.total() as f64 / 1024.0,
+                    memory.nodes as f64 / 1024.0,
+                    memory.edges as f64 / 1024.0,
+                    memory.index as f64 / 1024.0,
+                );
+            }
+            Input::Timeline { file: Some(file), gap } => {
+                let Some(traj) = read_trajectory(&file) else {
+                    failure.get_or_insert(Failure::Io);
+                    continue;
+                };
+                // By default, a gap is any interval over twice the usual one.
+                let gap = gap.or_else(|| traj.median_interval().map(|dt| 2.0 * dt)).unwrap_or(f64::INFINITY);
+                print_timeline(traj.timeline(gap), gap);
+            }
+            Input::Timeline { file: None, gap } => {
+                let mut timelines = g.timelines(gap).peekable();
+                if timelines.peek().is_none() {
+                    println!("No transforms have timed samples.");
+                }
+                for (from, to, gap, timeline) in timelines {
+                    println!("{}", format!("{from} -> {to}:").blue().bold());
+                    print_timeline(timeline, gap);
+                }
+            }
+            Input::Show(true) => match g.header() {
+                Some(header) => {
+                    let fields = [
+                        ("Tool:", header.tool.clone()),
+                        ("Created:", header.created.map(format_time)),
+                        ("Author:", header.author.clone()),
+                        ("Description:", header.description.clone()),
+                    ];
+                    for (label, value) in fields {
+                        if let Some(value) = value {
+                            println!("{} {value}", label.blue().bold());
+                        }
+                    }
+                }
+                None => println!("The graph has no header."),
+            },
+            Input::SetHeader { field, value } => {
+                let mut header = g.header().cloned().unwrap_or_default();
+                match field {
+                    HeaderField::Author => header.author = value,
+                    HeaderField::Description => header.description = value,
+                }
+                if g.header() != Some(&header) {
+                    g.set_header(Some(header));
+                    dirty = true;
+                }
+            }
+            Input::ShowGroup(group) => {
+                let frames = g.group(&group);
+                if frames.is_empty() {
+                    eprintln!("Unknown group {}!", group.bold());
+                    failure.get_or_insert(Failure::NotFound);
+                    continue;
+                }
+                println!("{} {}", format!("Frames in {group}:").blue().bold(), frames.join(", "));
+                println!("{}", "Transforms:".blue().bold());
+                for e in g.edges().filter(|e| frames.contains(&e.from) || frames.contains(&e.to)) {
+                    println!("{}", transform_line(&e));
+                }
+            }
+            Input::Show(false) => {
+                println!("{} {}", "Nodes:".blue().bold(), g.nodes().join(", "));
+                println!("{}", "Transforms:".blue().bold());
+                for e in g.edges() {
+                    println!("{}", transform_line(&e));
+                }
+                if g.groups().next().is_some() {
+                    println!("{} {}", "Groups:".blue().bold(), g.groups().join(", "));
+                }
+                if let Some(max_age) = g.max_age() {
+                    println!("{} {} days", "Max age of calibrations:".blue().bold(), max_age.as_secs_f64() / 86400.0);
+                }
+                if !vars.is_empty() {
+                    println!("{} {}", "Variables:".blue().bold(), vars.keys().join(", "));
+                }
+                if !watches.is_empty() {
+                    println!("{} {}", "Watching:".blue().bold(), watches.iter().map(|w| format!("{} -> {}", w.from, w.to)).join(", "));
+                }
+            }
+        }
+
+        report_watches(&g, &mut watches, &units, matrix);
+    }
+
+    match failure {
+        Some(f) if !interactive => f.into(),
+        _ => ExitCode::SUCCESS,
+    }
+}
+
+fn report_warnings(warnings: impl IntoIterator<Item = Warning>) {
+    for warning in warnings {
+        eprintln!("{} {warning}", "Warning:".yellow());
+    }
+}
+
+fn print_timeline(timeline: Timeline, gap: f64) {
+    println!("{} {}", "Samples:".blue().bold(), timeline.samples);
+    println!("{} {:.3} s", "Span:".blue().bold(), timeline.span);
+    println!("{} {:.2} Hz", "Rate:".blue().bold(), timeline.rate);
+    println!("{} {} over {gap:.3} s", "Gaps:".blue().bold(), timeline.gaps.len());
+    for (start, end) in timeline.gaps {
+        println!("  {start:.3} .. {end:.3} ({:.3} s)", end - start);
+    }
+}
+
+/// Print the watched transforms that changed since they were last printed.
+fn report_watches(g: &TfGraph, watches: &mut [Watch], units: &Units, matrix: MatrixFormat) {
+    for w in watches {
+        let now = query_owned(g, &w.from, &w.to);
+        if now == w.last {
+            continue;
+        }
+        match &now {
+            Some((tf, path)) => print_tf(&w.from, &w.to, tf, path, units, matrix),
+            None => println!("No transform between {} and {} anymore.", w.from.bold().green(), w.to.bold().green()),
+        }
+        w.last = now;
+    }
+}
+
+fn expand_or_report(path: &str) -> Option<String> {
+    expand_path(path).inspect_err(|e| eprintln!("Could not expand {path}: {e}")).ok()
+}
+
+/// Expand a leading `~` or `~user`, and `$VAR` or `${VAR}` anywhere in `path`.
+fn expand_path(path: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = path;
+    if let Some(tilde) = path.strip_prefix('~') {
+        let (user, tail) = tilde.split_at(tilde.find('/').unwrap_or(tilde.len()));
+        let home = if user.is_empty() { std::env::var("HOME").ok() } else { home_of(user) };
+        out += &home.ok_or_else(|| format!("unknown home directory for ~{user}"))?;
+        rest = tail;
+    }
+
+    while let Some(i) = rest.find('$') {
+        out += &rest[..i];
+        let after = &rest[i + 1..];
+        let (var, tail) = if let Some(braced) = after.strip_prefix('{') {
+            let end = braced.find('}').ok_or("unclosed ${")?;
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+            after.split_at(end)
+        };
+        if var.is_empty() {
+            // A lone `$` is kept
+            out.push('$');
+        } else {
+            out += &std::env::var(var).map_err(|_| format!("${var} is not set"))?;
+        }
+        rest = tail;
+    }
+    out += rest;
+    Ok(out)
+}
+
+/// Home directory of `user`, from `/etc/passwd`.
+fn home_of(user: &str) -> Option<String> {
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines()
+        .map(|line| line.split(':').collect_vec())
+        .find(|fields| fields.len() >= 6 && fields[0] == user)
+        .map(|fields| fields[5].to_owned())
+}
+
+/// Number of most recent commands listed by `history`.
+const HISTORY_SHOWN: usize = 20;
+
+/// Look up `!!` (the last command) or `!N` (the `N`th command, from 1) in the history.
+///
+/// Returns `None` for other lines, and `Some(None)` if there is no such command.
+fn recall<'h>(line: &str, history: &'h [String]) -> Option<Option<&'h str>> {
+    let n = line.trim().strip_prefix('!')?;
+    let entry = if n == "!" {
+        history.last()
+    } else {
+        n.parse::<usize>().ok().and_then(|n| history.get(n.checked_sub(1)?))
+    };
+    Some(entry.map(String::as_str))
+}
+
+/// Where aliases defined at startup are read from: `tfgen/aliases` in `$XDG_CONFIG_HOME`, or in `~/.config`.
+fn aliases_file() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".config")))?;
+    Some(config.join("tfgen").join("aliases"))
+}
+
+/// Read `alias NAME = <command>` lines, skipping blank lines and `#` comments. A missing file defines none.
+fn read_aliases(file: &Path) -> BTreeMap<String, String> {
+    let text = match std::fs::read_to_string(file) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return BTreeMap::new(),
+        Err(e) => {
+            eprintln!("Could not read {}: {e}", file.display());
+            return BTreeMap::new();
+        }
+    };
+    let mut aliases = BTreeMap::new();
+    for (i, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty() && !l.trim_start().starts_with('#')) {
+        match parse_input(line.trim()) {
+            Some(Input::Alias { name, command: Some(command) }) => {
+                aliases.insert(name, command);
+            }
+            _ => eprintln!("{}:{}: expected alias NAME = <command>", file.display(), i + 1),
+        }
+    }
+    aliases
+}
+
+/// Replace a leading alias with its command. Expanded commands are not expanded again.
+fn expand_alias<'a>(line: &'a str, aliases: &BTreeMap<String, String>) -> Cow<'a, str> {
+    let line = line.trim_start();
+    let (first, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    match aliases.get(first) {
+        Some(command) if rest.is_empty() => Cow::Owned(command.clone()),
+        Some(command) => Cow::Owned(format!("{command} {rest}")),
+        None => Cow::Borrowed(line),
+    }
+}
+
+/// A transform as listed by `show`, with its state if not the default.
+fn transform_line(e: &EdgeView) -> String {
+    let mut s = format!("  {} -> {}: {}", e.from, e.to, se3::DisplaySE3(e.tf));
+    if !e.enabled {
+        s += " (disabled)";
+    }
+    if e.dynamic {
+        s += " (dynamic)";
+    }
+    if e.cost != 1.0 {
+        s += &format!(" (cost {})", e.cost);
+    }
+    if let Some(time) = e.calibrated {
+        s += &format!(" (calibrated {})", &format_time(time)[..10]);
+    }
+    s
+}
+
+/// Explain why `from -> to` could not be queried, suggesting similar names for an unknown frame.
+fn report_query_error(g: &TfGraph, from: &str, to: &str, error: QueryError) {
+    match error {
+        QueryError::NotConnected => eprintln!("{} and {} are not connected!", from.bold().green(), to.bold().green()),
+        QueryError::UnknownFrame(frame) => match &g.similar_frames(&frame)[..] {
+            [] => eprintln!("Unknown frame {}!", frame.bold().green()),
+            similar => eprintln!("Unknown frame {}! Did you mean {}?", frame.bold().green(), similar.iter().take(3).join(", ")),
+        },
+    }
+}
+
+/// Add a transform, reporting why it was rejected, if it was.
+fn add_or_report(g: &mut TfGraph, from: String, to: String, tf: SE3) -> bool {
+    match g.add_tf(from.clone(), to.clone(), tf) {
+        Ok(()) => true,
+        Err(tfgen::Error::Cycle) => {
+            eprintln!("Could not add cyclic transform {} -> {}", from.bold().green(), to.bold().green());
+            false
+        }
+        Err(e) => {
+            eprintln!("{}", e.to_string().bright_red());
+            false
+        }
+    }
+}
+
+/// Read transforms from a foreign JSON `file`, as described by the `mapping` file.
+fn import(mapping: &str, file: &str) -> Option<(Imported, Vec<Warning>)> {
+    let read = |file: &str| -> Option<serde_json::Value> {
+        let fd = File::open(file).inspect_err(|_| eprintln!("Could not open {file}!")).ok()?;
+        serde_json::from_reader(std::io::BufReader::new(fd)).inspect_err(|e| eprintln!("Could not parse {file}: {e}")).ok()
+    };
+    let mapping: FieldMapping = serde_json::from_value(read(mapping)?)
+        .inspect_err(|e| eprintln!("Invalid mapping: {e}"))
+        .ok()?;
+    mapping.import_with_warnings(&read(file)?).inspect_err(|e| eprintln!("Could not import {file}: {e}")).ok()
+}
+
+/// Read one `x, y, z` point per line. A first line that is not a point is skipped as a header.
+fn read_points(file: &str, units: &Units) -> Option<Vec<na::Point3<f64>>> {
+    let file = expand_or_report(file)?;
+    let text = std::fs::read_to_string(&file).inspect_err(|_| eprintln!("Could not open {file}!")).ok()?;
+    let mut points = Vec::new();
+    for (i, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        match parse_csv::<f64>(line, ',').ok().filter(|v| v.len() == 3) {
+            Some(v) => points.push(na::Point3::new(v[0], v[1], v[2]) * units.length.meters()),
+            None if i == 0 => {}
+            None => {
+                eprintln!("{file}:{}: expected x, y, z", i + 1);
+                return None;
+            }
+        }
+    }
+    Some(points)
+}
+
+fn read_trajectory(file: &str) -> Option<Trajectory> {
+    let file = expand_or_report(file)?;
+    let fd = File::open(&file).inspect_err(|_| eprintln!("Could not open {file}!")).ok()?;
+    Trajectory::read_tum(std::io::BufReader::new(fd)).inspect_err(|e| eprintln!("Could not read {file}: {e}")).ok()
+}
+
+/// Read the first number of each line, skipping blank lines and `#` comments, e.g. from a TUM file.
+fn read_timestamps(file: &str) -> Option<Vec<f64>> {
+    let file = expand_or_report(file)?;
+    let text = std::fs::read_to_string(&file).inspect_err(|_| eprintln!("Could not open {file}!")).ok()?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+        .map(|(i, l)| {
+            l.split_whitespace().next().and_then(|t| t.parse().ok())
+                .or_else(|| { eprintln!("{file}:{}: expected a timestamp", i + 1); None })
+        })
+        .collect()
+}
+
+fn write_trajectory(traj: &Trajectory, file: &str) -> bool {
+    let Some(file) = expand_or_report(file) else {
+        return false;
+    };
+    let written = File::create(&file).and_then(|fd| {
+        let mut writer = std::io::BufWriter::new(fd);
+        traj.write_tum(&mut writer)?;
+        writer.flush()
+    });
+    written.inspect_err(|e| eprintln!("Could not write {file}: {e}")).is_ok()
+}
+
+/// `file` with `_n` inserted before its extension, e.g. `day_2.tum`.
+fn numbered_file(file: &str, n: usize) -> String {
+    let path = Path::new(file);
+    match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) => path.with_file_name(format!("{}_{n}.{}", stem.to_string_lossy(), ext.to_string_lossy())).to_string_lossy().into_owned(),
+        _ => format!("{file}_{n}"),
+    }
+}
+
+/// A small sensor rig to try commands on: an IMU and a lidar on `base_link`, and two cameras on the lidar.
+fn example_rig() -> TfGraph {
+    let mut g = TfGraph::new();
+    let transforms = [
+        ("imu", "base_link", [0.0, 0.0, 0.3, 0.0, 0.0, 0.0]),
+        ("lidar", "base_link", [0.2, 0.0, 1.5, 0.0, 0.0, 0.0]),
+        ("cam_left", "lidar", [0.1, 0.3, -0.2, 0.0, 0.0, 0.3]),
+        ("cam_right", "lidar", [0.1, -0.3, -0.2, 0.0, 0.0, -0.3]),
+    ];
+    for (from, to, tf) in transforms {
+        let tf = se3::from_array(&tf).expect("translation and roll, pitch, yaw");
+        g.add_tf(from.to_owned(), to.to_owned(), tf).expect("the example rig is a tree");
+    }
+    g.set_root("base_link").expect("frame exists");
+    g.set_header(Some(tfgen::Header {
+        description: Some("Example rig: an IMU and a lidar on base_link, and two cameras on the lidar".to_owned()),
+        ..Default::default()
+    }));
+    g
+}
+
+fn print_example_intro(g: &TfGraph) {
+    println!("{} {}", "Loaded the example rig:".blue().bold(), g.nodes().join(", "));
+    println!("Try {}, {} or {}.", "tree".bold(), "cam_left -> cam_right".bold(), "show".bold());
+}
+
+fn load(g: &mut TfGraph, file: &str) -> bool {
+    let mut fd = match File::open(file) {
+        Ok(fd) => fd,
+        Err(e) => {
+            debug!(file, error = %e, "open failed");
+            eprintln!("Could not open {file}!");
+            return false;
+        }
+    };
+    debug!(file, "loading");
+    let loaded = if is_yaml(Path::new(file)) { g.load_yaml(&mut fd) } else { g.load_json(&mut fd) };
+    match loaded {
+        Ok(()) => {
+            println!("Loaded transforms from {file}.");
+            true
+        }
+        Err(e) => {
+            eprintln!("Could not load file: {e}");
+            false
+        }
+    }
+}
+
+/// Whether `file` is to be read and written as YAML rather than JSON, by its extension.
+fn is_yaml(file: &Path) -> bool {
+    file.extension().is_some_and(|ext| ext == "yaml" || ext == "yml")
+}
+
+/// Load a JSON or YAML graph file, without printing anything.
+fn load_file(g: &mut TfGraph, file: &Path) -> Result<(), tfgen::Error> {
+    let mut fd = File::open(file)?;
+    if is_yaml(file) { g.load_yaml(&mut fd) } else { g.load_json(&mut fd) }
+}
+
+/// Save to `file`, recording this program and the current time in the header unless it names them already.
+///
+/// A frozen graph, i.e. in read-only mode, is never saved.
+fn save(g: &mut TfGraph, file: &str) -> bool {
+    if g.is_frozen() {
+        eprintln!("{}", "Read-only mode: the graph cannot be saved!".bright_red());
+        return false;
+    }
+    let mut header = g.header().cloned().unwrap_or_default();
+    header.tool.get_or_insert_with(|| format!("tfgen {}", env!("CARGO_PKG_VERSION")));
+    header.created.get_or_insert_with(|| SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()));
+    g.set_header(Some(header));
+    let good = match File::create(file) {
+        Ok(mut fd) => {
+            debug!(file, "saving");
+            let written = if is_yaml(Path::new(file)) {
+                g.dump_yaml(&mut fd).map_err(|e| e.to_string())
+            } else {
+                g.dump_json(&mut fd).map_err(|e| e.to_string())
+            };
+            written.inspect_err(|e| debug!(file, error = %e, "write failed")).is_ok()
+        }
+        Err(e) => {
+            debug!(file, error = %e, "create failed");
+            false
+        }
+    };
+    if good {
+        println!("Transform graph was saved to {file}.");
+    } else {
+        eprintln!("Error saving transform graph.");
+    }
+    good
+}
+
+/// What the REPL waits for.
+enum Event {
+    /// A line of input, or `None` at the end of input.
+    Line(Option<String>),
+    /// The watched file was written.
+    FileChanged,
+}
+
+/// Read input lines on a separate thread, so that the REPL can also wait for file changes.
+///
+/// At a terminal, reading goes on after the end of input (Ctrl-D), to answer the save prompt. An error
+/// ends the input.
+fn read_lines(sender: Sender<Event>) {
+    let interactive = stdin().is_terminal();
+    std::thread::spawn(move || loop {
+        let mut line = String::new();
+        let line = match stdin().read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => Some(line),
+            Err(e) => {
+                eprintln!("Could not read input: {e}");
+                let _ = sender.send(Event::Line(None));
+                break;
+            }
+        };
+        let done = line.is_none() && !interactive;
+        if sender.send(Event::Line(line)).is_err() || done {
+            break;
+        }
+    });
+}
+
+/// Send `Event::FileChanged` whenever `file` is written or replaced.
+fn watch_file(file: &str, sender: Sender<Event>) -> notify::Result<RecommendedWatcher> {
+    let path = std::path::absolute(file).map_err(notify::Error::io)?;
+    let (Some(dir), Some(file_name)) = (path.parent(), path.file_name().map(ToOwned::to_owned)) else {
+        return Err(notify::Error::generic("not a file"));
+    };
+    // Watch the directory, since editors and tools often replace the file instead of writing to it.
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if (event.kind.is_create() || event.kind.is_modify()) && event.paths.iter().any(|p| p.file_name() == Some(&file_name)) {
+            let _ = sender.send(Event::FileChanged);
+        }
+    })?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    Ok(watcher)
+}
+
+/// Reload a watched file and print what changed. Returns `false` if nothing changed.
+///
+/// A file that cannot be loaded is skipped, as it may be partially written.
+fn reload(g: &mut TfGraph, file: &str, dirty: bool, units: &Units) -> bool {
+    // Loading into a copy keeps the session settings, such as the buffer duration.
+    let mut new = g.clone();
+    new.take_warnings();
+    new.set_collect_warnings(true);
+    if load_file(&mut new, Path::new(file)).is_err() {
+        debug!(file, "could not reload");
+        return false;
+    }
+    let changes = g.diff(&new);
+    if changes.is_empty() {
+        return false;
+    }
+    println!();
+    println!("{}", format!("Reloaded {file}:").blue().bold());
+    if dirty {
+        println!("{}", "Unsaved changes were discarded.".bright_red());
+    }
+    report_warnings(new.take_warnings());
+    print_changes(&changes, units);
+    *g = new;
+    true
+}
+
+/// Format seconds since the Unix epoch as a UTC date and time, e.g. `2024-03-01 12:00:00`.
+fn format_time(secs: u64) -> String {
+    // Civil date from days since the epoch, after Howard Hinnant's `civil_from_days`.
+    let (days, rem) = ((secs / 86400) as i64, secs % 86400);
+    let z = days + 719468;
+    let (era, doe) = (z.div_euclid(146097), z.rem_euclid(146097));
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}", rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+/// Seconds since the Unix epoch of a UTC date, e.g. `2024-03-01`.
+fn parse_date(s: &str) -> Option<u64> {
+    let mut parts = s.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // Inverse of `format_time`, after Howard Hinnant's `days_from_civil`.
+    let y = if month <= 2 { year - 1 } else { year };
+    let (era, yoe) = (y.div_euclid(400), y.rem_euclid(400));
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    u64::try_from(days * 86400).ok()
+}
+
+/// A duration in days (`90d`) or hours (`12h`).
+fn parse_age(s: &str) -> Option<Duration> {
+    let hours = if let Some(days) = s.strip_suffix('d') {
+        days.parse::<u64>().ok()? * 24
+    } else {
+        s.strip_suffix('h')?.parse().ok()?
+    };
+    Some(Duration::from_secs(hours * 3600))
+}
+
+/// Print transforms that were added (`+`), removed (`-`) or updated (`~`).
+fn print_changes(changes: &[Change<SE3>], units: &Units) {
+    for change in changes {
+        match change {
+            Change::Added(t) => println!("+ {}", add_line(&t.from, &t.to, &t.tf, units)),
+            Change::Removed { from, to } => println!("- {from} -> {to}"),
+            Change::Updated(t) => println!("~ {}", add_line(&t.from, &t.to, &t.tf, units)),
+        }
+    }
+}
+
+/// Offer to save unsaved changes before quitting. Returns `false` if quitting was cancelled.
+///
+/// Nothing is offered in read-only mode, where the graph cannot be saved.
+fn confirm_quit(g: &mut TfGraph, file: Option<&str>, events: &Receiver<Event>) -> bool {
+    if g.is_frozen() {
+        return true;
+    }
+    let read_answer = |question: &str| {
+        print!("{question}");
+        stdout().flush().expect("Error writing prompt");
+        // None on EOF
+        loop {
+            match events.recv() {
+                Ok(Event::Line(answer)) => return answer.map(|a| a.trim().to_owned()),
+                Ok(Event::FileChanged) => {}
+                Err(_) => return None,
+            }
+        }
+    };
+
+    loop {
+        let question = match file {
+            Some(file) => format!("Save changes to {file}? [y]es / [n]o / [c]ancel: "),
+            None => "Save changes? [y]es / [n]o / [c]ancel: ".to_owned(),
+        };
+        let Some(answer) = read_answer(&question) else {
+            return true;
+        };
+        match answer.as_str() {
+            "y" | "yes" => {
+                let file = match file {
+                    Some(file) => file.to_owned(),
+                    None => read_answer("File name: ").unwrap_or_default(),
+                };
+                if !file.is_empty() && save(g, &file) {
+                    return true;
+                }
+            }
+            "n" | "no" => return true,
+            "c" | "cancel" => return false,
+            _ => {}
+        }
+    }
+}
+
+/// Prompt showing the graph name, its size, and `*` if there are unsaved changes.
+fn print_prompt(g: &TfGraph, file: Option<&str>, dirty: bool) {
+    let name = file
+        .and_then(|f| Path::new(f).file_stem())
+        .map_or("unnamed".into(), |s| s.to_string_lossy());
+    print!("{} ({} frames, {} tfs){}> ", name.bold(), g.nodes().count(), g.edges().count(), if dirty { "*" } else { "" });
+    stdout().flush().expect("Error writing prompt");
+}
+
+fn query_owned(g: &TfGraph, from: &str, to: &str) -> Option<(SE3, Vec<String>)> {
+    g.query_tf(from, to).map(|(tf, path)| (tf, path.into_iter().map(str::to_owned).collect()))
+}
+
+fn print_tf(from: &str, to: &str, tf: &SE3, path: &[impl AsRef<str>], units: &Units, matrix: MatrixFormat) {
+    let mut mat: na::Matrix4<f64> = na::convert(*tf);
+    mat.fixed_view_mut::<3, 1>(0, 3).unscale_mut(units.length.meters());
+    println!("Transform from {} to {}: (Path: {})", from.bold().green(), to.bold().green(), path.iter().map(AsRef::as_ref).join(" -> "));
+    match matrix {
+        MatrixFormat::Pretty => print!("{mat}"),
+        _ => println!("{}", matrix.format(&mat)),
+    }
+    println!("[x,y,z, qx,qy,qz,qw]: {:?} ({})", units.to7(tf), units.length);
+    let (r, p, y) = tf.rotation.euler_angles();
+    println!("[roll, pitch, yaw]: {:?} ({})", [r, p, y].map(|a| a / units.angle.radians()), units.angle);
+}
+
+/// An add command reproducing `tf` in the current units. Values are printed in full, so that parsing them gives back `tf`.
+fn add_line(from: &str, to: &str, tf: &SE3, units: &Units) -> String {
+    format!("{from} -> {to} : {}", units.to7(tf).iter().join(", "))
+}
+
+/// A `static_transform_publisher` command publishing the queried transform `from -> to`.
+fn ros_line(from: &str, to: &str, tf: &SE3, convention: EdgeConvention) -> String {
+    // ROS publishes the pose of the child frame in the parent frame.
+    let (parent, child) = match convention {
+        EdgeConvention::SourceToTarget => (to, from),
+        EdgeConvention::TargetToSource => (from, to),
+    };
+    let [x, y, z, qx, qy, qz, qw] = tf.to7();
+    format!("ros2 run tf2_ros static_transform_publisher --x {x} --y {y} --z {z} --qx {qx} --qy {qy} --qz {qz} --qw {qw} --frame-id {parent} --child-frame-id {child}")
+}
+
+fn parse_pair(s: &str) -> Option<(String, String)> {
+    let (src, dst) = s.split_once("->")?;
+    Some((src.trim().to_owned(), dst.trim().to_owned()))
+}
+
+/// Split off a trailing `--conv <convention>` option.
+fn split_conv(s: &str) -> Option<(&str, Option<Convention>)> {
+    match s.split_once("--conv ") {
+        Some((rem, conv)) => Some((rem, Some(conv.trim().parse().ok()?))),
+        None => Some((s, None)),
+    }
+}
+
+/// A frame name pattern: a glob, or a regex written as `/regex/`.
+enum Pattern {
+    Glob(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    fn new(s: &str) -> Option<Self> {
+        match s.strip_prefix('/').and_then(|s| s.strip_suffix('/')) {
+            Some(re) => Regex::new(re).ok().map(Self::Regex),
+            None => Some(Self::Glob(s.to_owned())),
+        }
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::Glob(glob) => glob_match(glob, name),
+            Self::Regex(re) => re.is_match(name),
+        }
+    }
+}
+
+fn is_glob(s: &str) -> bool {
+    s.contains(['*', '?'])
+}
+
+/// Whether `s` is a glob or a regex, rather than a frame name.
+fn is_pattern(s: &str) -> bool {
+    is_glob(s) || (s.len() > 1 && s.starts_with('/') && s.ends_with('/'))
+}
+
+/// Match a frame name against a pattern, where `*` matches any run of characters and `?` any one character.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let (p, n): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+    let (mut i, mut j) = (0, 0);
+    // Position after the last `*`, and the name position it is matched up to.
+    let mut star: Option<(usize, usize)> = None;
+    while j < n.len() {
+        match p.get(i) {
+            Some('*') => {
+                star = Some((i + 1, j));
+                i += 1;
             }
-            Input::Load(file) => {
-                let Ok(mut fd) = File::open(&file) else {
-                    eprintln!("Could not open {file}!");
-                    continue;
-                };
-                if g.load_json(&mut fd).is_ok() {
-                    println!("Loaded transforms from {file}.");
-                }
-                else {
-                    eprintln!("Could not load file.");
-                }
+            Some(&c) if c == '?' || c == n[j] => {
+                i += 1;
+                j += 1;
             }
-            Input::Save(file) => {
-                let good =
-                    if let Ok(mut fd) = File::create(&file) {
-                        g.dump_json(&mut fd).is_ok()
-                    } else {
-                        false
-                    };
-                if good {
-                    println!("Transform graph was saved to {file}.");
-                } else {
-                    eprintln!("Error saving transform graph.");
+            _ => match star {
+                // Let the last `*` match one more character
+                Some((si, sj)) => {
+                    i = si;
+                    j = sj + 1;
+                    star = Some((si, sj + 1));
                 }
-            }
-            Input::Show => {
-                println!("{} {}", "Nodes:".blue().bold(), g.nodes().join(", "));
-                println!("{} {}", "Transforms:".blue().bold(), g.transforms().map(|(a, b)| format!("{} -> {}", a, b)).join(", "));
-            }
+                None => return false,
+            },
         }
     }
+    p[i..].iter().all(|&c| c == '*')
+}
 
-    ExitCode::SUCCESS
+/// Variable names start with a letter or `_`, and cannot be `rand` or `random`.
+fn is_var_name(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !matches!(s, "rand" | "random")
 }
 
 fn parse_csv<T: FromStr>(s: &str, delim: char) -> Result<Vec<T>, <T as FromStr>::Err> {
@@ -84,30 +1896,695 @@ fn parse_csv<T: FromStr>(s: &str, delim: char) -> Result<Vec<T>, <T as FromStr>:
         .collect()
 }
 
+/// Units of transforms entered and printed in the REPL. Transforms are stored in meters and radians.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct Units {
+    length: Length,
+    angle: Angle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum Length {
+    #[default]
+    M,
+    Cm,
+    Mm,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum Angle {
+    #[default]
+    Rad,
+    Deg,
+}
+
+impl Length {
+    fn meters(self) -> f64 {
+        match self {
+            Length::M => 1.0,
+            Length::Cm => 0.01,
+            Length::Mm => 0.001,
+        }
+    }
+}
+
+impl Angle {
+    fn radians(self) -> f64 {
+        match self {
+            Angle::Rad => 1.0,
+            Angle::Deg => 1f64.to_radians(),
+        }
+    }
+}
+
+impl std::fmt::Display for Length {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self { Length::M => "m", Length::Cm => "cm", Length::Mm => "mm" })
+    }
+}
+
+impl std::fmt::Display for Angle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self { Angle::Rad => "rad", Angle::Deg => "deg" })
+    }
+}
+
+impl Units {
+    /// Convert values in any of the forms accepted by `se3::from_array`.
+    fn se3_from(&self, values: &[f64]) -> Option<SE3> {
+        let mut values = values.to_vec();
+        let (lengths, angles): (&[usize], &[usize]) = match values.len() {
+            3 => (&[0, 1, 2], &[]),
+            6 => (&[0, 1, 2], &[3, 4, 5]),
+            7 => (&[0, 1, 2], &[]),
+            12 | 16 => (&[3, 7, 11], &[]),
+            _ => (&[], &[]),
+        };
+        lengths.iter().for_each(|&i| values[i] *= self.length.meters());
+        angles.iter().for_each(|&i| values[i] *= self.angle.radians());
+        se3::from_array(&values)
+    }
+
+    fn to7(&self, tf: &SE3) -> [f64; 7] {
+        let mut a = tf.to7();
+        a[..3].iter_mut().for_each(|x| *x /= self.length.meters());
+        a
+    }
+}
+
+/// How `print_tf` prints the 4x4 matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum MatrixFormat {
+    /// nalgebra's boxed layout.
+    #[default]
+    Pretty,
+    /// Comma-separated, row-major.
+    Flat,
+    /// A `numpy.array` literal.
+    Numpy,
+    /// A C/C++ array initializer.
+    C,
+}
+
+impl MatrixFormat {
+    fn format(self, mat: &na::Matrix4<f64>) -> String {
+        let rows = || mat.row_iter().map(|row| row.iter().map(|x| format!("{x:?}")).join(", "));
+        match self {
+            MatrixFormat::Pretty => mat.to_string(),
+            MatrixFormat::Flat => rows().join(", "),
+            MatrixFormat::Numpy => format!("np.array([[{}]])", rows().join("],\n          [")),
+            MatrixFormat::C => format!("{{{{{}}}}}", rows().join("},\n {")),
+        }
+    }
+}
+
+/// A transform argument: values as typed, `random`, or the name of a variable set with `let`.
+#[derive(PartialEq, Debug)]
+enum TfArg {
+    Values(Vec<f64>),
+    Random,
+    Var(String),
+}
+
+impl Input {
+    /// Whether the command changes the graph, replaces it, or writes it back to a file.
+    fn writes(&self) -> bool {
+        matches!(
+            self,
+            Input::Add { .. }
+                | Input::Direction(Some(_))
+                | Input::Orientation(Some(_))
+                | Input::MatchNames(Some(_))
+                | Input::MaxAge(_)
+                | Input::Calibrated { .. }
+                | Input::SetEnabled { .. }
+                | Input::SetCost { .. }
+                | Input::Reset
+                | Input::Import { .. }
+                | Input::Fit { .. }
+                | Input::WatchFile(Some(_))
+                | Input::SetRoot { .. }
+                | Input::SetHeader { .. }
+                | Input::Tag { .. }
+                | Input::RemoveGroup(_)
+                | Input::RemoveTf { .. }
+                | Input::RemoveFrame(_)
+                | Input::RemoveMatching(_)
+                | Input::Replay { .. }
+                | Input::Commit(_)
+                | Input::Checkout(_)
+                | Input::ApplyPatch(_)
+                | Input::Example
+                | Input::Load(_)
+                | Input::LoadDot(_)
+                | Input::Save { .. }
+        )
+    }
+}
+
+/// A header field that can be set with `header`.
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum HeaderField {
+    Author,
+    Description,
+}
+
+/// How to print the result of a query.
+#[derive(PartialEq, Debug)]
+enum QueryOutput {
+    Transform,
+    /// An add command.
+    Add,
+    /// A ROS 2 static transform publisher command.
+    Ros,
+    /// A JSON object with `from`, `to`, `tf` and `path`, or the `QueryError`.
+    Json,
+}
+
+/// Timestamps to resample a trajectory at.
+#[derive(PartialEq, Debug)]
+enum SampleTimes {
+    /// Fixed rate, in Hz.
+    Rate(f64),
+    /// The timestamps listed in a file.
+    File(String),
+}
+
+/// How far to shift the timestamps of a trajectory.
+#[derive(PartialEq, Debug)]
+enum ClockOffset {
+    /// A known offset, in seconds.
+    Seconds(f64),
+    /// The offset that aligns it with a reference trajectory, searching up to `max` seconds either way.
+    MatchTo { reference: String, max: f64 },
+}
+
 #[derive(PartialEq, Debug)]
 enum Input {
-    Add { from: String, to: String, tf: SE3 },
-    Query { from: String, to: String }, // Could use &str here.
+    /// `conv` is the axis convention of the frames in `tf`, to be converted to FLU.
+    Add { from: String, to: String, tf: TfArg, conv: Option<Convention> },
+    Units { length: Option<Length>, angle: Option<Angle> },
+    Direction(Option<EdgeConvention>),
+    /// Store transforms in canonical direction (`true`) or as added.
+    Orientation(Option<bool>),
+    Matrix(MatrixFormat),
+    /// Calibrations older than this are stale, or none with `None`.
+    MaxAge(Option<Duration>),
+    /// Show how long timed samples are kept, or set it.
+    Buffer(Option<Duration>),
+    Interpolation(Option<Interpolation>),
+    Extrapolation(Option<Extrapolation>),
+    /// Set the calibration time of a transform, or forget it with `None`.
+    Calibrated { from: String, to: String, time: Option<u64> },
+    /// Define an alias, or remove it if `command` is `None`.
+    Alias { name: String, command: Option<String> },
+    Aliases,
+    History,
+    MatchNames(Option<NameMatching>),
+    Let { name: String, from: String, to: String },
+    Query { from: String, to: String, output: QueryOutput }, // Could use &str here.
+    /// Query with timed transforms interpolated at `stamp`.
+    QueryAt { from: String, to: String, stamp: f64 },
+    /// Frame names are glob patterns.
+    QueryMatching { from: String, to: String, output: QueryOutput },
+    SetEnabled { from: String, to: String, enabled: bool },
+    SetCost { from: String, to: String, cost: f64 },
+    Bench { from: String, to: String, iterations: u32 },
+    Watch { from: String, to: String },
+    Unwatch { from: String, to: String },
     Reset,
     Quit,
+    ForceQuit,
     Help,
-    Show,
+    /// Print the graph, or only its header with `true`.
+    Show(bool),
+    /// Set a field of the header, or clear it with `None`.
+    SetHeader { field: HeaderField, value: Option<String> },
+    Stats,
+    /// Timing of a trajectory file, or of the timed transforms without one, with gaps longer than the
+    /// threshold in seconds.
+    Timeline { file: Option<String>, gap: Option<f64> },
+    Import { mapping: String, file: String, conv: Option<Convention> },
+    Report { file: String, layout: Layout },
+    Reachable(String),
+    /// A glob, or a regex between slashes.
+    Frames(String),
+    Fit { from: String, to: String, src_file: String, dst_file: String },
+    Intrinsics { frame: String, fx: f64, fy: f64, cx: f64, cy: f64 },
+    /// `point` is in the current length unit.
+    Project { world: String, camera: String, point: [f64; 3] },
+    TransformTrajectory { input: String, output: String, from: String, to: String, side: Side },
+    /// Add the poses of a trajectory as timed samples of a transform.
+    Replay { file: String, from: String, to: String },
+    Resample { input: String, output: String, at: SampleTimes, cubic: bool },
+    /// Keep the poses from `start` to `end`.
+    Trim { input: String, output: String, start: f64, end: f64 },
+    /// Write windows of `span` seconds to numbered files named after `output`.
+    Split { input: String, output: String, span: f64 },
+    /// Move a trajectory onto another clock.
+    Shift { input: String, output: String, offset: ClockOffset },
+    /// Drop poses reproduced by interpolation, with tolerances in the current units.
+    Decimate { input: String, output: String, translation: f64, rotation: f64 },
+    /// Rank transforms by how much they break loops, with tolerances in the current units, if given.
+    DetectOutliers(Option<(f64, f64)>),
+    /// Frames and transforms of a group.
+    ShowGroup(String),
+    /// Add frames to a group, or remove them from it.
+    Tag { frames: Vec<String>, group: String, tag: bool },
+    Groups,
+    /// Remove the frames of a group.
+    RemoveGroup(String),
+    /// Remove the transforms between two frames.
+    RemoveTf { from: String, to: String },
+    /// Remove a frame and its transforms.
+    RemoveFrame(String),
+    /// Remove the frames matching a pattern, as for `frames`.
+    RemoveMatching(String),
+    /// Save the frames matching a pattern and the transforms between them.
+    ExportMatching { pattern: String, file: String },
+    /// Save the frames of a group and the transforms between them.
+    ExportGroup { group: String, file: String },
+    /// Replace the graph with the example rig.
+    Example,
+    /// Load a file and reload it when it changes, or stop watching with `None`.
+    WatchFile(Option<String>),
+    Commit(String),
+    Log,
+    /// Restore a revision, numbered from 1 as in `log`.
+    Checkout(usize),
+    ExportEnv { root: String, file: String, style: EnvStyle },
+    ExportMat(String),
+    /// Write the graph for Graphviz.
+    Dot(String),
+    /// Replace the frames and transforms with those of a DOT file, keeping the file name to save to.
+    LoadDot(String),
+    /// Designate a root frame, or stop treating it as one.
+    SetRoot { frame: String, root: bool },
+    Validate,
+    /// Print the frames below a root, or below all roots with `None`.
+    Tree(Option<String>),
+    /// Compare with a graph file, optionally writing the changes as a patch.
+    Diff { file: String, patch: Option<String> },
+    ApplyPatch(String),
     Load(String), // &str
-    Save(String),
+    /// With `normalize`, put transforms in canonical form first.
+    Save { file: String, create_dirs: bool, normalize: bool },
+}
+
+/// A group name: not empty, and without whitespace.
+fn group_name(s: &str) -> Option<String> {
+    let name = s.trim();
+    (!name.is_empty() && !name.contains(char::is_whitespace)).then(|| name.to_owned())
 }
 
 fn parse_input(line: &str) -> Option<Input> {
     match line.trim() {
         "q" | "quit" => Some(Input::Quit),
+        "q!" | "quit!" => Some(Input::ForceQuit),
         "r" | "reset" => Some(Input::Reset),
         "h" | "help" => Some(Input::Help),
-        "s" | "show" => Some(Input::Show),
+        "s" | "show" => Some(Input::Show(false)),
+        "show --header" => Some(Input::Show(true)),
+        s if s.starts_with("show @") => Some(Input::ShowGroup(group_name(&s[6..])?)),
+        s if s.starts_with("remove @") => Some(Input::RemoveGroup(group_name(&s[8..])?)),
+        s if s.starts_with("remove ") => match s[7..].split_once("->") {
+            Some((src, dst)) => {
+                let (src, dst) = (src.trim(), dst.trim());
+                (!src.is_empty() && !dst.is_empty()).then(|| Input::RemoveTf { from: src.to_owned(), to: dst.to_owned() })
+            }
+            None if is_pattern(s[7..].trim()) => Some(Input::RemoveMatching(s[7..].trim().to_owned())),
+            None => Some(Input::RemoveFrame(s[7..].trim().to_owned())),
+        },
+        s if s.starts_with("export @") => {
+            let (group, file) = s[8..].split_once(char::is_whitespace)?;
+            let file = file.trim();
+            if file.is_empty() {
+                return None;
+            }
+            Some(Input::ExportGroup { group: group_name(group)?, file: file.to_owned() })
+        }
+        s if s.starts_with("export ") => {
+            let (pattern, file) = s[7..].trim().split_once(char::is_whitespace)?;
+            let file = file.trim();
+            if file.is_empty() {
+                return None;
+            }
+            Some(Input::ExportMatching { pattern: pattern.to_owned(), file: file.to_owned() })
+        }
+        s if s.starts_with("tag ") || s.starts_with("untag ") => {
+            let (cmd, rem) = s.split_once(' ')?;
+            let (frames, group) = rem.split_once(':')?;
+            let frames = frames.split(',').map(|f| f.trim().to_owned()).collect_vec();
+            if frames.iter().any(String::is_empty) {
+                return None;
+            }
+            Some(Input::Tag { frames, group: group_name(group)?, tag: cmd == "tag" })
+        }
+        "groups" => Some(Input::Groups),
+        "detect-outliers" => Some(Input::DetectOutliers(None)),
+        s if s.starts_with("detect-outliers ") => {
+            let (translation, rotation) = s[16..].trim().strip_prefix("--tolerance ")?.split_whitespace().collect_tuple()?;
+            let tolerance: (f64, f64) = (translation.parse().ok()?, rotation.parse().ok()?);
+            (tolerance.0 > 0.0 && tolerance.1 > 0.0).then_some(Input::DetectOutliers(Some(tolerance)))
+        }
+        s if s.starts_with("header ") => {
+            let (field, value) = s[7..].trim().split_once(' ').unwrap_or((s[7..].trim(), ""));
+            let field = match field {
+                "author" => HeaderField::Author,
+                "description" => HeaderField::Description,
+                _ => return None,
+            };
+            let value = Some(value.trim()).filter(|v| !v.is_empty()).map(str::to_owned);
+            Some(Input::SetHeader { field, value })
+        }
+        "stats" => Some(Input::Stats),
+        "example" => Some(Input::Example),
+        s if s == "units" || s.starts_with("units ") => {
+            let (mut length, mut angle) = (None, None);
+            for word in s[5..].split_whitespace() {
+                match word {
+                    "m" => length = Some(Length::M),
+                    "cm" => length = Some(Length::Cm),
+                    "mm" => length = Some(Length::Mm),
+                    "rad" => angle = Some(Angle::Rad),
+                    "deg" => angle = Some(Angle::Deg),
+                    _ => return None,
+                }
+            }
+            Some(Input::Units { length, angle })
+        }
+        "match-names" => Some(Input::MatchNames(None)),
+        "match-names exact" => Some(Input::MatchNames(Some(NameMatching::EXACT))),
+        "match-names normalized" => Some(Input::MatchNames(Some(NameMatching::NORMALIZED))),
+        s if s.starts_with("matrix ") => Some(Input::Matrix(match s[7..].trim() {
+            "pretty" => MatrixFormat::Pretty,
+            "flat" => MatrixFormat::Flat,
+            "numpy" => MatrixFormat::Numpy,
+            "c" => MatrixFormat::C,
+            _ => return None,
+        })),
+        "buffer" => Some(Input::Buffer(None)),
+        s if s.starts_with("buffer ") => {
+            let seconds = s[7..].trim().parse().ok().filter(|&seconds: &f64| seconds >= 0.0)?;
+            Some(Input::Buffer(Some(Duration::try_from_secs_f64(seconds).ok()?)))
+        }
+        "interpolation" => Some(Input::Interpolation(None)),
+        "interpolation linear" => Some(Input::Interpolation(Some(Interpolation::Linear))),
+        "interpolation cubic" => Some(Input::Interpolation(Some(Interpolation::Cubic))),
+        "extrapolation" => Some(Input::Extrapolation(None)),
+        "extrapolation none" => Some(Input::Extrapolation(Some(Extrapolation::Error))),
+        "extrapolation clamp" => Some(Input::Extrapolation(Some(Extrapolation::ClampToNearest))),
+        s if s.starts_with("extrapolation ") => {
+            let seconds = s[14..].trim().parse().ok().filter(|&seconds: &f64| seconds >= 0.0)?;
+            Some(Input::Extrapolation(Some(Extrapolation::ExtrapolateUpTo(Duration::try_from_secs_f64(seconds).ok()?))))
+        }
+        "max-age off" => Some(Input::MaxAge(None)),
+        s if s.starts_with("max-age ") => Some(Input::MaxAge(Some(parse_age(s[8..].trim())?))),
+        s if s.starts_with("calibrated ") => {
+            let (src, rem) = s[11..].split_once("->")?;
+            let (dst, time) = rem.split_once(':')?;
+            let time = match time.trim() {
+                "none" => None,
+                "now" => Some(SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs()),
+                date => Some(parse_date(date)?),
+            };
+            Some(Input::Calibrated { from: src.trim().to_owned(), to: dst.trim().to_owned(), time })
+        }
+        "direction" => Some(Input::Direction(None)),
+        "direction a-to-b" => Some(Input::Direction(Some(EdgeConvention::SourceToTarget))),
+        "direction b-to-a" => Some(Input::Direction(Some(EdgeConvention::TargetToSource))),
+        "orientation" => Some(Input::Orientation(None)),
+        "orientation canonical" => Some(Input::Orientation(Some(true))),
+        "orientation as-added" => Some(Input::Orientation(Some(false))),
         s if s.starts_with("save ") => {
-            let s = s[5..].trim();
+            let mut s = s[5..].trim();
+            let (mut create_dirs, mut normalize) = (false, false);
+            loop {
+                if let Some(rest) = s.strip_prefix("-p ") {
+                    create_dirs = true;
+                    s = rest.trim();
+                } else if let Some(rest) = s.strip_prefix("--normalize ") {
+                    normalize = true;
+                    s = rest.trim();
+                } else {
+                    break;
+                }
+            }
             if s.is_empty() {
                 return None;
             }
-            Some(Input::Save(s.to_owned())) // slice ascii bytes should work
+            Some(Input::Save { file: s.to_owned(), create_dirs, normalize }) // slice ascii bytes should work
+        }
+        s if s.starts_with("enable ") || s.starts_with("disable ") => {
+            let (cmd, rem) = s.split_once(' ')?;
+            let (src, dst) = rem.split_once("->")?;
+            Some(Input::SetEnabled {
+                from: src.trim().to_owned(),
+                to: dst.trim().to_owned(),
+                enabled: cmd == "enable",
+            })
+        }
+        s if s.starts_with("cost ") => {
+            let (src, rem) = s[5..].split_once("->")?;
+            let (dst, cost) = rem.split_once(':')?;
+            Some(Input::SetCost {
+                from: src.trim().to_owned(),
+                to: dst.trim().to_owned(),
+                cost: cost.trim().parse().ok()?,
+            })
+        }
+        s if s.starts_with("bench ") => {
+            let (src, rem) = s[6..].split_once("->")?;
+            let (dst, iterations) = match rem.split_once(':') {
+                Some((dst, n)) => (dst, n.trim().parse().ok().filter(|&n| n > 0)?),
+                None => (rem, 10000),
+            };
+            Some(Input::Bench {
+                from: src.trim().to_owned(),
+                to: dst.trim().to_owned(),
+                iterations,
+            })
+        }
+        "alias" => Some(Input::Aliases),
+        "history" => Some(Input::History),
+        s if s.starts_with("alias ") => {
+            let (name, command) = s[6..].split_once('=')?;
+            let (name, command) = (name.trim(), command.trim());
+            if !is_var_name(name) || command.is_empty() {
+                return None;
+            }
+            Some(Input::Alias { name: name.to_owned(), command: Some(command.to_owned()) })
+        }
+        s if s.starts_with("unalias ") => {
+            let name = s[8..].trim();
+            is_var_name(name).then(|| Input::Alias { name: name.to_owned(), command: None })
+        }
+        s if s.starts_with("let ") => {
+            let (name, rem) = s[4..].split_once('=')?;
+            let name = name.trim();
+            if !is_var_name(name) {
+                return None;
+            }
+            let (from, to) = parse_pair(rem)?;
+            Some(Input::Let { name: name.to_owned(), from, to })
+        }
+        s if s.starts_with("watch ") => {
+            let (from, to) = parse_pair(&s[6..])?;
+            Some(Input::Watch { from, to })
+        }
+        s if s.starts_with("unwatch ") => {
+            let (from, to) = parse_pair(&s[8..])?;
+            Some(Input::Unwatch { from, to })
+        }
+        s if s.starts_with("import ") => {
+            let (rem, conv) = split_conv(&s[7..])?;
+            let (mapping, file) = rem.trim().split_once(' ')?;
+            Some(Input::Import { mapping: mapping.to_owned(), file: file.trim().to_owned(), conv })
+        }
+        s if s.starts_with("fit ") => {
+            let (from, rem) = s[4..].split_once("->")?;
+            let (to, files) = rem.split_once(':')?;
+            let (src_file, dst_file) = files.trim().split_once(' ')?;
+            Some(Input::Fit {
+                from: from.trim().to_owned(),
+                to: to.trim().to_owned(),
+                src_file: src_file.to_owned(),
+                dst_file: dst_file.trim().to_owned(),
+            })
+        }
+        s if s.starts_with("intrinsics ") => {
+            let (frame, values) = s[11..].split_once(':')?;
+            let [fx, fy, cx, cy] = parse_csv(values, ',').ok()?.try_into().ok()?;
+            Some(Input::Intrinsics { frame: frame.trim().to_owned(), fx, fy, cx, cy })
+        }
+        s if s.starts_with("project ") => {
+            let words: Vec<_> = s[8..].split_whitespace().collect();
+            let [world, camera, x, y, z] = words[..] else {
+                return None;
+            };
+            Some(Input::Project {
+                world: world.to_owned(),
+                camera: camera.to_owned(),
+                point: [x.parse().ok()?, y.parse().ok()?, z.parse().ok()?],
+            })
+        }
+        s if s.starts_with("transform-trajectory ") => {
+            let mut words = s[21..].trim_start().splitn(3, ' ');
+            let (input, output, rem) = (words.next()?, words.next()?, words.next()?);
+            let (rem, side) = match rem.trim().strip_suffix("--right") {
+                Some(rem) => (rem, Side::Right),
+                None => (rem, Side::Left),
+            };
+            let (from, to) = parse_pair(rem)?;
+            Some(Input::TransformTrajectory { input: input.to_owned(), output: output.to_owned(), from, to, side })
+        }
+        s if s.starts_with("replay ") => {
+            let (file, pair) = s[7..].trim_start().split_once(' ')?;
+            let (from, to) = parse_pair(pair)?;
+            (!from.is_empty() && !to.is_empty()).then(|| Input::Replay { file: file.to_owned(), from, to })
+        }
+        s if s.starts_with("resample ") => {
+            let (files, at) = s[9..].split_once(':')?;
+            let (input, output) = files.trim().split_once(' ')?;
+            let (at, cubic) = match at.trim().strip_suffix("--cubic") {
+                Some(at) => (at, true),
+                None => (at, false),
+            };
+            let at = match at.trim() {
+                "" => return None,
+                at => match at.parse::<f64>() {
+                    Ok(rate) if rate > 0.0 => SampleTimes::Rate(rate),
+                    Ok(_) => return None,
+                    Err(_) => SampleTimes::File(at.to_owned()),
+                },
+            };
+            Some(Input::Resample { input: input.to_owned(), output: output.trim().to_owned(), at, cubic })
+        }
+        s if s.starts_with("trim ") => {
+            let (files, times) = s[5..].split_once(':')?;
+            let (input, output) = files.trim().split_once(' ')?;
+            let (start, end) = times.split_whitespace().map(str::parse).collect_tuple()?;
+            let (start, end) = (start.ok()?, end.ok()?);
+            (start <= end).then(|| Input::Trim { input: input.to_owned(), output: output.trim().to_owned(), start, end })
+        }
+        s if s.starts_with("shift ") => {
+            let (files, offset) = s[6..].split_once(':')?;
+            let (input, output) = files.trim().split_once(' ')?;
+            let (offset, max) = match offset.split_once("--max") {
+                Some((offset, max)) => (offset, Some(max.trim().parse().ok().filter(|&max: &f64| max > 0.0)?)),
+                None => (offset, None),
+            };
+            let offset = match offset.trim() {
+                "" => return None,
+                offset => match (offset.parse::<f64>(), max) {
+                    (Ok(seconds), None) => ClockOffset::Seconds(seconds),
+                    (Ok(_), Some(_)) => return None,
+                    (Err(_), max) => ClockOffset::MatchTo { reference: offset.to_owned(), max: max.unwrap_or(1.0) },
+                },
+            };
+            Some(Input::Shift { input: input.to_owned(), output: output.trim().to_owned(), offset })
+        }
+        s if s.starts_with("decimate ") => {
+            let (files, tolerance) = s[9..].split_once(':')?;
+            let (input, output) = files.trim().split_once(' ')?;
+            let (translation, rotation) = tolerance.split_whitespace().map(str::parse).collect_tuple()?;
+            let (translation, rotation): (f64, f64) = (translation.ok()?, rotation.ok()?);
+            (translation >= 0.0 && rotation >= 0.0)
+                .then(|| Input::Decimate { input: input.to_owned(), output: output.trim().to_owned(), translation, rotation })
+        }
+        s if s.starts_with("split ") => {
+            let (files, span) = s[6..].split_once(':')?;
+            let (input, output) = files.trim().split_once(' ')?;
+            let span = span.trim().parse().ok().filter(|&span: &f64| span > 0.0)?;
+            Some(Input::Split { input: input.to_owned(), output: output.trim().to_owned(), span })
+        }
+        "stats --timeline" => Some(Input::Timeline { file: None, gap: None }),
+        s if s.starts_with("stats --timeline ") => {
+            // A number alone is the gap for the timed transforms of the graph.
+            if let Ok(gap) = s[17..].trim().parse::<f64>() {
+                return (gap > 0.0).then_some(Input::Timeline { file: None, gap: Some(gap) });
+            }
+            let (file, gap) = match s[17..].trim().split_once(' ') {
+                Some((file, gap)) => (file, Some(gap.trim().parse().ok().filter(|&gap: &f64| gap > 0.0)?)),
+                None => (s[17..].trim(), None),
+            };
+            (!file.is_empty()).then(|| Input::Timeline { file: Some(file.to_owned()), gap })
+        }
+        s if s.starts_with("frames ") => Some(Input::Frames(s[7..].trim().to_owned())),
+        s if s.starts_with("reachable ") => Some(Input::Reachable(s[10..].trim().to_owned())),
+        s if s.starts_with("report ") => {
+            let (file, layout) = match s[7..].split_once("--physical") {
+                Some((file, args)) => {
+                    let mut args = args.split_whitespace();
+                    let root = args.next()?.to_owned();
+                    let plane = match args.next() {
+                        None | Some("xy") => Plane::XY,
+                        Some("xz") => Plane::XZ,
+                        Some("yz") => Plane::YZ,
+                        Some(_) => return None,
+                    };
+                    if args.next().is_some() {
+                        return None;
+                    }
+                    (file.trim(), Layout::Physical { root, plane })
+                }
+                None => (s[7..].trim(), Layout::Tree),
+            };
+            if file.is_empty() {
+                return None;
+            }
+            Some(Input::Report { file: file.to_owned(), layout })
+        }
+        "unwatch-file" => Some(Input::WatchFile(None)),
+        s if s.starts_with("watch-file ") => {
+            let s = s[11..].trim();
+            (!s.is_empty()).then(|| Input::WatchFile(Some(s.to_owned())))
+        }
+        "log" => Some(Input::Log),
+        "validate" => Some(Input::Validate),
+        "tree" => Some(Input::Tree(None)),
+        s if s.starts_with("tree ") => Some(Input::Tree(Some(s[5..].trim().to_owned()))),
+        s if s.starts_with("set-root ") || s.starts_with("unset-root ") => {
+            let (cmd, frame) = s.split_once(' ')?;
+            Some(Input::SetRoot { frame: frame.trim().to_owned(), root: cmd == "set-root" })
+        }
+        s if s.starts_with("commit ") => {
+            let message = s[7..].trim();
+            let message = message.strip_prefix('"').and_then(|m| m.strip_suffix('"')).unwrap_or(message);
+            (!message.is_empty()).then(|| Input::Commit(message.to_owned()))
+        }
+        s if s.starts_with("checkout ") => Some(Input::Checkout(s[9..].trim().parse().ok().filter(|&n| n > 0)?)),
+        s if s.starts_with("load-dot ") => {
+            let s = s[9..].trim();
+            (!s.is_empty()).then(|| Input::LoadDot(s.to_owned()))
+        }
+        s if s.starts_with("dot ") => {
+            let s = s[4..].trim();
+            (!s.is_empty()).then(|| Input::Dot(s.to_owned()))
+        }
+        s if s.starts_with("export-mat ") => {
+            let s = s[11..].trim();
+            (!s.is_empty()).then(|| Input::ExportMat(s.to_owned()))
+        }
+        s if s.starts_with("export-env ") => {
+            let (args, style) = match s[11..].trim().strip_suffix("--shell") {
+                Some(args) => (args, EnvStyle::Shell),
+                None => (&s[11..], EnvStyle::DotEnv),
+            };
+            let (root, file) = args.trim().split_once(' ')?;
+            Some(Input::ExportEnv { root: root.to_owned(), file: file.trim().to_owned(), style })
+        }
+        s if s.starts_with("diff ") => {
+            let (file, patch) = match s[5..].split_once("--patch") {
+                Some((_, patch)) if patch.trim().is_empty() => return None,
+                Some((file, patch)) => (file.trim(), Some(patch.trim().to_owned())),
+                None => (s[5..].trim(), None),
+            };
+            (!file.is_empty()).then(|| Input::Diff { file: file.to_owned(), patch })
+        }
+        s if s.starts_with("apply-patch ") => {
+            let s = s[12..].trim();
+            (!s.is_empty()).then(|| Input::ApplyPatch(s.to_owned()))
         }
         s if s.starts_with("load ") => {
             let s = s[5..].trim();
@@ -116,21 +2593,52 @@ fn parse_input(line: &str) -> Option<Input> {
             }
             Some(Input::Load(s.to_owned()))
         }
+        s if s.starts_with("query ") => match parse_input(&s[6..])? {
+            query @ (Input::Query { .. } | Input::QueryMatching { .. }) => Some(query),
+            _ => None,
+        },
         s => {
             let (src, rem) = s.split_once("->")?;
             if let Some((dst, tf)) = rem.split_once(':') {
+                let (tf, conv) = split_conv(tf)?;
                 Some(Input::Add {
                     from: src.trim().to_owned(),
                     to: dst.trim().to_owned(),
                     tf: match tf.trim() {
-                        "rand" | "random" => se3::random(),
-                        tf => se3::from_array(parse_csv(tf, ',').ok()?.as_slice())?
-                    }
+                        "rand" | "random" => TfArg::Random,
+                        var if is_var_name(var) => TfArg::Var(var.to_owned()),
+                        tf => {
+                            let values: Vec<f64> = parse_csv(tf, ',').ok()?;
+                            // Validity does not depend on units
+                            Units::default().se3_from(&values)?;
+                            TfArg::Values(values)
+                        }
+                    },
+                    conv,
                 })
             } else {
+                let rem = rem.trim();
+                if let Some((dst, stamp)) = rem.split_once('@') {
+                    let stamp = stamp.trim().parse().ok().filter(|stamp: &f64| stamp.is_finite())?;
+                    return Some(Input::QueryAt { from: src.trim().to_owned(), to: dst.trim().to_owned(), stamp });
+                }
+                let (dst, output) = if let Some(dst) = rem.strip_suffix("--as-add") {
+                    (dst, QueryOutput::Add)
+                } else if let Some(dst) = rem.strip_suffix("--ros") {
+                    (dst, QueryOutput::Ros)
+                } else if let Some(dst) = rem.strip_suffix("--json") {
+                    (dst, QueryOutput::Json)
+                } else {
+                    (rem, QueryOutput::Transform)
+                };
+                let (src, dst) = (src.trim(), dst.trim());
+                if is_glob(src) || is_glob(dst) {
+                    return Some(Input::QueryMatching { from: src.to_owned(), to: dst.to_owned(), output });
+                }
                 Some(Input::Query {
                     from: src.trim().to_owned(),
-                    to: rem.trim().to_owned(),
+                    to: dst.trim().to_owned(),
+                    output,
                 })
             }
         }
@@ -138,14 +2646,70 @@ fn parse_input(line: &str) -> Option<Input> {
 }
 
 fn print_help() {
-    println!("{} Source -> Target: <tx, ty, tz, qx, qy, qz, qw | tx, ty, tz | qx, qy, qz, qw | 3x3 mat | 4x4 mat | random>", "* Add a transform:".blue().bold());
-    println!("{} Source -> Target", "* Query transform:".blue().bold());
+    println!("{} Source -> Target: <tx, ty, tz, qx, qy, qz, qw | tx, ty, tz, roll, pitch, yaw | tx, ty, tz | qx, qy, qz, qw | 3x3 mat | 4x4 mat | random>", "* Add a transform:".blue().bold());
+    println!("{} Source -> Target: <transform> --conv <flu | rdf | rub | frd>", "* Add a transform given in another axis convention:".blue().bold());
+    println!("{} units [m | cm | mm] [rad | deg]", "* Set units of entered and printed transforms:".blue().bold());
+    println!("{} match-names [exact | normalized]", "* Match frame names exactly, or ignoring case and extra whitespace:".blue().bold());
+    println!("{} matrix <pretty | flat | numpy | c>", "* Set how queried matrices are printed:".blue().bold());
+    println!("{} direction [a-to-b | b-to-a]", "* Set whether a -> b : T maps points from a into b, or from b into a:".blue().bold());
+    println!("{} orientation [canonical | as-added]", "* Store transforms from the frame whose name sorts first, or as added:".blue().bold());
+    println!("{} Source -> Target [--as-add | --ros | --json] (print an add command, a ROS 2 static_transform_publisher command or JSON instead)", "* Query transform:".blue().bold());
+    println!("{} Source -> Target @ <SECONDS> (interpolating timed transforms)", "* Query transform at a time:".blue().bold());
+    println!("{} Source -> Target [--ros | --json] (with * and ? in names, e.g. base -> * or cam* -> base)", "* Query all matching transforms:".blue().bold());
+    println!("{} let NAME = Source -> Target (use NAME in place of a transform)", "* Store a query result:".blue().bold());
+    println!("{} enable | disable Source -> Target", "* Enable/disable a transform for queries:".blue().bold());
+    println!("{} remove Source -> Target | remove Frame | remove <GLOB | /REGEX/>", "* Remove a transform, or frames with all their transforms:".blue().bold());
+    println!("{} cost Source -> Target: <cost>", "* Set routing cost of a transform:".blue().bold());
+    println!("{} bench Source -> Target [: iterations]", "* Benchmark queries:".blue().bold());
+    println!("{} watch | unwatch Source -> Target", "* Reprint a query when it changes:".blue().bold());
+    println!("{} frames <GLOB | /REGEX/>", "* List frames by name:".blue().bold());
+    println!("{} export <GLOB | /REGEX/> <FILE_NAME>", "* Save matching frames with the transforms between them:".blue().bold());
+    println!("{} reachable Frame", "* List frames connected to a frame:".blue().bold());
+    println!("{} alias NAME = <command> | unalias NAME | alias (list)", "* Define a shorthand, expanded at the start of a line:".blue().bold());
+    println!("{} history | !! | !N", "* List recent commands, or run the last or Nth one again:".blue().bold());
     println!("{} r | reset", "* Remove all transforms:".blue().bold());
     println!("{} s | show", "* Show graph status:".blue().bold());
-    println!("{} q | quit", "* Quit:".blue().bold());
+    println!("{} detect-outliers [--tolerance <LENGTH> <ANGLE>]", "* Rank transforms that break loops, most suspect first (default 1 cm, 1°):".blue().bold());
+    println!("{} show @group | groups", "* Show the frames and transforms of a group, or list groups:".blue().bold());
+    println!("{} tag | untag Frame[, Frame ...] : group", "* Add frames to a group, or remove them from it:".blue().bold());
+    println!("{} remove @group | export @group <FILE_NAME>", "* Remove the frames of a group, or save them with the transforms between them:".blue().bold());
+    println!("{} show --header", "* Show who created the graph file, and when:".blue().bold());
+    println!("{} header author | description [<TEXT>]", "* Set or clear a header field, saved with the graph:".blue().bold());
+    println!("{} stats", "* Show graph size and memory usage:".blue().bold());
+    println!("{} stats --timeline [FILE.tum] [GAP_SECONDS]", "* Show sample rate and gaps of the timed transforms, or of a trajectory:".blue().bold());
+    println!("{} q | quit (q! | quit! to discard unsaved changes)", "* Quit:".blue().bold());
     println!("{} h | help", "* Help:".blue().bold());
-    println!("{} save <FILE_NAME>", "* Save to json:".blue().bold());
-    println!("{} load <FILE_NAME>", "* Load from json:".blue().bold());
+    println!("{} save [-p] [--normalize] <FILE_NAME> (-p creates missing directories, --normalize canonicalizes transforms)", "* Save to json, or yaml for .yaml and .yml files:".blue().bold());
+    println!("{} fit Source -> Target : <SOURCE_POINTS.csv> <TARGET_POINTS.csv>", "* Add a transform fitted to corresponding points:".blue().bold());
+    println!("{} intrinsics Camera : fx, fy, cx, cy", "* Set pinhole intrinsics of a camera frame (x right, y down, z forward):".blue().bold());
+    println!("{} project World Camera x y z", "* Project a point into a camera image:".blue().bold());
+    println!("{} transform-trajectory <IN.tum> <OUT.tum> Source -> Target [--right]", "* Apply a transform to a trajectory:".blue().bold());
+    println!("{} replay <FILE.tum> Source -> Target", "* Add the poses of a trajectory as timed samples of a transform:".blue().bold());
+    println!("{} buffer [SECONDS]", "* Show or set how long timed samples are kept:".blue().bold());
+    println!("{} interpolation [linear | cubic]", "* Show or set how timed transforms are interpolated:".blue().bold());
+    println!("{} extrapolation [none | clamp | SECONDS]", "* Show or set how far timed transforms are extrapolated:".blue().bold());
+    println!("{} resample <IN.tum> <OUT.tum> : <RATE_HZ | TIMESTAMPS_FILE> [--cubic]", "* Interpolate a trajectory:".blue().bold());
+    println!("{} trim <IN.tum> <OUT.tum> : <START> <END>", "* Keep the poses of a trajectory within a time range:".blue().bold());
+    println!("{} shift <IN.tum> <OUT.tum> : <SECONDS | REF.tum [--max SECONDS]>", "* Add an offset to the timestamps of a trajectory, or estimate the one aligning it with REF:".blue().bold());
+    println!("{} decimate <IN.tum> <OUT.tum> : <LENGTH> <ANGLE>", "* Drop poses that interpolation reproduces within a tolerance:".blue().bold());
+    println!("{} split <IN.tum> <OUT.tum> : <SECONDS>", "* Cut a trajectory into numbered files of fixed duration (OUT_1.tum, ...):".blue().bold());
+    println!("{} report <FILE_NAME>.svg [--physical Root [xy | xz | yz]]", "* Draw the frame graph, optionally at the frame positions in Root:".blue().bold());
+    println!("{} load <FILE_NAME>", "* Load from json, or yaml for .yaml and .yml files:".blue().bold());
+    println!("{} example", "* Replace the graph with an example rig to try commands on:".blue().bold());
+    println!("{} watch-file <FILE_NAME> | unwatch-file", "* Load from json, and reload whenever the file changes:".blue().bold());
+    println!("{} export-env Root <FILE_NAME> [--shell]", "* Write transforms from Root as environment variables (TF_ROOT_TO_FRAME):".blue().bold());
+    println!("{} export-mat <FILE_NAME>.mat", "* Write transforms for MATLAB/Octave:".blue().bold());
+    println!("{} dot <FILE_NAME>.dot", "* Write the graph for Graphviz:".blue().bold());
+    println!("{} load-dot <FILE_NAME>.dot", "* Replace frames and transforms with those of a DOT file written by dot:".blue().bold());
+    println!("{} calibrated Source -> Target : <YYYY-MM-DD | now | none>", "* Record when a transform was calibrated:".blue().bold());
+    println!("{} max-age <DAYS>d | <HOURS>h | off", "* Warn when queries use older calibrations:".blue().bold());
+    println!("{} set-root Frame | unset-root Frame", "* Designate the root of a group of connected frames:".blue().bold());
+    println!("{} tree [Root]", "* Print the frames below a root, or below all roots:".blue().bold());
+    println!("{} validate", "* Check for missing roots, unreachable frames and stale calibrations:".blue().bold());
+    println!("{} commit <MESSAGE> | log | checkout <REVISION>", "* Snapshot the transforms, list snapshots, or restore one:".blue().bold());
+    println!("{} diff <FILE_NAME> [--patch <PATCH_FILE>]", "* Compare with a json file, optionally saving the changes as a patch:".blue().bold());
+    println!("{} apply-patch <PATCH_FILE>", "* Apply a patch, or nothing if any change conflicts:".blue().bold());
+    println!("{} import <MAPPING_FILE> <FILE_NAME> [--conv <convention>]", "* Import transforms from foreign json:".blue().bold());
 }
 
 #[cfg(test)]
@@ -156,15 +2720,63 @@ mod test {
     fn test_input() {
         let inputs = [
             ("q", Input::Quit),
+            ("quit!", Input::ForceQuit),
             ("r ", Input::Reset),
             ("help", Input::Help),
-            ("show", Input::Show),
+            ("show", Input::Show(false)),
+            ("show --header", Input::Show(true)),
+            ("show @cameras", Input::ShowGroup("cameras".to_owned())),
+            ("remove @old_rig", Input::RemoveGroup("old_rig".to_owned())),
+            ("remove cam -> base", Input::RemoveTf { from: "cam".to_owned(), to: "base".to_owned() }),
+            ("remove old_cam", Input::RemoveFrame("old_cam".to_owned())),
+            ("remove bag_*", Input::RemoveMatching("bag_*".to_owned())),
+            ("remove /^bag_[0-9]+$/", Input::RemoveMatching("/^bag_[0-9]+$/".to_owned())),
+            ("export cam_* cameras.json", Input::ExportMatching { pattern: "cam_*".to_owned(), file: "cameras.json".to_owned() }),
+            ("export @left_arm arm.json", Input::ExportGroup { group: "left_arm".to_owned(), file: "arm.json".to_owned() }),
+            ("tag cam_left, cam_right : cameras", Input::Tag { frames: vec!["cam_left".to_owned(), "cam_right".to_owned()], group: "cameras".to_owned(), tag: true }),
+            ("untag cam : cameras", Input::Tag { frames: vec!["cam".to_owned()], group: "cameras".to_owned(), tag: false }),
+            ("groups", Input::Groups),
+            ("detect-outliers", Input::DetectOutliers(None)),
+            ("detect-outliers --tolerance 5 0.5", Input::DetectOutliers(Some((5.0, 0.5)))),
+            ("header author Jane Doe", Input::SetHeader { field: HeaderField::Author, value: Some("Jane Doe".to_owned()) }),
+            ("header description", Input::SetHeader { field: HeaderField::Description, value: None }),
+            ("stats", Input::Stats),
+            ("stats --timeline", Input::Timeline { file: None, gap: None }),
+            ("stats --timeline 0.5", Input::Timeline { file: None, gap: Some(0.5) }),
+            ("stats --timeline tf.tum", Input::Timeline { file: Some("tf.tum".to_owned()), gap: None }),
+            ("stats --timeline tf.tum 0.5", Input::Timeline { file: Some("tf.tum".to_owned()), gap: Some(0.5) }),
+            ("example", Input::Example),
+            ("units mm", Input::Units { length: Some(Length::Mm), angle: None }),
+            ("units", Input::Units { length: None, angle: None }),
+            ("direction", Input::Direction(None)),
+            ("matrix numpy", Input::Matrix(MatrixFormat::Numpy)),
+            ("match-names normalized", Input::MatchNames(Some(NameMatching::NORMALIZED))),
+            ("direction b-to-a", Input::Direction(Some(EdgeConvention::TargetToSource))),
+            ("orientation canonical", Input::Orientation(Some(true))),
             (
                 "Alice -> Bob : 0,0,0",
                 Input::Add {
                     from: "Alice".to_owned(),
                     to: "Bob".to_owned(),
-                    tf: SE3::identity(),
+                    tf: TfArg::Values(vec![0.0, 0.0, 0.0]),
+                    conv: None,
+                },
+            ),
+            (
+                "a -> c : T_1 --conv opencv",
+                Input::Add {
+                    from: "a".to_owned(),
+                    to: "c".to_owned(),
+                    tf: TfArg::Var("T_1".to_owned()),
+                    conv: Some(Convention::Rdf),
+                },
+            ),
+            (
+                "let T1 = a -> b",
+                Input::Let {
+                    name: "T1".to_owned(),
+                    from: "a".to_owned(),
+                    to: "b".to_owned(),
                 },
             ),
             (
@@ -172,20 +2784,250 @@ mod test {
                 Input::Query {
                     from: "Bob".to_owned(),
                     to: "Alice".to_owned(),
+                    output: QueryOutput::Transform,
+                },
+            ),
+            (
+                "a -> b --as-add",
+                Input::Query {
+                    from: "a".to_owned(),
+                    to: "b".to_owned(),
+                    output: QueryOutput::Add,
+                },
+            ),
+            (
+                "a -> b --ros",
+                Input::Query { from: "a".to_owned(), to: "b".to_owned(), output: QueryOutput::Ros },
+            ),
+            (
+                "a -> b --json",
+                Input::Query { from: "a".to_owned(), to: "b".to_owned(), output: QueryOutput::Json },
+            ),
+            (
+                "query base -> *",
+                Input::QueryMatching { from: "base".to_owned(), to: "*".to_owned(), output: QueryOutput::Transform },
+            ),
+            (
+                "cam* -> base --ros",
+                Input::QueryMatching { from: "cam*".to_owned(), to: "base".to_owned(), output: QueryOutput::Ros },
+            ),
+            (
+                "query cam -> base",
+                Input::Query { from: "cam".to_owned(), to: "base".to_owned(), output: QueryOutput::Transform },
+            ),
+            (
+                "disable a -> b",
+                Input::SetEnabled {
+                    from: "a".to_owned(),
+                    to: "b".to_owned(),
+                    enabled: false,
+                },
+            ),
+            (
+                "cost a -> b : 2",
+                Input::SetCost {
+                    from: "a".to_owned(),
+                    to: "b".to_owned(),
+                    cost: 2.0,
+                },
+            ),
+            (
+                "bench a -> b : 100",
+                Input::Bench {
+                    from: "a".to_owned(),
+                    to: "b".to_owned(),
+                    iterations: 100,
+                },
+            ),
+            (
+                "watch a->b",
+                Input::Watch {
+                    from: "a".to_owned(),
+                    to: "b".to_owned(),
                 },
             ),
             (
                 "load  some file ",
                 Input::Load("some file".to_owned())
-            )
+            ),
+            (
+                "import map.json  vendor calib.json",
+                Input::Import {
+                    mapping: "map.json".to_owned(),
+                    file: "vendor calib.json".to_owned(),
+                    conv: None,
+                },
+            ),
+            (
+                "intrinsics cam : 500, 500, 320, 240",
+                Input::Intrinsics { frame: "cam".to_owned(), fx: 500.0, fy: 500.0, cx: 320.0, cy: 240.0 },
+            ),
+            (
+                "project world cam 0.2 -0.1 1",
+                Input::Project { world: "world".to_owned(), camera: "cam".to_owned(), point: [0.2, -0.1, 1.0] },
+            ),
+            (
+                "fit a -> b : a.csv b.csv",
+                Input::Fit {
+                    from: "a".to_owned(),
+                    to: "b".to_owned(),
+                    src_file: "a.csv".to_owned(),
+                    dst_file: "b.csv".to_owned(),
+                },
+            ),
+            (
+                "transform-trajectory in.tum out.tum imu -> cam --right",
+                Input::TransformTrajectory {
+                    input: "in.tum".to_owned(),
+                    output: "out.tum".to_owned(),
+                    from: "imu".to_owned(),
+                    to: "cam".to_owned(),
+                    side: Side::Right,
+                },
+            ),
+            (
+                "resample in.tum out.tum : 100",
+                Input::Resample {
+                    input: "in.tum".to_owned(),
+                    output: "out.tum".to_owned(),
+                    at: SampleTimes::Rate(100.0),
+                    cubic: false,
+                },
+            ),
+            (
+                "resample in.tum out.tum : cam.tum --cubic",
+                Input::Resample {
+                    input: "in.tum".to_owned(),
+                    output: "out.tum".to_owned(),
+                    at: SampleTimes::File("cam.tum".to_owned()),
+                    cubic: true,
+                },
+            ),
+            ("trim in.tum out.tum : 10 20.5", Input::Trim { input: "in.tum".to_owned(), output: "out.tum".to_owned(), start: 10.0, end: 20.5 }),
+            ("shift in.tum out.tum : -0.25", Input::Shift { input: "in.tum".to_owned(), output: "out.tum".to_owned(), offset: ClockOffset::Seconds(-0.25) }),
+            (
+                "shift in.tum out.tum : ref.tum --max 5",
+                Input::Shift {
+                    input: "in.tum".to_owned(),
+                    output: "out.tum".to_owned(),
+                    offset: ClockOffset::MatchTo { reference: "ref.tum".to_owned(), max: 5.0 },
+                },
+            ),
+            ("decimate in.tum out.tum : 1 0.1", Input::Decimate { input: "in.tum".to_owned(), output: "out.tum".to_owned(), translation: 1.0, rotation: 0.1 }),
+            ("split week.tum day.tum : 86400", Input::Split { input: "week.tum".to_owned(), output: "day.tum".to_owned(), span: 86400.0 }),
+            ("reachable base", Input::Reachable("base".to_owned())),
+            ("watch-file calib.json", Input::WatchFile(Some("calib.json".to_owned()))),
+            ("unwatch-file", Input::WatchFile(None)),
+            ("diff new.json", Input::Diff { file: "new.json".to_owned(), patch: None }),
+            ("diff new.json --patch up.json", Input::Diff { file: "new.json".to_owned(), patch: Some("up.json".to_owned()) }),
+            ("apply-patch up.json", Input::ApplyPatch("up.json".to_owned())),
+            ("commit \"after lidar recal\"", Input::Commit("after lidar recal".to_owned())),
+            ("commit v2", Input::Commit("v2".to_owned())),
+            ("log", Input::Log),
+            ("set-root base_link", Input::SetRoot { frame: "base_link".to_owned(), root: true }),
+            ("unset-root base_link", Input::SetRoot { frame: "base_link".to_owned(), root: false }),
+            ("validate", Input::Validate),
+            ("tree", Input::Tree(None)),
+            ("tree map", Input::Tree(Some("map".to_owned()))),
+            ("max-age 90d", Input::MaxAge(Some(Duration::from_secs(90 * 86400)))),
+            ("max-age off", Input::MaxAge(None)),
+            ("buffer", Input::Buffer(None)),
+            ("buffer 2.5", Input::Buffer(Some(Duration::from_millis(2500)))),
+            ("interpolation cubic", Input::Interpolation(Some(Interpolation::Cubic))),
+            ("extrapolation", Input::Extrapolation(None)),
+            ("extrapolation clamp", Input::Extrapolation(Some(Extrapolation::ClampToNearest))),
+            ("extrapolation 0.5", Input::Extrapolation(Some(Extrapolation::ExtrapolateUpTo(Duration::from_millis(500))))),
+            ("replay odom.tum odom -> base", Input::Replay { file: "odom.tum".to_owned(), from: "odom".to_owned(), to: "base".to_owned() }),
+            ("cam -> odom @ 12.5", Input::QueryAt { from: "cam".to_owned(), to: "odom".to_owned(), stamp: 12.5 }),
+            (
+                "calibrated cam -> base : 2024-03-01",
+                Input::Calibrated { from: "cam".to_owned(), to: "base".to_owned(), time: Some(1709251200) },
+            ),
+            ("calibrated cam -> base : none", Input::Calibrated { from: "cam".to_owned(), to: "base".to_owned(), time: None }),
+            ("export-mat tf.mat", Input::ExportMat("tf.mat".to_owned())),
+            ("dot tf.dot", Input::Dot("tf.dot".to_owned())),
+            ("load-dot tf.dot", Input::LoadDot("tf.dot".to_owned())),
+            ("export-env base tf.env", Input::ExportEnv { root: "base".to_owned(), file: "tf.env".to_owned(), style: EnvStyle::DotEnv }),
+            ("export-env base tf.sh --shell", Input::ExportEnv { root: "base".to_owned(), file: "tf.sh".to_owned(), style: EnvStyle::Shell }),
+            ("checkout 2", Input::Checkout(2)),
+            ("alias qcb = query cam -> base", Input::Alias { name: "qcb".to_owned(), command: Some("query cam -> base".to_owned()) }),
+            ("unalias qcb", Input::Alias { name: "qcb".to_owned(), command: None }),
+            ("frames /^cam_\\d+$/", Input::Frames("/^cam_\\d+$/".to_owned())),
+            (
+                "report frames.svg",
+                Input::Report { file: "frames.svg".to_owned(), layout: Layout::Tree },
+            ),
+            (
+                "report frames.svg --physical base_link xz",
+                Input::Report {
+                    file: "frames.svg".to_owned(),
+                    layout: Layout::Physical { root: "base_link".to_owned(), plane: Plane::XZ },
+                },
+            ),
+            (
+                "save -p ~/calib/a.json",
+                Input::Save {
+                    file: "~/calib/a.json".to_owned(),
+                    create_dirs: true,
+                    normalize: false,
+                },
+            ),
+            (
+                "save --normalize -p a.json",
+                Input::Save {
+                    file: "a.json".to_owned(),
+                    create_dirs: true,
+                    normalize: true,
+                },
+            ),
         ];
 
         let bad_inputs = [
             "",
             "qr",
             "save ",
+            "dot ",
+            "header tool tfgen",
+            "remove cam ->",
+            "show @",
+            "trim in.tum out.tum : 20 10",
+            "split in.tum out.tum : 0",
+            "decimate in.tum out.tum : 1",
+            "shift in.tum out.tum : 0.5 --max 1",
+            "buffer -1",
+            "interpolation spline",
+            "extrapolation -1",
+            "replay odom.tum odom ->",
+            "cam -> odom @ 1 --json",
+            "shift in.tum out.tum : ref.tum --max 0",
+            "stats --timeline tf.tum -1",
+            "stats --timeline 0",
+            "detect-outliers --tolerance 1",
+            "detect-outliers --tolerance 0 1",
+            "export @arm",
+            "export cam_*",
+            "tag a, : cameras",
+            "tag a : left arm",
             "Alice -> Bob : 0,0,0,0,0",
-            "a->b: 1,1,0,0,0,0,0,0,1"
+            "a->b: 1,1,0,0,0,0,0,0,1",
+            "bench a -> b : 0",
+            "units ft",
+            "a -> b : 1,2,3 --conv xyz",
+            "resample a.tum b.tum : 0",
+            "let 1T = a -> b",
+            "let rand = a -> b",
+            "query a -> b : 1, 2, 3",
+            "alias a b = show",
+            "alias x =",
+            "matrix latex",
+            "diff new.json --patch",
+            "apply-patch ",
+            "commit \"\"",
+            "checkout 0",
+            "checkout head",
+            "export-env base",
+            "max-age 90",
+            "calibrated cam -> base : yesterday",
         ];
 
         for (line, result) in inputs {
@@ -195,4 +3037,175 @@ mod test {
             assert_eq!(parse_input(line), None);
         }
     }
+
+    #[test]
+    fn test_add_line() {
+        let tf = se3::random();
+        for units in [Units::default(), Units { length: Length::Mm, angle: Angle::Deg }] {
+            let Some(Input::Add { tf: TfArg::Values(values), .. }) = parse_input(&add_line("a", "b", &tf, &units)) else {
+                panic!("add line does not parse");
+            };
+            let parsed = units.se3_from(&values).unwrap();
+            assert!((parsed.to_homogeneous() - tf.to_homogeneous()).amax() < 1e-15);
+        }
+    }
+
+    #[test]
+    fn test_writes() {
+        for line in ["a -> b : 1, 2, 3", "direction b-to-a", "cost a -> b : 2", "reset", "load a.json", "save a.json", "commit x", "header author me", "max-age 90d"] {
+            assert!(parse_input(line).unwrap().writes(), "{line}");
+        }
+        for line in ["a -> b", "direction", "show", "show --header", "diff a.json", "export-mat a.mat", "log"] {
+            assert!(!parse_input(line).unwrap().writes(), "{line}");
+        }
+    }
+
+    #[test]
+    fn test_example_rig() {
+        let g = example_rig();
+        assert!(g.validate().is_empty());
+        assert_eq!(g.tree("base_link").unwrap().len(), 5);
+        let (tf, path) = g.query_tf("cam_left", "cam_right").unwrap();
+        assert_eq!(path, ["cam_left", "lidar", "cam_right"]);
+        assert!((tf.translation.vector.y - 0.6 * 0.3f64.cos()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_export_read_only() {
+        let mut g = example_rig();
+        g.freeze();
+        let file = std::env::temp_dir().join(format!("tfgen_export_{}.json", std::process::id()));
+        let file = file.to_str().unwrap();
+        assert!(!save(&mut g, file));
+        assert!(save(&mut g.subgraph(|n| n.starts_with("cam")), file));
+        let mut exported = TfGraph::new();
+        exported.load_json(&mut File::open(file).unwrap()).unwrap();
+        std::fs::remove_file(file).unwrap();
+        assert!(exported.nodes().all(|n| n.starts_with("cam")));
+    }
+
+    #[test]
+    fn test_reload_keeps_settings() {
+        let file = std::env::temp_dir().join(format!("tfgen_reload_{}.json", std::process::id()));
+        let file = file.to_str().unwrap();
+        assert!(save(&mut example_rig(), file));
+        let mut g = TfGraph::new();
+        g.set_buffer_duration(Duration::from_secs(3));
+        g.set_interpolation(Interpolation::Cubic);
+        g.set_extrapolation(Extrapolation::ClampToNearest);
+        let reloaded = reload(&mut g, file, false, &Units::default());
+        std::fs::remove_file(file).unwrap();
+        assert!(reloaded);
+        assert_eq!(g.nodes().count(), example_rig().nodes().count());
+        assert_eq!(g.buffer_duration(), Duration::from_secs(3));
+        assert_eq!(g.interpolation(), Interpolation::Cubic);
+        assert_eq!(g.extrapolation(), Extrapolation::ClampToNearest);
+    }
+
+    #[test]
+    fn test_numbered_file() {
+        assert_eq!(numbered_file("out/day.tum", 2), "out/day_2.tum");
+        assert_eq!(numbered_file("day", 10), "day_10");
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(0), "1970-01-01 00:00:00");
+        assert_eq!(format_time(951827696), "2000-02-29 12:34:56");
+        assert_eq!(format_time(1709294400), "2024-03-01 12:00:00");
+        assert_eq!(parse_date("2000-02-29"), Some(951782400));
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(parse_date("1969-12-31"), None);
+        assert_eq!(parse_age("90d"), Some(Duration::from_secs(90 * 86400)));
+        assert_eq!(parse_age("12h"), Some(Duration::from_secs(12 * 3600)));
+        assert_eq!(parse_age("3w"), None);
+    }
+
+    #[test]
+    fn test_ros_line() {
+        let tf = se3::from_array(&[1.0, 2.0, 3.0]).unwrap();
+        let line = ros_line("cam", "base", &tf, EdgeConvention::SourceToTarget);
+        assert!(line.contains("--x 1 --y 2 --z 3 --qx 0 --qy 0 --qz 0 --qw 1 --frame-id base --child-frame-id cam"));
+        assert!(ros_line("cam", "base", &tf, EdgeConvention::TargetToSource).ends_with("--frame-id cam --child-frame-id base"));
+    }
+
+    #[test]
+    fn test_units() {
+        let units = Units { length: Length::Mm, angle: Angle::Deg };
+        let tf = units.se3_from(&[1000.0, 0.0, 0.0, 0.0, 0.0, 90.0]).unwrap();
+        assert!((tf.translation.x - 1.0).abs() < 1e-15);
+        assert!((tf.rotation.angle() - std::f64::consts::FRAC_PI_2).abs() < 1e-15);
+        assert_eq!(units.to7(&tf)[0], 1000.0);
+        // Rotations are unaffected
+        assert_eq!(units.se3_from(&[0.0, 0.0, 1.0, 0.0]), Units::default().se3_from(&[0.0, 0.0, 1.0, 0.0]));
+    }
+
+    #[test]
+    fn test_matrix_format() {
+        let mat = na::Matrix4::new_translation(&na::Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!(MatrixFormat::Flat.format(&mat), "1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 2.0, 0.0, 0.0, 1.0, 3.0, 0.0, 0.0, 0.0, 1.0");
+        assert!(MatrixFormat::Numpy.format(&mat).starts_with("np.array([[1.0, 0.0, 0.0, 1.0],\n          [0.0, 1.0, 0.0, 2.0],"));
+        assert!(MatrixFormat::C.format(&mat).ends_with("{0.0, 0.0, 0.0, 1.0}}"));
+    }
+
+    #[test]
+    fn test_recall() {
+        let history = ["a -> b".to_owned(), "show".to_owned()];
+        assert_eq!(recall("!!", &history), Some(Some("show")));
+        assert_eq!(recall("!1", &history), Some(Some("a -> b")));
+        assert_eq!(recall("!0", &history), Some(None));
+        assert_eq!(recall("!3", &history), Some(None));
+        assert_eq!(recall("!!", &[]), Some(None));
+        assert_eq!(recall("q!", &history), None);
+    }
+
+    #[test]
+    fn test_read_aliases() {
+        let file = std::env::temp_dir().join(format!("tfgen_aliases_{}", std::process::id()));
+        std::fs::write(&file, "# Shorthands\nalias qcb = query cam -> base\n\nnot an alias\n  alias rt=transform-trajectory\n").unwrap();
+        let aliases = read_aliases(&file);
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(aliases, BTreeMap::from([
+            ("qcb".to_owned(), "query cam -> base".to_owned()),
+            ("rt".to_owned(), "transform-trajectory".to_owned()),
+        ]));
+        assert!(read_aliases(&file).is_empty());
+    }
+
+    #[test]
+    fn test_expand_alias() {
+        let aliases = BTreeMap::from([("qcb".to_owned(), "cam -> base".to_owned()), ("rt".to_owned(), "transform-trajectory".to_owned())]);
+        assert_eq!(expand_alias("qcb", &aliases), "cam -> base");
+        assert_eq!(expand_alias("rt a.tum b.tum x -> y", &aliases), "transform-trajectory a.tum b.tum x -> y");
+        assert_eq!(expand_alias("qcbx -> y", &aliases), "qcbx -> y");
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("cam*", "cam_front"));
+        assert!(glob_match("cam*", "cam"));
+        assert!(glob_match("*_link", "base_link"));
+        assert!(glob_match("c?m*_*", "cam_left_optical"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("cam?", "cam"));
+        assert!(!glob_match("*_link", "base_link_2"));
+
+        let re = Pattern::new("/^cam_[0-9]+$/").unwrap();
+        assert!(re.matches("cam_12") && !re.matches("cam_12_optical"));
+        assert!(Pattern::new("/(/").is_none());
+        assert!(Pattern::new("cam_*").unwrap().matches("cam_12_optical"));
+    }
+
+    #[test]
+    fn test_expand_path() {
+        let home = std::env::var("HOME").unwrap();
+        assert_eq!(expand_path("~/a.json").unwrap(), format!("{home}/a.json"));
+        assert_eq!(expand_path("~").unwrap(), home);
+        assert_eq!(expand_path("$HOME/x/${HOME}y").unwrap(), format!("{home}/x/{home}y"));
+        assert_eq!(expand_path("a$/b~").unwrap(), "a$/b~");
+        assert!(expand_path("$TFGEN_SURELY_UNSET/a").is_err());
+        assert!(expand_path("${HOME").is_err());
+        assert!(expand_path("~surely_no_such_user/a").is_err());
+    }
 }
@@ -0,0 +1,124 @@
+//! Export to MATLAB/Octave `.mat` files (Level 5 MAT-file format), without external dependencies.
+
+use std::io;
+
+use crate::{Frame, TfGraph};
+
+// Data types of data elements.
+const MI_INT8: u32 = 1;
+const MI_UINT16: u32 = 4;
+const MI_INT32: u32 = 5;
+const MI_UINT32: u32 = 6;
+const MI_DOUBLE: u32 = 9;
+const MI_MATRIX: u32 = 14;
+
+// Array classes.
+const MX_STRUCT: u32 = 2;
+const MX_CHAR: u32 = 4;
+const MX_DOUBLE: u32 = 6;
+
+/// Length each struct field name is padded to, including the terminating NUL.
+const FIELD_NAME_LEN: usize = 8;
+
+impl<N: Frame> TfGraph<N> {
+    /// Write all transforms as a `.mat` file with a single variable `name`: a 1×n struct array with
+    /// fields `from`, `to` and `T`, the 4×4 homogeneous matrix of the transform.
+    ///
+    /// `T` follows the graph's `EdgeConvention`, like the result of `query_tf(from, to)`.
+    pub fn write_mat(&self, name: &str, writer: &mut impl io::Write) -> io::Result<()> {
+        let fields = ["from", "to", "T"];
+        let mut names = vec![0u8; FIELD_NAME_LEN * fields.len()];
+        for (i, field) in fields.iter().enumerate() {
+            names[i * FIELD_NAME_LEN..][..field.len()].copy_from_slice(field.as_bytes());
+        }
+        let mut data = vec![
+            small_element(MI_INT32, &(FIELD_NAME_LEN as i32).to_le_bytes()),
+            element(MI_INT8, &names),
+        ];
+        for e in self.edges() {
+            let t: Vec<u8> = e.tf.to_homogeneous().iter().flat_map(|x| x.to_le_bytes()).collect(); // column-major
+            data.push(char_array(e.from));
+            data.push(char_array(e.to));
+            data.push(matrix(MX_DOUBLE, &[4, 4], "", &[element(MI_DOUBLE, &t)]));
+        }
+        let count = self.edges().count() as i32;
+
+        writer.write_all(&header())?;
+        writer.write_all(&matrix(MX_STRUCT, &[1, count], name, &data))
+    }
+}
+
+/// The 128-byte file header: descriptive text, no subsystem data, version 0x0100, and the endian indicator.
+fn header() -> Vec<u8> {
+    let mut header = format!("MATLAB 5.0 MAT-file, Created by tfgen {}", env!("CARGO_PKG_VERSION")).into_bytes();
+    header.resize(116, b' ');
+    header.extend_from_slice(&[0; 8]);
+    header.extend_from_slice(&0x0100u16.to_le_bytes());
+    header.extend_from_slice(b"IM");
+    header
+}
+
+/// A data element: type, size, and the data padded to 8 bytes.
+fn element(ty: u32, data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + data.len().next_multiple_of(8));
+    out.extend_from_slice(&ty.to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    out.resize(8 + data.len().next_multiple_of(8), 0);
+    out
+}
+
+/// A data element of at most 4 bytes, with type and size packed into a single tag.
+fn small_element(ty: u32, data: &[u8]) -> Vec<u8> {
+    debug_assert!(data.len() <= 4);
+    let mut out = ((data.len() as u32) << 16 | ty).to_le_bytes().to_vec();
+    out.extend_from_slice(data);
+    out.resize(8, 0);
+    out
+}
+
+/// An array: flags, dimensions, name, then the class-specific `data` elements.
+fn matrix(class: u32, dims: &[i32], name: &str, data: &[Vec<u8>]) -> Vec<u8> {
+    let flags: Vec<u8> = [class, 0].iter().flat_map(|x| x.to_le_bytes()).collect();
+    let dims: Vec<u8> = dims.iter().flat_map(|x| x.to_le_bytes()).collect();
+    let mut body = element(MI_UINT32, &flags);
+    body.extend(element(MI_INT32, &dims));
+    body.extend(element(MI_INT8, name.as_bytes()));
+    body.extend(data.concat());
+    element(MI_MATRIX, &body)
+}
+
+/// A 1×n character array, as UTF-16 code units.
+fn char_array(s: &str) -> Vec<u8> {
+    let chars: Vec<u8> = s.encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+    matrix(MX_CHAR, &[1, (chars.len() / 2) as i32], "", &[element(MI_UINT16, &chars)])
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{se3, TfGraph};
+
+    #[test]
+    fn write_mat() {
+        let mut g = TfGraph::new();
+        let tf = se3::random();
+        g.add_tf("cam".to_owned(), "base".to_owned(), tf).unwrap();
+        let mut buf = Vec::new();
+        g.write_mat("transforms", &mut buf).unwrap();
+
+        assert!(buf.starts_with(b"MATLAB 5.0 MAT-file"));
+        assert_eq!(buf[124..128], [0, 1, b'I', b'M']);
+        // A single struct array covering the rest of the file.
+        let u32_at = |i: usize| u32::from_le_bytes(buf[i..i + 4].try_into().unwrap());
+        assert_eq!((u32_at(128), u32_at(132) as usize), (super::MI_MATRIX, buf.len() - 136));
+        assert_eq!(u32_at(144), super::MX_STRUCT);
+        assert_eq!(buf.len() % 8, 0);
+
+        let contains = |needle: &[u8]| buf.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(b"transforms"));
+        assert!(contains(b"from\0\0\0\0to\0\0\0\0\0\0T\0\0\0\0\0\0\0"));
+        assert!(contains(b"c\0a\0m\0"));
+        let t: Vec<u8> = tf.to_homogeneous().iter().flat_map(|x| x.to_le_bytes()).collect();
+        assert!(contains(&t));
+    }
+}
@@ -0,0 +1,211 @@
+//! Matching and validation of frame names.
+
+use std::{borrow::Cow, fmt};
+
+use crate::{Frame, TransformEdge, TransformGraph};
+
+/// How frame names given to `add_tf` and queries are matched against existing frames.
+///
+/// Frames keep the name they were first added with. The default matches names exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "json", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct NameMatching {
+    /// Ignore case, e.g. `Base_Link` is `base_link`.
+    pub ignore_case: bool,
+    /// Ignore leading and trailing whitespace.
+    pub trim: bool,
+    /// Treat any run of whitespace as a single space.
+    pub collapse_whitespace: bool,
+}
+
+impl NameMatching {
+    /// Exact matching.
+    pub const EXACT: Self = Self { ignore_case: false, trim: false, collapse_whitespace: false };
+    /// All normalizations.
+    pub const NORMALIZED: Self = Self { ignore_case: true, trim: true, collapse_whitespace: true };
+
+    /// The normalized form of `name`. Two names match if their normalized forms are equal.
+    pub fn normalize<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        let mut name = Cow::Borrowed(if self.trim { name.trim() } else { name });
+        if self.collapse_whitespace && name.contains(char::is_whitespace) {
+            let mut out = String::with_capacity(name.len());
+            let mut in_space = false;
+            for c in name.chars() {
+                if !c.is_whitespace() {
+                    out.push(c);
+                } else if !in_space {
+                    out.push(' ');
+                }
+                in_space = c.is_whitespace();
+            }
+            name = Cow::Owned(out);
+        }
+        if self.ignore_case && name.chars().any(char::is_uppercase) {
+            name = Cow::Owned(name.to_lowercase());
+        }
+        name
+    }
+
+    pub fn matches(&self, a: &str, b: &str) -> bool {
+        self.normalize(a) == self.normalize(b)
+    }
+}
+
+/// A frame name rejected by the graph's name validator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct InvalidName {
+    pub name: String,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for InvalidName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid frame name {:?}: {}", self.name, self.reason)
+    }
+}
+
+impl std::error::Error for InvalidName {}
+
+/// Checks a frame name, returning the reason it is invalid.
+pub type NameValidator = fn(&str) -> Result<(), &'static str>;
+
+/// The default name validator: names must be non-empty, must not start or end with `/`, and must not
+/// contain control characters.
+pub fn validate_name(name: &str) -> Result<(), &'static str> {
+    if name.is_empty() {
+        Err("empty")
+    } else if name.starts_with('/') || name.ends_with('/') {
+        Err("leading or trailing slash")
+    } else if name.contains(char::is_control) {
+        Err("control character")
+    } else {
+        Ok(())
+    }
+}
+
+impl<N: Frame, E: TransformEdge> TransformGraph<N, E> {
+    /// Set the check applied to frame names by `add_tf` (default: `validate_name`).
+    ///
+    /// Existing frames are not checked. Use `|_| Ok(())` to accept any name.
+    pub fn set_name_validator(&mut self, validator: NameValidator) {
+        self.validator = validator;
+    }
+
+    /// Check a frame name with the graph's validator.
+    pub fn check_name(&self, name: &str) -> Result<(), InvalidName> {
+        (self.validator)(name).map_err(|reason| InvalidName { name: name.to_owned(), reason })
+    }
+
+    /// Set how frame names are matched (default: exactly).
+    ///
+    /// Returns `None` if two existing frames would then match each other, or if the graph is frozen.
+    pub fn set_name_matching(&mut self, matching: NameMatching) -> Option<()> {
+        let mut seen = std::collections::HashSet::new();
+        if self.frozen || !self.nodes().all(|n| seen.insert(matching.normalize(n))) {
+            return None;
+        }
+        self.names = matching;
+        self.rebuild_lookup();
+        Some(())
+    }
+
+    pub fn name_matching(&self) -> NameMatching {
+        self.names
+    }
+
+    /// Whether both names refer to the same existing frame under the graph's `NameMatching`, e.g.
+    /// `"Base_Link"` and `" base_link"` with `NameMatching::NORMALIZED`.
+    pub fn is_same_frame(&self, a: &str, b: &str) -> bool {
+        self.find_node(a).is_some_and(|a| self.find_node(b) == Some(a))
+    }
+
+    /// Existing frames whose names are within a few edits of `name`, closest first, e.g. to suggest
+    /// corrections for a misspelled frame.
+    ///
+    /// Names are compared in their normalized form (see `NameMatching`). At most one edit is allowed per
+    /// three characters of `name`, and at least one.
+    pub fn similar_frames(&self, name: &str) -> Vec<&str> {
+        let name = self.names.normalize(name);
+        let max = (name.chars().count() / 3).max(1);
+        let mut similar: Vec<_> = self.nodes()
+            .map(|n| (edit_distance(&name, &self.names.normalize(n)), n))
+            .filter(|&(d, _)| d <= max)
+            .collect();
+        similar.sort_by_key(|&(d, _)| d);
+        similar.into_iter().map(|(_, n)| n).collect()
+    }
+}
+
+/// Levenshtein distance between `a` and `b`, in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diag = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let next = (diag + usize::from(ca != cb)).min(row[j] + 1).min(row[j + 1] + 1);
+            diag = std::mem::replace(&mut row[j + 1], next);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{se3, Error, TfGraph};
+    use super::{InvalidName, NameMatching};
+
+    #[test]
+    fn name_matching() {
+        let m = NameMatching::NORMALIZED;
+        assert_eq!(m.normalize("  Base \t Link "), "base link");
+        assert_eq!(NameMatching { collapse_whitespace: true, ..Default::default() }.normalize(" a  b"), " a b");
+        assert!(!NameMatching::EXACT.matches("a", "A"));
+
+        let mut g = TfGraph::new();
+        g.add_tf("Base_Link".to_owned(), "cam".to_owned(), se3::random()).unwrap();
+        g.add_tf("base_link".to_owned(), "imu".to_owned(), se3::random()).unwrap();
+        assert!(g.set_name_matching(m).is_none());
+
+        g.reset();
+        g.set_name_matching(m).unwrap();
+        g.add_tf("Base_Link".to_owned(), "cam".to_owned(), se3::random()).unwrap();
+        g.add_tf("base_link ".to_owned(), "imu".to_owned(), se3::random()).unwrap();
+        assert!(g.nodes().eq(["Base_Link", "cam", "imu"]));
+        assert_eq!(g.query_tf("CAM", " imu").unwrap().1, ["cam", "Base_Link", "imu"]);
+        assert!(g.is_same_frame("BASE_LINK", "base_link"));
+        assert!(!g.is_same_frame("cam", "imu"));
+        assert!(!g.is_same_frame("radar", "Radar"));
+    }
+
+    #[test]
+    fn similar_frames() {
+        assert_eq!(super::edit_distance("kitten", "sitting"), 3);
+        assert_eq!(super::edit_distance("", "abc"), 3);
+
+        let mut g = TfGraph::new();
+        g.add_tf("cam_left".to_owned(), "base_link".to_owned(), se3::random()).unwrap();
+        g.add_tf("cam_right".to_owned(), "base_link".to_owned(), se3::random()).unwrap();
+        assert_eq!(g.similar_frames("cam_lft"), ["cam_left"]);
+        assert_eq!(g.similar_frames("base"), Vec::<&str>::new());
+        g.set_name_matching(NameMatching::NORMALIZED).unwrap();
+        assert_eq!(g.similar_frames("Cam_Rigt"), ["cam_right"]);
+    }
+
+    #[test]
+    fn name_validation() {
+        let mut g = TfGraph::new();
+        assert!(matches!(g.add_tf("/base".to_owned(), "cam".to_owned(), se3::random()), Err(Error::InvalidName(_))));
+        assert!(g.add_tf("base".to_owned(), "".to_owned(), se3::random()).is_err());
+        assert!(!g.can_add_tf("base", "cam\n"));
+        assert_eq!(g.nodes().count(), 0);
+        assert_eq!(g.check_name("a/"), Err(InvalidName { name: "a/".to_owned(), reason: "leading or trailing slash" }));
+
+        g.set_name_validator(|_| Ok(()));
+        g.add_tf("/base".to_owned(), "cam".to_owned(), se3::random()).unwrap();
+        g.set_name_validator(|name| if name.is_ascii() { Ok(()) } else { Err("not ASCII") });
+        assert!(g.add_tf("/base".to_owned(), "kamera_ü".to_owned(), se3::random()).is_err());
+    }
+}
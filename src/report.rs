@@ -0,0 +1,243 @@
+//! SVG rendering of the frame graph, in the style of tf2's `view_frames`.
+
+use std::{collections::VecDeque, io};
+
+use petgraph::{graph::NodeIndex, visit::EdgeRef};
+
+use crate::{Frame, TfGraph};
+
+const COLUMN_WIDTH: f64 = 160.0;
+const ROW_HEIGHT: f64 = 110.0;
+const MARGIN: f64 = 40.0;
+const NODE_HEIGHT: f64 = 28.0;
+/// Size of the area physical layouts are scaled to fit.
+const PHYSICAL_SIZE: f64 = 800.0;
+
+/// Center of each node, and the width and height of the drawing.
+type Positions = (Vec<(f64, f64)>, (f64, f64));
+
+/// Where `dump_svg_with` places the frames.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Layout {
+    /// Each connected component as a tree.
+    Tree,
+    /// At the origin of each frame in `root`, projected onto `plane`. Frames not connected to `root`
+    /// are placed in a row below.
+    Physical { root: String, plane: Plane },
+}
+
+/// A coordinate plane, named by the axes drawn to the right and upwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Plane {
+    /// Top view.
+    XY,
+    /// Side view.
+    XZ,
+    /// Front view.
+    YZ,
+}
+
+impl<N: Frame> TfGraph<N> {
+    /// Render the graph as an SVG: each connected component is drawn as a tree, and each
+    /// transform is labeled with its translation length and rotation angle.
+    ///
+    /// Arrows point from `from` to `to` of each transform. Disabled transforms are dashed.
+    pub fn dump_svg(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        self.dump_svg_with(writer, &Layout::Tree)
+    }
+
+    /// Like `dump_svg`, with a choice of layout.
+    ///
+    /// Fails with `io::ErrorKind::NotFound` if the root of a physical layout does not exist.
+    pub fn dump_svg_with(&self, writer: &mut impl io::Write, layout: &Layout) -> io::Result<()> {
+        let (centers, (width, height)) = match layout {
+            Layout::Tree => self.tree_positions(),
+            Layout::Physical { root, plane } => self.physical_positions(root, *plane)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no frame {root}")))?,
+        };
+        let pos = |ix: NodeIndex| centers[ix.index()];
+
+        writeln!(writer, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="sans-serif" font-size="12">"#)?;
+        writeln!(writer, r#"<defs><marker id="arrow" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="8" markerHeight="8" orient="auto"><path d="M0,0 L10,5 L0,10 z"/></marker></defs>"#)?;
+
+        for e in self.g.edge_references() {
+            let ((x1, y1), (x2, y2)) = (pos(e.source()), pos(e.target()));
+            // Stop at the node boxes
+            let len = (x2 - x1).hypot(y2 - y1);
+            let shorten = if len > NODE_HEIGHT { NODE_HEIGHT / 2.0 / len } else { 0.0 };
+            let (dx, dy) = ((x2 - x1) * shorten, (y2 - y1) * shorten);
+            let (x1, y1, x2, y2) = (x1 + dx, y1 + dy, x2 - dx, y2 - dy);
+            let w = e.weight();
+            let dash = if w.enabled { "" } else { r#" stroke-dasharray="5,4""# };
+            writeln!(writer, r#"<line x1="{x1:.1}" y1="{y1:.1}" x2="{x2:.1}" y2="{y2:.1}" stroke="black"{dash} marker-end="url(#arrow)"/>"#)?;
+
+            let mut label = format!("{:.3} m, {:.1}°", w.tf.translation.vector.norm(), w.tf.rotation.angle().to_degrees());
+            if w.cost != 1.0 {
+                label += &format!(", cost {}", w.cost);
+            }
+            if !w.enabled {
+                label += ", disabled";
+            }
+            writeln!(writer, r#"<text x="{:.1}" y="{:.1}" fill="dimgray" text-anchor="middle">{label}</text>"#, (x1 + x2) / 2.0, (y1 + y2) / 2.0 + 4.0)?;
+        }
+
+        for ix in self.g.node_indices() {
+            let (x, y) = pos(ix);
+            let name = escape(self.g[ix].name());
+            let w = 16.0 + 7.5 * self.g[ix].name().chars().count() as f64;
+            writeln!(writer, r#"<rect x="{:.1}" y="{:.1}" width="{w:.1}" height="{NODE_HEIGHT}" rx="14" fill="lightyellow" stroke="black"/>"#, x - w / 2.0, y - NODE_HEIGHT / 2.0)?;
+            writeln!(writer, r#"<text x="{x:.1}" y="{:.1}" text-anchor="middle">{name}</text>"#, y + 4.0)?;
+        }
+
+        writeln!(writer, "</svg>")
+    }
+
+    /// Center of each node and size of the drawing, for `Layout::Tree`.
+    fn tree_positions(&self) -> Positions {
+        let layout = self.tree_layout();
+        let columns = layout.iter().map(|&(x, _)| x + 1.0).fold(0.0, f64::max);
+        let rows = layout.iter().map(|&(_, d)| d + 1).max().unwrap_or(0);
+        let width = 2.0 * MARGIN + COLUMN_WIDTH * columns;
+        let height = 2.0 * MARGIN + ROW_HEIGHT * rows.saturating_sub(1) as f64 + NODE_HEIGHT;
+        let centers = layout.into_iter()
+            .map(|(x, depth)| (MARGIN + COLUMN_WIDTH * (x + 0.5), MARGIN + ROW_HEIGHT * depth as f64 + NODE_HEIGHT / 2.0))
+            .collect();
+        (centers, (width, height))
+    }
+
+    /// Center of each node and size of the drawing, for `Layout::Physical`.
+    fn physical_positions(&self, root: &str, plane: Plane) -> Option<Positions> {
+        let mut points = vec![None; self.g.node_count()];
+        for (ix, tf) in self.flatten_from(self.find_node(root)?) {
+            // Origin of the frame, in `root`
+            let p = tf.inverse().translation.vector;
+            // SVG coordinates point right and down.
+            points[ix.index()] = Some(match plane {
+                Plane::XY => (p.x, -p.y),
+                Plane::XZ => (p.x, -p.z),
+                Plane::YZ => (p.y, -p.z),
+            });
+        }
+
+        // The root is always placed.
+        let placed = || points.iter().flatten();
+        let (min_x, max_x) = (placed().map(|p| p.0).fold(f64::INFINITY, f64::min), placed().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max));
+        let (min_y, max_y) = (placed().map(|p| p.1).fold(f64::INFINITY, f64::min), placed().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max));
+        let range = (max_x - min_x).max(max_y - min_y);
+        let scale = if range > 0.0 { PHYSICAL_SIZE / range } else { 0.0 };
+        let (width, physical_height) = ((max_x - min_x) * scale + COLUMN_WIDTH, (max_y - min_y) * scale + NODE_HEIGHT);
+
+        let mut next_column = 0.0;
+        let row = MARGIN + physical_height + ROW_HEIGHT;
+        let centers = points.iter().map(|p| match p {
+            Some((x, y)) => (MARGIN + COLUMN_WIDTH / 2.0 + (x - min_x) * scale, MARGIN + NODE_HEIGHT / 2.0 + (y - min_y) * scale),
+            None => {
+                next_column += 1.0;
+                (MARGIN + COLUMN_WIDTH * (next_column - 0.5), row)
+            }
+        }).collect();
+        let width = width.max(COLUMN_WIDTH * next_column) + 2.0 * MARGIN;
+        let height = if next_column > 0.0 { row + NODE_HEIGHT / 2.0 + MARGIN } else { physical_height + 2.0 * MARGIN };
+        Some((centers, (width, height)))
+    }
+
+    /// `(column, depth)` of each node: a breadth-first spanning tree per connected component,
+    /// with leaves in separate columns and parents centered over their children.
+    fn tree_layout(&self) -> Vec<(f64, usize)> {
+        let n = self.g.node_count();
+        let mut depth = vec![usize::MAX; n];
+        let mut children: Vec<Vec<NodeIndex>> = vec![Vec::new(); n];
+        let mut components = Vec::new(); // (root, nodes in BFS order)
+
+        for root in self.g.node_indices() {
+            if depth[root.index()] != usize::MAX {
+                continue;
+            }
+            depth[root.index()] = 0;
+            let mut order = Vec::new();
+            let mut queue = VecDeque::from([root]);
+            while let Some(node) = queue.pop_front() {
+                order.push(node);
+                for next in self.g.neighbors(node) {
+                    if depth[next.index()] == usize::MAX {
+                        depth[next.index()] = depth[node.index()] + 1;
+                        children[node.index()].push(next);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            components.push((root, order));
+        }
+
+        let mut column = vec![0.0; n];
+        let mut next_leaf = 0.0;
+        for (root, order) in components {
+            // Leaves take columns in depth-first order, so that subtrees do not overlap.
+            let mut stack = vec![root];
+            while let Some(node) = stack.pop() {
+                let c = &children[node.index()];
+                if c.is_empty() {
+                    column[node.index()] = next_leaf;
+                    next_leaf += 1.0;
+                }
+                stack.extend(c.iter().rev());
+            }
+            for node in order.iter().rev() {
+                let c = &children[node.index()];
+                if let (Some(first), Some(last)) = (c.first(), c.last()) {
+                    column[node.index()] = (column[first.index()] + column[last.index()]) / 2.0;
+                }
+            }
+        }
+
+        column.into_iter().zip(depth).collect()
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{se3, TfGraph};
+    use super::{Layout, Plane};
+
+    #[test]
+    fn svg() {
+        let mut g = TfGraph::new();
+        g.add_tf("cam".to_owned(), "base".to_owned(), se3::from_array(&[3.0, 4.0, 0.0]).unwrap()).unwrap();
+        g.add_tf("lidar".to_owned(), "base".to_owned(), se3::random()).unwrap();
+        g.add_tf("<x>".to_owned(), "y".to_owned(), se3::random()).unwrap();
+        g.set_enabled("lidar", "base", false).unwrap();
+
+        let mut out = Vec::new();
+        g.dump_svg(&mut out).unwrap();
+        let svg = String::from_utf8(out).unwrap();
+        assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<line").count(), 3);
+        assert_eq!(svg.matches("<rect").count(), 5);
+        assert!(svg.contains("5.000 m, 0.0°"));
+        assert!(svg.contains("&lt;x&gt;"));
+        assert_eq!(svg.matches("stroke-dasharray").count(), 1);
+
+        let layout = g.tree_layout();
+        // base is centered over cam and lidar
+        assert_eq!(layout[1], ((layout[0].0 + layout[2].0) / 2.0, 1));
+
+        let physical = |root: &str, plane| g.physical_positions(root, plane).unwrap().0;
+        // cam is at x = 3, y = 4 in base: to the right and up
+        let pos = physical("base", Plane::XY);
+        let (dx, dy) = (pos[0].0 - pos[1].0, pos[0].1 - pos[1].1);
+        assert!(dx > 0.0 && (4.0 * dx + 3.0 * dy).abs() < 1e-9);
+        // Not connected to base: in a row below
+        assert!(pos[3].1 > pos[0].1 && pos[3].1 == pos[4].1);
+        let pos = physical("cam", Plane::YZ);
+        assert_eq!(pos[0].1, pos[1].1);
+        assert!(g.physical_positions("nowhere", Plane::XY).is_none());
+
+        let mut out = Vec::new();
+        g.dump_svg_with(&mut out, &Layout::Physical { root: "base".to_owned(), plane: Plane::XZ }).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().matches("<line").count(), 3);
+    }
+}
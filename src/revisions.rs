@@ -0,0 +1,87 @@
+//! Named snapshots of the transforms of a graph, saved with it.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{diff::{Change, TransformRecord}, Frame, TransformEdge, TransformGraph};
+
+/// The transforms of a graph at the time of a `commit`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Revision<E> {
+    pub message: String,
+    /// Seconds since the Unix epoch.
+    pub time: u64,
+    pub transforms: Vec<TransformRecord<E>>,
+}
+
+impl<N: Frame, E: TransformEdge + Clone> TransformGraph<N, E> {
+    /// Snapshot the current transforms as a new revision. Returns its index, or `None` if the graph is frozen.
+    pub fn commit(&mut self, message: impl Into<String>) -> Option<usize> {
+        if self.frozen {
+            return None;
+        }
+        let transforms = self.edges().map(|e| TransformRecord {
+            from: e.from.to_owned(),
+            to: e.to.to_owned(),
+            tf: e.tf.clone(),
+            enabled: e.enabled,
+            cost: e.cost,
+            calibrated: e.calibrated,
+        }).collect();
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        self.revisions.push(Revision { message: message.into(), time, transforms });
+        Some(self.revisions.len() - 1)
+    }
+
+    /// All revisions, oldest first.
+    pub fn revisions(&self) -> &[Revision<E>] {
+        &self.revisions
+    }
+}
+
+impl<N: Frame + From<String> + Clone, E: TransformEdge + Clone> TransformGraph<N, E> {
+    /// Replace all transforms with those of revision `rev`. Frames are kept, and revisions are not changed.
+    ///
+    /// Returns `None` if there is no such revision, if its transforms are cyclic while cycles are forbidden,
+    /// or if the graph is frozen.
+    pub fn checkout(&mut self, rev: usize) -> Option<()> {
+        if self.frozen {
+            return None;
+        }
+        let patch: Vec<_> = self.revisions.get(rev)?.transforms.iter().cloned().map(Change::Added).collect();
+        let mut g = self.clone();
+        g.g.clear_edges();
+        g.apply_patch(&patch).ok()?;
+        *self = g;
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{se3, TfGraph};
+
+    #[test]
+    fn revisions() {
+        let mut g = TfGraph::new();
+        g.add_tf("base".to_owned(), "cam".to_owned(), se3::random()).unwrap();
+        let before = g.commit("initial").unwrap();
+        let (tf, _) = g.query_tf("base", "cam").unwrap();
+
+        g.add_tf("base".to_owned(), "cam".to_owned(), se3::random()).unwrap();
+        g.add_tf("cam".to_owned(), "lidar".to_owned(), se3::random()).unwrap();
+        g.set_enabled("base", "cam", false).unwrap();
+        let after = g.commit("after lidar recal").unwrap();
+        assert_eq!(g.revisions().len(), 2);
+        assert_eq!(g.revisions()[after].message, "after lidar recal");
+
+        let current = g.clone();
+        g.checkout(before).unwrap();
+        assert_eq!(g.query_tf("base", "cam").unwrap().0, tf);
+        assert!(g.query_tf("cam", "lidar").is_none());
+        assert_eq!(g.nodes().count(), 3);
+
+        g.checkout(after).unwrap();
+        assert!(g.diff(&current).is_empty());
+        assert!(g.checkout(2).is_none());
+    }
+}
@@ -0,0 +1,252 @@
+//! Root frames of connected groups of frames, and validation of the frame hierarchy.
+
+use std::collections::VecDeque;
+
+use petgraph::{graph::NodeIndex, visit::EdgeRef};
+
+use crate::{Frame, TransformEdge, TransformGraph};
+
+/// A problem with the frame hierarchy, found by `validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue<'a> {
+    /// Connected frames without a root.
+    NoRoot(Vec<&'a str>),
+    /// Connected frames with more than one root, e.g. after linking two groups that each had one.
+    SeveralRoots(Vec<&'a str>),
+    /// A frame connected to `root` only through disabled transforms.
+    Unreachable { frame: &'a str, root: &'a str },
+    /// A transform calibrated before the maximum age (see `set_max_age`).
+    Stale { from: &'a str, to: &'a str, calibrated: u64 },
+}
+
+impl<N: Frame, E: TransformEdge> TransformGraph<N, E> {
+    /// Designate `frame` as the root of the frames connected to it, replacing their previous root.
+    ///
+    /// Returns `None` if there is no such frame, or if the graph is frozen.
+    pub fn set_root(&mut self, frame: &str) -> Option<()> {
+        let node = self.find_node(frame)?;
+        if self.frozen {
+            return None;
+        }
+        let component = self.component(node, false);
+        self.roots.retain(|r| !component.contains(r));
+        self.roots.push(node);
+        Some(())
+    }
+
+    /// Stop treating `frame` as a root. Returns `None` if it is not one, or if the graph is frozen.
+    pub fn unset_root(&mut self, frame: &str) -> Option<()> {
+        let node = self.find_node(frame)?;
+        let i = self.roots.iter().position(|&r| r == node)?;
+        if self.frozen {
+            return None;
+        }
+        self.roots.remove(i);
+        Some(())
+    }
+
+    /// Root frames, in the order they were designated.
+    pub fn roots(&self) -> impl Iterator<Item = &str> {
+        self.roots.iter().map(|&r| self.g[r].name())
+    }
+
+    /// Frames reachable from `root` over enabled transforms, depth first, with their depth below `root`.
+    ///
+    /// Returns `None` if `root` does not exist.
+    pub fn tree(&self, root: &str) -> Option<Vec<(usize, &str)>> {
+        let root = self.find_node(root)?;
+        let mut seen = vec![false; self.g.node_count()];
+        seen[root.index()] = true;
+        let mut stack = vec![(0, root)];
+        let mut out = Vec::new();
+        while let Some((depth, node)) = stack.pop() {
+            out.push((depth, self.g[node].name()));
+            let mut children: Vec<_> = self.g.edges(node)
+                .filter(|e| e.weight().enabled)
+                .map(|e| if e.source() == node { e.target() } else { e.source() })
+                .filter(|next| !std::mem::replace(&mut seen[next.index()], true))
+                .collect();
+            // Visit children in the order they were added.
+            children.sort();
+            stack.extend(children.into_iter().rev().map(|child| (depth + 1, child)));
+        }
+        Some(out)
+    }
+
+    /// The frames above `frame` up to its root, parent first, over enabled transforms. Empty for a root.
+    ///
+    /// Returns `None` if there is no such frame, or if no root reaches it. If cycles are allowed, the
+    /// parent of each frame is its neighbour on a path from the root with the fewest transforms.
+    pub fn ancestors(&self, frame: &str) -> Option<Vec<&str>> {
+        let node = self.find_node(frame)?;
+        let (_, parents) = self.parents(node)?;
+        Some(std::iter::successors(parents[node.index()], |n| parents[n.index()]).map(|n| self.g[n].name()).collect())
+    }
+
+    /// The frames below `frame`, depth first as in `tree`, over enabled transforms. See `ancestors`.
+    pub fn descendants(&self, frame: &str) -> Option<Vec<&str>> {
+        let node = self.find_node(frame)?;
+        let (_, parents) = self.parents(node)?;
+        let mut children = vec![Vec::new(); self.g.node_count()];
+        for child in self.g.node_indices() {
+            if let Some(parent) = parents[child.index()] {
+                children[parent.index()].push(child);
+            }
+        }
+        let mut stack = children[node.index()].clone();
+        stack.reverse();
+        let mut out = Vec::new();
+        while let Some(n) = stack.pop() {
+            out.push(self.g[n].name());
+            stack.extend(children[n.index()].iter().rev());
+        }
+        Some(out)
+    }
+
+    /// The lowest frame that is `a` or one of its ancestors, and `b` or one of its ancestors, e.g. to
+    /// decide where to attach a frame seen by two sensors. See `ancestors`.
+    ///
+    /// Returns `None` if either frame does not exist, or if they are not below the same root.
+    pub fn lowest_common_ancestor(&self, a: &str, b: &str) -> Option<&str> {
+        let (a, b) = (self.find_node(a)?, self.find_node(b)?);
+        let (root, parents) = self.parents(a)?;
+        if b != root && parents[b.index()].is_none() {
+            return None;
+        }
+        let above_a: Vec<_> = std::iter::successors(Some(a), |n| parents[n.index()]).collect();
+        let common = std::iter::successors(Some(b), |n| parents[n.index()]).find(|n| above_a.contains(n))?;
+        Some(self.g[common].name())
+    }
+
+    /// The root that reaches `node` over enabled transforms, and the parent of each frame it reaches,
+    /// found breadth first.
+    fn parents(&self, node: NodeIndex) -> Option<(NodeIndex, Vec<Option<NodeIndex>>)> {
+        self.roots.iter().find_map(|&root| {
+            let mut parents = vec![None; self.g.node_count()];
+            let mut queue = VecDeque::from([root]);
+            while let Some(n) = queue.pop_front() {
+                for e in self.g.edges(n).filter(|e| e.weight().enabled) {
+                    let next = if e.source() == n { e.target() } else { e.source() };
+                    if next != root && parents[next.index()].is_none() {
+                        parents[next.index()] = Some(n);
+                        queue.push_back(next);
+                    }
+                }
+            }
+            (node == root || parents[node.index()].is_some()).then_some((root, parents))
+        })
+    }
+
+    /// Check that each group of connected frames has exactly one root, that every frame can be
+    /// reached from its root, and that no calibration is stale.
+    pub fn validate(&self) -> Vec<Issue<'_>> {
+        let mut issues = Vec::new();
+        let mut done = vec![false; self.g.node_count()];
+        for node in self.g.node_indices() {
+            if done[node.index()] {
+                continue;
+            }
+            let component = self.component(node, false);
+            component.iter().for_each(|n| done[n.index()] = true);
+            let roots: Vec<_> = self.roots.iter().filter(|r| component.contains(r)).collect();
+            match roots[..] {
+                [] => issues.push(Issue::NoRoot(component.iter().map(|&n| self.g[n].name()).collect())),
+                [&root] => {
+                    let reachable = self.component(root, true);
+                    issues.extend(component.iter().filter(|n| !reachable.contains(n)).map(|&n| Issue::Unreachable {
+                        frame: self.g[n].name(),
+                        root: self.g[root].name(),
+                    }));
+                }
+                _ => issues.push(Issue::SeveralRoots(roots.iter().map(|&&r| self.g[r].name()).collect())),
+            }
+        }
+        if let Some(oldest) = self.stale_before() {
+            issues.extend(self.edges().filter_map(|e| {
+                let calibrated = e.calibrated.filter(|&t| t < oldest)?;
+                Some(Issue::Stale { from: e.from, to: e.to, calibrated })
+            }));
+        }
+        issues
+    }
+
+    /// Frames connected to `node`, itself included, in index order.
+    fn component(&self, node: NodeIndex, enabled_only: bool) -> Vec<NodeIndex> {
+        let mut seen = vec![false; self.g.node_count()];
+        seen[node.index()] = true;
+        let mut queue = VecDeque::from([node]);
+        while let Some(node) = queue.pop_front() {
+            for e in self.g.edges(node).filter(|e| !enabled_only || e.weight().enabled) {
+                let next = if e.source() == node { e.target() } else { e.source() };
+                if !std::mem::replace(&mut seen[next.index()], true) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        self.g.node_indices().filter(|n| seen[n.index()]).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{se3, TfGraph};
+    use super::Issue;
+
+    #[test]
+    fn roots() {
+        let mut g = TfGraph::new();
+        g.add_tf("cam".to_owned(), "base".to_owned(), se3::random()).unwrap();
+        g.add_tf("base".to_owned(), "imu".to_owned(), se3::random()).unwrap();
+        g.add_tf("cam_optical".to_owned(), "cam".to_owned(), se3::random()).unwrap();
+        g.add_tf("x".to_owned(), "y".to_owned(), se3::random()).unwrap();
+
+        assert_eq!(g.validate(), [Issue::NoRoot(vec!["cam", "base", "imu", "cam_optical"]), Issue::NoRoot(vec!["x", "y"])]);
+        g.set_root("cam").unwrap();
+        g.set_root("base").unwrap();
+        g.set_root("x").unwrap();
+        assert!(g.roots().eq(["base", "x"]));
+        assert!(g.validate().is_empty());
+        assert!(g.set_root("z").is_none());
+
+        assert_eq!(g.tree("base").unwrap(), [(0, "base"), (1, "cam"), (2, "cam_optical"), (1, "imu")]);
+        g.set_enabled("cam", "base", false).unwrap();
+        assert_eq!(g.tree("base").unwrap(), [(0, "base"), (1, "imu")]);
+        assert_eq!(g.validate(), [
+            Issue::Unreachable { frame: "cam", root: "base" },
+            Issue::Unreachable { frame: "cam_optical", root: "base" },
+        ]);
+
+        g.add_tf("y".to_owned(), "imu".to_owned(), se3::random()).unwrap();
+        assert_eq!(g.validate(), [Issue::SeveralRoots(vec!["base", "x"])]);
+        g.unset_root("x").unwrap();
+        assert!(g.unset_root("x").is_none());
+    }
+
+    #[test]
+    fn ancestry() {
+        let mut g = TfGraph::new();
+        g.add_tf("cam".to_owned(), "base".to_owned(), se3::random()).unwrap();
+        g.add_tf("base".to_owned(), "imu".to_owned(), se3::random()).unwrap();
+        g.add_tf("cam_optical".to_owned(), "cam".to_owned(), se3::random()).unwrap();
+        g.add_tf("cam_left".to_owned(), "cam".to_owned(), se3::random()).unwrap();
+        g.add_tf("x".to_owned(), "y".to_owned(), se3::random()).unwrap();
+        g.set_root("base").unwrap();
+        g.set_root("x").unwrap();
+
+        assert_eq!(g.ancestors("cam_optical").unwrap(), ["cam", "base"]);
+        assert_eq!(g.ancestors("base").unwrap(), Vec::<&str>::new());
+        assert_eq!(g.ancestors("y").unwrap(), ["x"]);
+        assert!(g.ancestors("nowhere").is_none());
+        assert_eq!(g.descendants("base").unwrap(), ["cam", "cam_optical", "cam_left", "imu"]);
+        assert_eq!(g.descendants("imu").unwrap(), Vec::<&str>::new());
+        assert_eq!(g.lowest_common_ancestor("cam_optical", "cam_left"), Some("cam"));
+        assert_eq!(g.lowest_common_ancestor("cam_optical", "imu"), Some("base"));
+        assert_eq!(g.lowest_common_ancestor("cam", "cam_left"), Some("cam"));
+        assert!(g.lowest_common_ancestor("cam", "y").is_none());
+
+        // Frames cut off from their root by a disabled transform have no ancestors.
+        g.set_enabled("cam", "base", false).unwrap();
+        assert!(g.ancestors("cam_optical").is_none());
+        assert_eq!(g.descendants("base").unwrap(), ["imu"]);
+    }
+}
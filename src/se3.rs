@@ -24,18 +24,42 @@ pub fn from7(a: &[f64]) -> Option<SE3> {
     }
     Some(
         SE3::from_parts([a[0], a[1], a[2]].into(),
-        na::UnitQuaternion::from_quaternion([a[3], a[4], a[5], a[6]].into()))
+        normalized([a[3], a[4], a[5], a[6]].into()))
     )
 }
 
+/// Normalize `q`, unless it is normalized up to rounding already, so that saved unit quaternions load
+/// with the same bits.
+fn normalized(q: na::Quaternion<f64>) -> na::UnitQuaternion<f64> {
+    if has_unit_rotation(&q) {
+        na::Unit::new_unchecked(q)
+    } else {
+        na::UnitQuaternion::from_quaternion(q)
+    }
+}
+
+/// Whether `from7` keeps the rotation `q` as it is, rather than normalizing it.
+pub(crate) fn has_unit_rotation(q: &na::Quaternion<f64>) -> bool {
+    (q.norm() - 1.0).abs() <= 4.0 * f64::EPSILON
+}
+
+/// Whether `from_array` normalizes the rotation of `a`: it holds a quaternion, as 4 or 7 values, that is
+/// not of unit length.
+pub fn normalizes_rotation(a: &[f64]) -> bool {
+    match a.len() {
+        7 => !has_unit_rotation(&[a[3], a[4], a[5], a[6]].into()),
+        4 => !has_unit_rotation(&[a[0], a[1], a[2], a[3]].into()),
+        _ => false,
+    }
+}
+
 pub fn from_array(a: &[f64]) -> Option<SE3> {
     match a.len() {
-        7 => Some(
-            SE3::from_parts([a[0], a[1], a[2]].into(),
-            na::UnitQuaternion::from_quaternion([a[3], a[4], a[5], a[6]].into()))
-        ),
+        7 => from7(a),
         // Trans only
         3 => Some(SE3::translation(a[0], a[1], a[2])),
+        // Trans + roll, pitch, yaw
+        6 => Some(SE3::from_parts([a[0], a[1], a[2]].into(), na::UnitQuaternion::from_euler_angles(a[3], a[4], a[5]))),
         // Quat
         4 => Some(
             na::convert(na::UnitQuaternion::from_quaternion([a[0], a[1], a[2], a[3]].into()))
@@ -51,9 +75,9 @@ pub fn from_array(a: &[f64]) -> Option<SE3> {
             }
             // Or extend to 4x4 matrix.
         }
-        // SE3 mat
-        16 => {
-            let mat = na::Matrix4::from_row_slice(a);
+        // SE3 mat, with or without the bottom row
+        12 | 16 => {
+            let mat = na::Matrix3x4::from_row_slice(&a[..12]);
             let rot = mat.fixed_view::<3, 3>(0, 0).into_owned(); // make owned matrix from view
             let trans = mat.fixed_view::<3, 1>(0, 3).into_owned();
             // Should check bottom row [0, 0, 0, 1]
@@ -69,6 +93,50 @@ pub fn from_array(a: &[f64]) -> Option<SE3> {
     }
 }
 
+/// A transform parsed from comma-separated values in any of the forms accepted by `from_array`,
+/// optionally enclosed in brackets, e.g. `"1, 2, 3, 0, 0, 0, 1".parse::<Literal>()`.
+///
+/// Translations are in meters and angles in radians.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Literal(pub SE3);
+
+/// Error parsing a `Literal`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseLiteralError {
+    /// A value is not a number.
+    Number(String),
+    /// The values do not form a transform: wrong count, or not a rotation matrix.
+    Invalid,
+}
+
+impl std::fmt::Display for ParseLiteralError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseLiteralError::Number(x) => write!(f, "not a number: {x:?}"),
+            ParseLiteralError::Invalid => f.write_str("expected 3, 4, 6, 7, 9, 12 or 16 values forming a valid transform"),
+        }
+    }
+}
+
+impl std::error::Error for ParseLiteralError {}
+
+impl std::str::FromStr for Literal {
+    type Err = ParseLiteralError;
+
+    fn from_str(s: &str) -> Result<Self, ParseLiteralError> {
+        from_array(&literal_values(s)?).map(Literal).ok_or(ParseLiteralError::Invalid)
+    }
+}
+
+/// The numbers of a `Literal`, before they are made a transform.
+pub(crate) fn literal_values(s: &str) -> Result<Vec<f64>, ParseLiteralError> {
+    let s = s.trim();
+    let s = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')).unwrap_or(s);
+    s.split(',')
+        .map(|x| x.trim().parse().map_err(|_| ParseLiteralError::Number(x.trim().to_owned())))
+        .collect()
+}
+
 /// Generate a random transform.
 /// The rotation part is sampled uniformly on S^3 (unit quaternion).
 /// The translation part is sampled uniformly within the cube `[-1, +1]`.
@@ -81,11 +149,193 @@ pub fn random() -> SE3 {
     SE3::from_parts(trans.into(), na::UnitQuaternion::from_quaternion(quat.into())) // normalized here
 }
 
+/// The same transform in a canonical form: components smaller than `epsilon` in magnitude set to zero,
+/// the quaternion renormalized, and its sign chosen so that `w >= 0` (or, if `w` is zero, the first
+/// non-zero component is positive). Applying it again changes nothing.
+pub fn canonical(tf: &SE3, epsilon: f64) -> SE3 {
+    let round = |x: f64| if x.abs() < epsilon { 0.0 } else { x };
+    let t = tf.translation.vector.map(round);
+    let q = tf.rotation;
+    let q = normalized(na::Quaternion::new(round(q.w), round(q.i), round(q.j), round(q.k)));
+    let negative = [q.w, q.i, q.j, q.k].into_iter().find(|&c| c != 0.0).is_some_and(|c| c < 0.0);
+    SE3::from_parts(t.into(), if negative { na::Unit::new_unchecked(-q.into_inner()) } else { q })
+}
+
+/// Interpolate between `a` (at `s = 0`) and `b` (at `s = 1`): linearly for the translation,
+/// and along the shortest arc for the rotation.
+pub fn interpolate(a: &SE3, b: &SE3, s: f64) -> SE3 {
+    a.lerp_slerp(b, s)
+}
+
+/// Least-squares rigid transform mapping `src` points onto the corresponding `dst` points
+/// (Kabsch/Umeyama without scale).
+///
+/// Returns `None` if the slices differ in length, or the points are fewer than 3 or collinear.
+pub fn fit_from_points(src: &[na::Point3<f64>], dst: &[na::Point3<f64>]) -> Option<SE3> {
+    if src.len() != dst.len() || src.len() < 3 {
+        return None;
+    }
+    let centroid = |pts: &[na::Point3<f64>]| pts.iter().map(|p| p.coords).sum::<na::Vector3<f64>>() / pts.len() as f64;
+    let (c_src, c_dst) = (centroid(src), centroid(dst));
+    let h: na::Matrix3<f64> = src.iter().zip(dst)
+        .map(|(s, d)| (s.coords - c_src) * (d.coords - c_dst).transpose())
+        .sum();
+    let rot = fit_rotation(&h)?;
+    Some(SE3::from_parts((c_dst - rot * c_src).into(), rot.into()))
+}
+
+/// The rotation `R` minimizing `sum |R * b_i - a_i|^2`, given `h = sum b_i * a_i^T`.
+///
+/// Returns `None` if the vectors do not determine a rotation, e.g. if they are all parallel.
+pub(crate) fn fit_rotation(h: &na::Matrix3<f64>) -> Option<na::Rotation3<f64>> {
+    let svd = h.svd(true, true);
+    let (u, v_t) = (svd.u?, svd.v_t?);
+    let mut sv: [f64; 3] = svd.singular_values.into();
+    sv.sort_unstable_by(|a, b| b.total_cmp(a));
+    if sv[1] <= sv[0] * 1e-9 {
+        return None;
+    }
+    // Flip the axis of the smallest singular value if needed, to get a rotation instead of a reflection.
+    let d = (v_t.transpose() * u.transpose()).determinant().signum();
+    let (i_min, _) = svd.singular_values.argmin();
+    let mut flip = na::Matrix3::identity();
+    flip[(i_min, i_min)] = d;
+    Some(na::Rotation3::from_matrix_unchecked(v_t.transpose() * flip * u.transpose()))
+}
+
+/// Compact one-line rendering of a transform, e.g. `t=[1.00, 2.00, 3.00] rpy=[0.0°, 90.0°, 0.0°]`.
+///
+/// Translation is in meters, roll, pitch and yaw in degrees. The formatting precision sets the number of
+/// decimals of the translation (default 2); angles get one decimal less.
+#[derive(Debug, Clone, Copy)]
+pub struct DisplaySE3<'a>(pub &'a SE3);
+
+impl std::fmt::Display for DisplaySE3<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Values that round to zero are printed without a sign.
+        fn fmt3(v: [f64; 3], prec: usize, unit: &str) -> String {
+            let half_ulp = 0.5 * 10f64.powi(-(prec as i32));
+            v.map(|x| if x.abs() < half_ulp { 0.0 } else { x }).map(|x| format!("{x:.prec$}{unit}")).join(", ")
+        }
+        let prec = f.precision().unwrap_or(2);
+        let (r, p, y) = self.0.rotation.euler_angles();
+        write!(f, "t=[{}] rpy=[{}]",
+            fmt3(self.0.translation.vector.into(), prec, ""),
+            fmt3([r, p, y].map(f64::to_degrees), prec.saturating_sub(1), "°"))
+    }
+}
+
+/// Axis conventions of frames, and conversion of transforms between them.
+pub mod convention {
+    use std::str::FromStr;
+
+    use nalgebra as na;
+    use super::SE3;
+
+    /// Directions of the x, y and z axes of a frame.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Convention {
+        /// Forward, left, up: ROS body frames (REP 103).
+        Flu,
+        /// Right, down, forward: OpenCV cameras.
+        Rdf,
+        /// Right, up, backward: OpenGL cameras.
+        Rub,
+        /// Forward, right, down: aerospace body frames.
+        Frd,
+    }
+
+    impl Convention {
+        /// Rotation taking coordinates in this convention to FLU coordinates.
+        fn to_flu(self) -> na::Rotation3<f64> {
+            let m = match self {
+                Convention::Flu => na::Matrix3::identity(),
+                Convention::Rdf => na::Matrix3::new(0.0, 0.0, 1.0, -1.0, 0.0, 0.0, 0.0, -1.0, 0.0),
+                Convention::Rub => na::Matrix3::new(0.0, 0.0, -1.0, -1.0, 0.0, 0.0, 0.0, 1.0, 0.0),
+                Convention::Frd => na::Matrix3::new(1.0, 0.0, 0.0, 0.0, -1.0, 0.0, 0.0, 0.0, -1.0),
+            };
+            na::Rotation3::from_matrix_unchecked(m)
+        }
+    }
+
+    impl FromStr for Convention {
+        type Err = ();
+
+        /// Accepts `flu`/`ros`, `rdf`/`opencv`, `rub`/`opengl` and `frd`, in any case.
+        fn from_str(s: &str) -> Result<Self, ()> {
+            match s.to_ascii_lowercase().as_str() {
+                "flu" | "ros" => Ok(Convention::Flu),
+                "rdf" | "opencv" => Ok(Convention::Rdf),
+                "rub" | "opengl" => Ok(Convention::Rub),
+                "frd" => Ok(Convention::Frd),
+                _ => Err(()),
+            }
+        }
+    }
+
+    /// Rotation taking coordinates of a point in convention `from` to convention `to`.
+    pub fn change_of_axes(from: Convention, to: Convention) -> na::UnitQuaternion<f64> {
+        (to.to_flu().inverse() * from.to_flu()).into()
+    }
+
+    /// Re-express `tf` between two frames that both use convention `from`, for the same frames using convention `to`.
+    pub fn convert(tf: &SE3, from: Convention, to: Convention) -> SE3 {
+        let c = SE3::from_parts(na::Translation3::identity(), change_of_axes(from, to));
+        c * tf * c.inverse()
+    }
+}
+
+/// Serde adapter storing an `SE3` compactly as `[tx, ty, tz, qx, qy, qz, qw]`.
+///
+/// Use with `#[serde(with = "se3::compact")]`.
+#[cfg(feature = "json")]
+pub mod compact {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+    use super::{from7, To7, SE3};
+
+    pub fn serialize<S: Serializer>(tf: &SE3, serializer: S) -> Result<S::Ok, S::Error> {
+        tf.to7().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SE3, D::Error> {
+        checked(&<[f64; 7]>::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+
+    /// `from7`, rejecting non-finite values and a zero quaternion.
+    pub(crate) fn checked(a: &[f64; 7]) -> Result<SE3, &'static str> {
+        if !a.iter().all(|x| x.is_finite()) {
+            return Err("non-finite value");
+        }
+        if a[3..].iter().all(|&x| x == 0.0) {
+            return Err("zero quaternion");
+        }
+        Ok(from7(a).unwrap())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use approx::assert_relative_eq;
 
+    #[test]
+    fn canonical() {
+        let drifted = na::Quaternion::new(-0.5, 0.5 + 1e-13, -1e-17, 0.7);
+        let tf = SE3::from_parts(na::Translation3::new(1.0, -1e-15, 0.0), na::Unit::new_unchecked(drifted));
+        let c = super::canonical(&tf, 1e-12);
+        assert_eq!(c.translation.vector, na::Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(c.rotation.j, 0.0);
+        assert!(c.rotation.w > 0.0);
+        assert_relative_eq!(c.rotation.norm(), 1.0, epsilon = 1e-15);
+        assert_relative_eq!(c.rotation.angle_to(&na::UnitQuaternion::from_quaternion(drifted)), 0.0, epsilon = 1e-12);
+        for _ in 0..1000 {
+            let c = super::canonical(&random(), 1e-12);
+            assert_eq!(super::canonical(&c, 1e-12), c);
+        }
+        let flip = from_array(&[0.0, 0.0, 0.0, 0.0, -1.0, 0.0, 0.0]).unwrap();
+        assert_eq!(super::canonical(&flip, 1e-12).rotation.j, 1.0);
+    }
+
     #[test]
     fn pose_vec7() {
         let vec = [1.0, 2.0, -1.0, 1.0, 0.0, 0.0, 0.0];
@@ -95,6 +345,82 @@ mod test {
         assert_relative_eq!(vec.as_slice(), vec2.as_slice());
     }
 
+    #[cfg(feature = "json")]
+    #[test]
+    fn compact_serde() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct W(#[serde(with = "compact")] SE3);
+
+        let pose = from_array(&[1.0, 2.0, 3.0, 0.0, 0.0, 1.0, 0.0]).unwrap();
+        let s = serde_json::to_string(&W(pose)).unwrap();
+        assert_eq!(s, "[1.0,2.0,3.0,0.0,0.0,1.0,0.0]");
+        assert_eq!(serde_json::from_str::<W>(&s).unwrap().0, pose);
+        assert!(serde_json::from_str::<W>("[1,2,3,0,0,0,0]").is_err());
+        assert!(compact::checked(&[1.0, f64::NAN, 3.0, 0.0, 0.0, 0.0, 1.0]).is_err());
+        assert!(compact::checked(&[1.0, 2.0, 3.0, 0.0, f64::INFINITY, 0.0, 1.0]).is_err());
+    }
+
+    #[test]
+    fn fit_points() {
+        let tf = random();
+        let src: Vec<_> = (0..10).map(|_| na::Point3::from(random().translation.vector)).collect();
+        let dst: Vec<_> = src.iter().map(|p| tf * p).collect();
+        assert_relative_eq!(fit_from_points(&src, &dst).unwrap(), tf, epsilon = 1e-9);
+
+        assert!(fit_from_points(&src[..2], &dst[..2]).is_none());
+        let line: Vec<_> = (0..5).map(|i| na::Point3::new(i as f64, 0.0, 0.0)).collect();
+        assert!(fit_from_points(&line, &line).is_none());
+    }
+
+    #[test]
+    fn parse_literal() {
+        let Literal(tf) = "[1, 2, 3, 0, 0, 1, 0]".parse().unwrap();
+        assert_eq!(tf, from_array(&[1.0, 2.0, 3.0, 0.0, 0.0, 1.0, 0.0]).unwrap());
+        let Literal(m) = "0,-1,0,1, 1,0,0,2, 0,0,1,3".parse().unwrap();
+        assert_relative_eq!(m, from_array(&[1.0, 2.0, 3.0, 0.0, 0.0, std::f64::consts::FRAC_PI_2]).unwrap(), epsilon = 1e-12);
+        assert_eq!("1, x, 3".parse::<Literal>(), Err(ParseLiteralError::Number("x".to_owned())));
+        assert_eq!("1, 2".parse::<Literal>(), Err(ParseLiteralError::Invalid));
+        assert_eq!("1,0,0, 0,1,0, 0,0,2".parse::<Literal>(), Err(ParseLiteralError::Invalid));
+
+        assert!(normalizes_rotation(&[1.0, 2.0, 3.0, 0.0, 0.0, 2.0, 0.0]));
+        assert!(normalizes_rotation(&[0.0, 0.0, 0.5, 0.5]));
+        assert!(!normalizes_rotation(&[1.0, 2.0, 3.0, 0.0, 0.0, 1.0, 0.0]));
+        assert!(!normalizes_rotation(&[1.0, 2.0, 3.0, 0.0, 2.0, 0.0]));
+    }
+
+    #[test]
+    fn display() {
+        let pose = from_array(&[1.0, 2.0, 3.0, 0.0, std::f64::consts::FRAC_PI_2, 0.0]).unwrap();
+        assert_eq!(DisplaySE3(&pose).to_string(), "t=[1.00, 2.00, 3.00] rpy=[0.0°, 90.0°, 0.0°]");
+        assert_eq!(format!("{:.3}", DisplaySE3(&SE3::identity())), "t=[0.000, 0.000, 0.000] rpy=[0.00°, 0.00°, 0.00°]");
+    }
+
+    #[test]
+    fn pose_rpy() {
+        let pose = from_array(&[1.0, 2.0, 3.0, 0.1, -0.2, 0.3]).unwrap();
+        let (r, p, y) = pose.rotation.euler_angles();
+        assert_relative_eq!([r, p, y].as_slice(), [0.1, -0.2, 0.3].as_slice(), epsilon = 1e-12);
+    }
+
+    #[test]
+    fn conventions() {
+        use convention::*;
+
+        // The optical axis of an OpenCV camera points forward.
+        let c = change_of_axes(Convention::Rdf, Convention::Flu);
+        assert_relative_eq!(c * na::Vector3::z(), na::Vector3::x());
+        assert_relative_eq!(c * na::Vector3::x(), -na::Vector3::y());
+        // OpenGL looks down -z
+        let c = change_of_axes(Convention::Rub, Convention::Rdf);
+        assert_relative_eq!(c * -na::Vector3::z(), na::Vector3::z());
+
+        let tf = random();
+        let there = convert(&tf, Convention::Flu, Convention::Rub);
+        assert_relative_eq!(convert(&there, Convention::Rub, Convention::Flu), tf, epsilon = 1e-12);
+        assert_relative_eq!(there.translation.vector.norm(), tf.translation.vector.norm(), epsilon = 1e-12);
+        assert_eq!("OpenCV".parse(), Ok(Convention::Rdf));
+    }
+
     #[test]
     fn pose_conversion() {
         let pose = SE3::identity();
@@ -0,0 +1,81 @@
+//! Calibration times of transforms, and detection of stale calibrations.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use itertools::Itertools;
+use petgraph::graph::NodeIndex;
+
+use crate::{Frame, TransformEdge, TransformGraph};
+
+impl<N: Frame, E: TransformEdge> TransformGraph<N, E> {
+    /// Record when the edges between two frames were calibrated, in seconds since the Unix epoch, or forget it with `None`.
+    ///
+    /// Returns `None` if there is no such edge.
+    pub fn set_calibrated(&mut self, a: &str, b: &str, time: Option<u64>) -> Option<()> {
+        let (a, b) = (self.find_node(a)?, self.find_node(b)?);
+        self.update_edges(a, b, |e| e.calibrated = time)
+    }
+
+    /// Consider calibrations older than `max_age` stale, or none with `None` (the default).
+    ///
+    /// With the `tracing` feature, `query_tf` warns about stale transforms on the path.
+    pub fn set_max_age(&mut self, max_age: Option<Duration>) {
+        self.max_age = max_age;
+    }
+
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    /// Transforms on the path `query_tf` would take whose calibration is stale, as `(from, to, calibrated)`,
+    /// in the order of the path. Transforms without a calibration time are never stale.
+    ///
+    /// Returns `None` if the frames are not connected.
+    pub fn stale_on_path(&self, src: &str, dst: &str) -> Option<Vec<(&str, &str, u64)>> {
+        let path_nodes = self.find_path(src, dst)?;
+        Some(self.stale_edges(&path_nodes))
+    }
+
+    pub(crate) fn stale_edges(&self, path_nodes: &[NodeIndex]) -> Vec<(&str, &str, u64)> {
+        let Some(oldest) = self.stale_before() else {
+            return Vec::new();
+        };
+        path_nodes.iter().tuple_windows().filter_map(|(&a, &b)| {
+            let calibrated = self.g[self.first_enabled_edge(a, b)].calibrated?;
+            (calibrated < oldest).then(|| (self.g[a].name(), self.g[b].name(), calibrated))
+        }).collect()
+    }
+
+    /// Calibrations before this time are stale, if there is a maximum age.
+    pub(crate) fn stale_before(&self) -> Option<u64> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        Some(now.saturating_sub(self.max_age?).as_secs())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use crate::{se3, TfGraph};
+
+    #[test]
+    fn stale() {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let day = 86400;
+        let mut g = TfGraph::new();
+        g.add_tf("base".to_owned(), "cam".to_owned(), se3::random()).unwrap();
+        g.add_tf("cam".to_owned(), "lidar".to_owned(), se3::random()).unwrap();
+        g.add_tf("lidar".to_owned(), "imu".to_owned(), se3::random()).unwrap();
+        g.set_calibrated("cam", "base", Some(now - 100 * day)).unwrap();
+        g.set_calibrated("cam", "lidar", Some(now - day)).unwrap();
+        assert!(g.set_calibrated("base", "imu", Some(now)).is_none());
+
+        assert_eq!(g.stale_on_path("imu", "base").unwrap(), []);
+        g.set_max_age(Some(Duration::from_secs(90 * day)));
+        assert_eq!(g.stale_on_path("imu", "base").unwrap(), [("cam", "base", now - 100 * day)]);
+        assert_eq!(g.stale_on_path("imu", "cam").unwrap(), []);
+        assert!(g.stale_on_path("imu", "nowhere").is_none());
+        assert!(g.edges().any(|e| e.calibrated == Some(now - day)));
+    }
+}
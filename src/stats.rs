@@ -0,0 +1,64 @@
+//! Size and memory usage of a graph.
+
+use std::mem::size_of;
+
+use petgraph::graph::{EdgeIndex, NodeIndex};
+
+use crate::{index::RouteIndex, se3::SE3, Edge, Frame, TransformEdge, TransformGraph};
+
+/// Approximate memory used by a graph, in bytes, including spare capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryFootprint {
+    /// Frames, including their names and the lookup by name.
+    pub nodes: usize,
+    /// Transforms and their state, including timed samples.
+    pub edges: usize,
+    /// The route index, if built.
+    pub index: usize,
+}
+
+impl MemoryFootprint {
+    pub fn total(&self) -> usize {
+        self.nodes + self.edges + self.index
+    }
+}
+
+impl<N: Frame, E: TransformEdge> TransformGraph<N, E> {
+    /// Approximate memory used by the graph. Frame data other than names is counted by its inline size only.
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        let (node_capacity, edge_capacity) = self.g.capacity();
+        // petgraph stores the weight with the first outgoing and incoming edge of each node, and the
+        // endpoints and next edges of each edge.
+        let node_size = size_of::<N>() + 2 * size_of::<EdgeIndex>();
+        let edge_size = size_of::<Edge<E>>() + 4 * size_of::<EdgeIndex>();
+        // Each lookup entry holds a copy of the normalized name, plus about a control byte.
+        let lookup_size = size_of::<(String, NodeIndex)>() + 1;
+        let lookup = self.lookup.capacity() * lookup_size + self.lookup.keys().map(String::len).sum::<usize>();
+        MemoryFootprint {
+            nodes: node_capacity * node_size + self.nodes().map(str::len).sum::<usize>() + lookup,
+            edges: edge_capacity * edge_size
+                + self.g.edge_weights().map(|e| e.samples.poses.capacity() * size_of::<(f64, SE3)>()).sum::<usize>(),
+            index: self.index.as_ref().map_or(0, RouteIndex::memory_footprint),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{se3, TfGraph};
+
+    #[test]
+    fn memory_footprint() {
+        let mut g = TfGraph::new();
+        let empty = g.memory_footprint();
+        for i in 1..100 {
+            g.add_tf(i.to_string(), (i / 2).to_string(), se3::random()).unwrap();
+        }
+        let full = g.memory_footprint();
+        assert!(full.nodes > empty.nodes + 100 * 24 && full.edges > empty.edges + 99 * 56);
+        assert_eq!(full.index, 0);
+        g.build_index().unwrap();
+        assert!(g.memory_footprint().index > 0);
+        assert_eq!(g.memory_footprint().total(), full.total() + g.memory_footprint().index);
+    }
+}
@@ -0,0 +1,306 @@
+//! Transforms that change over time, kept as timestamped samples and interpolated on query, like a tf2 buffer.
+//!
+//! The samples of each transform are a `Trajectory`, looked up with the interpolation and extrapolation
+//! set for the whole graph.
+
+use std::{collections::HashMap, time::Duration};
+
+use itertools::Itertools;
+use petgraph::{graph::{EdgeIndex, NodeIndex}, visit::EdgeRef};
+
+use crate::{se3::SE3, trajectory::{Extrapolation, Interpolation, Timeline}, Error, Frame, TfGraph, Warning};
+
+pub(crate) const DEFAULT_BUFFER_DURATION: Duration = Duration::from_secs(10);
+
+impl<N: Frame> TfGraph<N> {
+    /// Add the value of a transform at time `stamp`, in seconds.
+    ///
+    /// Works like `add_tf`, and the newest sample also becomes the transform `query_tf` uses. A sample
+    /// with the same stamp is replaced, and samples older than the buffer duration before the newest one
+    /// are dropped, with `Warning::SampleDropped` if that is the new one. Samples are not saved to files.
+    /// Fails with `Error::InvalidTimestamp` if `stamp` is not finite.
+    pub fn add_tf_at(&mut self, src: N, dst: N, tf: SE3, stamp: f64) -> Result<(), Error> {
+        if !stamp.is_finite() {
+            return Err(Error::InvalidTimestamp(stamp));
+        }
+        let (a, edge_ix) = self.add_tf_edge(src, dst, tf)?;
+        // The edge may be stored the other way around, see `set_canonical_edges`.
+        let tf = if self.g.edge_endpoints(edge_ix).expect("edge exists").0 == a { tf } else { tf.inverse() };
+        let oldest = self.buffer_duration.as_secs_f64();
+        let (interpolation, extrapolation) = (self.interpolation, self.extrapolation);
+        let edge = &mut self.g[edge_ix];
+        let samples = &mut edge.samples;
+        samples.interpolation = edge.interpolation.unwrap_or(interpolation);
+        samples.extrapolation = extrapolation;
+        let poses = &mut samples.poses;
+        let i = poses.partition_point(|&(t, _)| t < stamp);
+        match poses.get_mut(i) {
+            Some(sample) if sample.0 == stamp => sample.1 = tf,
+            _ => poses.insert(i, (stamp, tf)),
+        }
+        let &(newest, latest) = poses.last().expect("just added");
+        let keep = poses.partition_point(|&(t, _)| t < newest - oldest);
+        poses.drain(..keep);
+        self.g[edge_ix].tf = latest;
+        if stamp < newest - oldest {
+            let (from, to) = self.edge_names(a, edge_ix);
+            self.warn(Warning::SampleDropped { from, to, stamp });
+        }
+        Ok(())
+    }
+
+    /// Like `query_tf`, with each transform on the path interpolated at time `stamp`.
+    ///
+    /// Transforms without samples are static and valid at any time. Outside the samples of a transform, the
+    /// extrapolation policy applies (see `set_extrapolation`), and by default the query fails. The path is
+    /// the one `query_tf` takes.
+    pub fn query_tf_at(&self, src: &str, dst: &str, stamp: f64) -> Option<(SE3, Vec<&str>)> {
+        let path_nodes = self.find_path(src, dst)?;
+        let mut tf = SE3::identity();
+        for (&a, &b) in path_nodes.iter().tuple_windows() {
+            let edge = self.first_enabled_edge(a, b);
+            tf = self.chain_tf(edge, &self.tf_at(edge, stamp)?, a, &tf);
+        }
+        Some((self.directed(tf), path_nodes.into_iter().map(|ix| self.g[ix].name()).collect()))
+    }
+
+    /// Transforms from `src` to each of `dsts` at `stamp`, in the same order, with a single traversal of the
+    /// graph, so that all of them are from the same instant. The paths are the ones `query_many` takes.
+    ///
+    /// An entry is `None` if that frame does not exist or is not connected, or if `stamp` is outside the
+    /// samples of a transform on its path (see `query_tf_at`). Returns `None` if `src` does not exist.
+    pub fn query_many_at(&self, src: &str, dsts: &[&str], stamp: f64) -> Option<Vec<Option<SE3>>> {
+        let tree: HashMap<NodeIndex, Option<SE3>> = self
+            .path_tree(self.find_node(src)?, Some(SE3::identity()), |edge, parent, tf| {
+                Some(self.chain_tf(edge, &self.tf_at(edge, stamp)?, parent, tf.as_ref()?))
+            })
+            .into_iter()
+            .collect();
+        Some(dsts.iter().map(|dst| tree.get(&self.find_node(dst)?).copied().flatten().map(|tf| self.directed(tf))).collect())
+    }
+
+    /// Whether the transform between two frames is dynamic, i.e. holds timed samples, in either direction.
+    /// Static transforms hold at any time, and dynamic ones only around their samples.
+    ///
+    /// Returns `None` if there is no transform between them.
+    pub fn is_dynamic(&self, a: &str, b: &str) -> Option<bool> {
+        let (a, b) = (self.find_node(a)?, self.find_node(b)?);
+        let mut edges = self.g.edges_connecting(a, b).peekable();
+        edges.peek()?;
+        Some(edges.any(|e| !e.weight().samples.poses.is_empty()))
+    }
+
+    /// The stamps of the oldest and newest samples between two frames, or `None` if there are none.
+    pub fn sample_span(&self, a: &str, b: &str) -> Option<(f64, f64)> {
+        let (a, b) = (self.find_node(a)?, self.find_node(b)?);
+        let poses = &self.g.edges_connecting(a, b).find(|e| !e.weight().samples.poses.is_empty())?.weight().samples.poses;
+        Some((poses.first()?.0, poses.last()?.0))
+    }
+
+    /// Timing of the samples of each dynamic transform, as `(from, to, gap, timeline)` in the stored
+    /// direction. Gaps are intervals longer than `gap` seconds, or by default than twice the median
+    /// interval of that transform, and `gap` is the threshold used.
+    pub fn timelines(&self, gap: Option<f64>) -> impl Iterator<Item = (&str, &str, f64, Timeline)> + '_ {
+        self.g.edge_references().filter(|e| !e.weight().samples.poses.is_empty()).map(move |e| {
+            let samples = &e.weight().samples;
+            let gap = gap.or_else(|| samples.median_interval().map(|dt| 2.0 * dt)).unwrap_or(f64::INFINITY);
+            (self.g[e.source()].name(), self.g[e.target()].name(), gap, samples.timeline(gap))
+        })
+    }
+
+    /// Keep samples for `duration` before the newest one of each transform. Defaults to 10 seconds.
+    ///
+    /// Takes effect as samples are added.
+    pub fn set_buffer_duration(&mut self, duration: Duration) {
+        self.buffer_duration = duration;
+    }
+
+    pub fn buffer_duration(&self) -> Duration {
+        self.buffer_duration
+    }
+
+    /// How timed transforms are interpolated between samples (default: `Linear`), except those with their
+    /// own interpolation, see `set_edge_interpolation`.
+    pub fn set_interpolation(&mut self, interpolation: Interpolation) {
+        self.interpolation = interpolation;
+        for e in self.g.edge_weights_mut() {
+            e.samples.interpolation = e.interpolation.unwrap_or(interpolation);
+        }
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Interpolate the samples of the transform between two frames, in either direction, as `interpolation`
+    /// says instead of the graph, or as the graph does again with `None`. The choice is kept when samples
+    /// are added or dropped, but not saved.
+    ///
+    /// Returns `None` if there is no transform between them.
+    pub fn set_edge_interpolation(&mut self, a: &str, b: &str, interpolation: Option<Interpolation>) -> Option<()> {
+        let (a, b) = (self.find_node(a)?, self.find_node(b)?);
+        let edges: Vec<_> = self.g.edges_connecting(a, b).map(|e| e.id()).collect();
+        if edges.is_empty() {
+            return None;
+        }
+        for e in edges {
+            let edge = &mut self.g[e];
+            edge.interpolation = interpolation;
+            edge.samples.interpolation = interpolation.unwrap_or(self.interpolation);
+        }
+        Some(())
+    }
+
+    /// The interpolation of the transform between two frames: its own, or else the graph's. Returns `None`
+    /// if there is no transform between them.
+    pub fn edge_interpolation(&self, a: &str, b: &str) -> Option<Interpolation> {
+        let (a, b) = (self.find_node(a)?, self.find_node(b)?);
+        let edge = self.g.edges_connecting(a, b).next()?;
+        Some(edge.weight().interpolation.unwrap_or(self.interpolation))
+    }
+
+    /// How timed transforms are looked up before their oldest or after their newest sample (default:
+    /// `Error`, the query fails).
+    pub fn set_extrapolation(&mut self, extrapolation: Extrapolation) {
+        self.extrapolation = extrapolation;
+        for e in self.g.edge_weights_mut() {
+            e.samples.extrapolation = extrapolation;
+        }
+    }
+
+    pub fn extrapolation(&self) -> Extrapolation {
+        self.extrapolation
+    }
+
+    /// The transform of `edge` at `stamp`, in the stored direction.
+    pub(crate) fn tf_at(&self, edge: EdgeIndex, stamp: f64) -> Option<SE3> {
+        let edge = &self.g[edge];
+        if edge.samples.poses.is_empty() {
+            return Some(edge.tf);
+        }
+        edge.samples.at(stamp)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use approx::assert_relative_eq;
+
+    use crate::{se3::{self, SE3}, trajectory::{Extrapolation, Interpolation}, EdgeConvention, Error, TfGraph};
+
+    #[test]
+    fn timed() {
+        let mut g = TfGraph::new();
+        let fixed = se3::random();
+        g.add_tf("cam".to_owned(), "base".to_owned(), fixed).unwrap();
+        let at = |x: f64| SE3::translation(x, 0.0, 0.0);
+        g.add_tf_at("odom".to_owned(), "base".to_owned(), at(1.0), 10.0).unwrap();
+        g.add_tf_at("odom".to_owned(), "base".to_owned(), at(3.0), 11.0).unwrap();
+        g.add_tf_at("odom".to_owned(), "base".to_owned(), at(2.0), 10.5).unwrap();
+        assert_eq!(g.sample_span("base", "odom"), Some((10.0, 11.0)));
+        assert_eq!(g.is_dynamic("base", "odom"), Some(true));
+        assert_eq!(g.is_dynamic("cam", "base"), Some(false));
+        assert_eq!(g.is_dynamic("cam", "odom"), None);
+        assert_eq!(g.edges().filter(|e| e.dynamic).map(|e| (e.from, e.to)).collect::<Vec<_>>(), [("odom", "base")]);
+        assert_eq!(g.query_tf("odom", "base").unwrap().0, at(3.0));
+
+        let (tf, path) = g.query_tf_at("cam", "odom", 10.25).unwrap();
+        assert_eq!(path, ["cam", "base", "odom"]);
+        assert_relative_eq!(tf, at(-1.5) * fixed, epsilon = 1e-12);
+        assert!(g.query_tf_at("cam", "odom", 9.0).is_none());
+        assert!(g.query_tf_at("cam", "odom", 11.5).is_none());
+        assert_eq!(g.query_tf_at("cam", "base", 100.0).unwrap().0, fixed);
+
+        let many = g.query_many_at("cam", &["odom", "base", "cam", "nowhere"], 10.25).unwrap();
+        assert_relative_eq!(many[0].unwrap(), at(-1.5) * fixed, epsilon = 1e-12);
+        assert_eq!(many[1..], [Some(fixed), Some(SE3::identity()), None]);
+        assert_eq!(g.query_many_at("cam", &["odom", "base"], 9.0).unwrap(), [None, Some(fixed)]);
+        assert!(g.query_many_at("nowhere", &["base"], 10.25).is_none());
+
+        let timelines: Vec<_> = g.timelines(Some(0.4)).collect();
+        assert_eq!(timelines.len(), 1);
+        let (from, to, gap, timeline) = &timelines[0];
+        assert_eq!((*from, *to, *gap), ("odom", "base", 0.4));
+        assert_eq!((timeline.samples, timeline.span, timeline.rate), (3, 1.0, 2.0));
+        assert_eq!(timeline.gaps, [(10.0, 10.5), (10.5, 11.0)]);
+        assert_eq!(g.timelines(None).next().unwrap().2, 1.0);
+
+        // Samples follow the transform when it is stored the other way, or the convention changes.
+        g.set_canonical_edges(true);
+        assert_relative_eq!(g.query_tf_at("odom", "base", 10.75).unwrap().0, at(2.5), epsilon = 1e-12);
+        g.set_edge_convention(EdgeConvention::TargetToSource);
+        assert_relative_eq!(g.query_tf_at("odom", "base", 10.75).unwrap().0, at(-2.5), epsilon = 1e-12);
+        g.set_edge_convention(EdgeConvention::SourceToTarget);
+
+        g.set_buffer_duration(Duration::from_millis(800));
+        g.add_tf_at("odom".to_owned(), "base".to_owned(), at(4.0), 11.5).unwrap();
+        assert_eq!(g.sample_span("odom", "base"), Some((11.0, 11.5)));
+        assert_relative_eq!(g.query_tf_at("odom", "base", 11.25).unwrap().0, at(3.5), epsilon = 1e-12);
+        assert!(matches!(g.add_tf_at("odom".to_owned(), "base".to_owned(), at(0.0), f64::NAN), Err(Error::InvalidTimestamp(_))));
+
+        // Outside the samples, as the extrapolation policy says.
+        assert!(g.query_tf_at("odom", "base", 11.75).is_none());
+        g.set_extrapolation(Extrapolation::ClampToNearest);
+        assert_eq!(g.query_tf_at("odom", "base", 11.75).unwrap().0, at(4.0));
+        g.set_extrapolation(Extrapolation::ExtrapolateUpTo(Duration::from_millis(500)));
+        assert_relative_eq!(g.query_tf_at("odom", "base", 11.75).unwrap().0, at(4.5), epsilon = 1e-12);
+        assert!(g.query_tf_at("odom", "base", 12.25).is_none());
+        g.set_extrapolation(Extrapolation::Error);
+
+        // Adding a plain transform makes it static again.
+        g.add_tf("odom".to_owned(), "base".to_owned(), at(5.0)).unwrap();
+        assert!(g.sample_span("odom", "base").is_none());
+        assert_eq!(g.is_dynamic("odom", "base"), Some(false));
+        assert_eq!(g.query_tf_at("odom", "base", 11.25).unwrap().0, at(5.0));
+    }
+
+    #[test]
+    fn cubic() {
+        // Quadratic in x, so that cubic interpolation is exact and linear is not.
+        let at = |t: f64| SE3::translation(t * t, 0.0, 0.0);
+        let mut g = TfGraph::new();
+        g.set_interpolation(Interpolation::Cubic);
+        for t in 0..4 {
+            g.add_tf_at("odom".to_owned(), "base".to_owned(), at(t as f64), t as f64).unwrap();
+        }
+        assert_relative_eq!(g.query_tf_at("odom", "base", 1.5).unwrap().0, at(1.5), epsilon = 1e-12);
+        g.set_interpolation(Interpolation::Linear);
+        assert_relative_eq!(g.query_tf_at("odom", "base", 1.5).unwrap().0.translation.x, 2.5, epsilon = 1e-12);
+
+        // Per edge, kept as samples are added and the graph's setting changes.
+        g.add_tf_at("map".to_owned(), "odom".to_owned(), SE3::identity(), 0.0).unwrap();
+        g.set_edge_interpolation("base", "odom", Some(Interpolation::Cubic)).unwrap();
+        g.add_tf_at("odom".to_owned(), "base".to_owned(), at(4.0), 4.0).unwrap();
+        g.set_interpolation(Interpolation::Linear);
+        assert_eq!(g.edge_interpolation("odom", "base"), Some(Interpolation::Cubic));
+        assert_eq!(g.edge_interpolation("map", "odom"), Some(Interpolation::Linear));
+        assert_relative_eq!(g.query_tf_at("odom", "base", 1.5).unwrap().0, at(1.5), epsilon = 1e-12);
+        g.set_edge_interpolation("odom", "base", None).unwrap();
+        assert_eq!(g.edge_interpolation("odom", "base"), Some(Interpolation::Linear));
+        assert_relative_eq!(g.query_tf_at("odom", "base", 1.5).unwrap().0.translation.x, 2.5, epsilon = 1e-12);
+        assert!(g.set_edge_interpolation("map", "base", Some(Interpolation::Cubic)).is_none());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn settings_survive_load() {
+        let mut g = TfGraph::new();
+        g.add_tf("cam".to_owned(), "base".to_owned(), se3::random()).unwrap();
+        let mut buf = Vec::new();
+        g.dump_json(&mut buf).unwrap();
+
+        let mut g = TfGraph::new();
+        g.set_buffer_duration(Duration::from_secs(3));
+        g.set_interpolation(Interpolation::Cubic);
+        g.set_extrapolation(Extrapolation::ClampToNearest);
+        g.set_name_validator(|_| Ok(()));
+        g.load_json(&mut buf.as_slice()).unwrap();
+        assert_eq!(g.buffer_duration(), Duration::from_secs(3));
+        assert_eq!(g.interpolation(), Interpolation::Cubic);
+        assert_eq!(g.extrapolation(), Extrapolation::ClampToNearest);
+        assert!(g.check_name("/cam").is_ok());
+        assert!(g.query_tf("cam", "base").is_some());
+    }
+}
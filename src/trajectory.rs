@@ -0,0 +1,473 @@
+//! Timed sequences of poses, read from and written to the
+//! [TUM format](https://cvg.cit.tum.de/data/datasets/rgbd-dataset/file_formats).
+
+use std::{io::{self, BufRead, Write}, time::Duration};
+
+use nalgebra as na;
+
+use crate::se3::{self, To7, SE3};
+
+/// Poses of one frame over time, in increasing time order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trajectory {
+    /// `(timestamp, pose)`, where the pose maps points in the moving frame into the reference frame.
+    pub poses: Vec<(f64, SE3)>,
+    /// How poses between samples are computed.
+    pub interpolation: Interpolation,
+    /// How poses outside the time span are computed.
+    pub extrapolation: Extrapolation,
+}
+
+/// Lookups before the first or after the last pose of a trajectory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Extrapolation {
+    /// Fail.
+    #[default]
+    Error,
+    /// Use the first or last pose.
+    ClampToNearest,
+    /// Continue with the velocity between the first or last two poses, up to this far outside
+    /// the time span. Fails beyond that.
+    ExtrapolateUpTo(Duration),
+}
+
+/// Interpolation between the poses of a trajectory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// Linear in translation, shortest arc in rotation. Velocity jumps at every sample.
+    #[default]
+    Linear,
+    /// Cubic Hermite (Catmull-Rom) in translation, squad in rotation. Passes through the same
+    /// samples, with continuous velocity.
+    Cubic,
+}
+
+/// Timing of the samples of a trajectory. See `Trajectory::timeline`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Timeline {
+    pub samples: usize,
+    /// Time from the first to the last sample, in seconds.
+    pub span: f64,
+    /// Mean sample rate in Hz, or 0 with fewer than two samples.
+    pub rate: f64,
+    /// `(start, end)` of each interval between consecutive samples longer than the gap threshold.
+    pub gaps: Vec<(f64, f64)>,
+}
+
+/// Which side of each pose a transform is applied on. See `Trajectory::transformed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Side {
+    /// Change the reference frame.
+    #[default]
+    Left,
+    /// Change the moving frame.
+    Right,
+}
+
+impl Trajectory {
+    /// A trajectory with linear interpolation. Poses must be sorted by time.
+    pub fn new(poses: Vec<(f64, SE3)>) -> Self {
+        Self { poses, interpolation: Interpolation::Linear, extrapolation: Extrapolation::Error }
+    }
+
+    pub fn with_interpolation(self, interpolation: Interpolation) -> Self {
+        Self { interpolation, ..self }
+    }
+
+    pub fn with_extrapolation(self, extrapolation: Extrapolation) -> Self {
+        Self { extrapolation, ..self }
+    }
+
+    /// Read lines of `timestamp tx ty tz qx qy qz qw`. Blank lines and lines starting with `#`
+    /// are skipped. Poses are sorted by time.
+    pub fn read_tum(reader: impl BufRead) -> io::Result<Self> {
+        let mut poses = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("line {}: expected timestamp tx ty tz qx qy qz qw", i + 1));
+            let v: Vec<f64> = line.split_whitespace().map(str::parse).collect::<Result<_, _>>().map_err(|_| invalid())?;
+            if v.len() != 8 || v[4..].iter().all(|&x| x == 0.0) {
+                return Err(invalid());
+            }
+            poses.push((v[0], se3::from7(&v[1..]).unwrap()));
+        }
+        poses.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Self::new(poses))
+    }
+
+    pub fn write_tum(&self, writer: &mut impl Write) -> io::Result<()> {
+        for (t, pose) in &self.poses {
+            let [x, y, z, qx, qy, qz, qw] = pose.to7();
+            writeln!(writer, "{t} {x} {y} {z} {qx} {qy} {qz} {qw}")?;
+        }
+        Ok(())
+    }
+
+    /// Pose at time `t`, interpolated between the neighbouring poses.
+    ///
+    /// Outside the time span of the trajectory, `extrapolation` applies.
+    pub fn at(&self, t: f64) -> Option<SE3> {
+        let (&(first, _), &(last, _)) = (self.poses.first()?, self.poses.last()?);
+        if t.is_nan() {
+            return None;
+        }
+        if t < first || t > last {
+            return self.extrapolate(t);
+        }
+        let i = self.poses.partition_point(|&(ti, _)| ti < t);
+        let (t1, p1) = self.poses[i];
+        if t1 == t {
+            return Some(p1);
+        }
+        let (t0, p0) = self.poses[i - 1];
+        let s = (t - t0) / (t1 - t0);
+        Some(match self.interpolation {
+            Interpolation::Linear => se3::interpolate(&p0, &p1, s),
+            Interpolation::Cubic => self.cubic(i - 1, s),
+        })
+    }
+
+    fn extrapolate(&self, t: f64) -> Option<SE3> {
+        let n = self.poses.len();
+        let (end, next) = if t < self.poses[0].0 { (0, 1.min(n - 1)) } else { (n - 1, n.saturating_sub(2)) };
+        match self.extrapolation {
+            Extrapolation::Error => None,
+            Extrapolation::ClampToNearest => Some(self.poses[end].1),
+            Extrapolation::ExtrapolateUpTo(limit) => {
+                let ((t0, p0), (t1, p1)) = (self.poses[next], self.poses[end]);
+                if (t - t1).abs() > limit.as_secs_f64() {
+                    return None;
+                }
+                if next == end {
+                    return Some(p1);
+                }
+                // Continue the linear interpolation from `next` through `end`.
+                let s = (t - t0) / (t1 - t0);
+                let translation = p0.translation.vector.lerp(&p1.translation.vector, s);
+                let rotation = p0.rotation * na::UnitQuaternion::from_scaled_axis((p0.rotation.inverse() * p1.rotation).scaled_axis() * s);
+                Some(SE3::from_parts(translation.into(), rotation))
+            }
+        }
+    }
+
+    /// Interpolate between samples `i` and `i + 1`, at `s` in `[0, 1]`.
+    fn cubic(&self, i: usize, s: f64) -> SE3 {
+        let (t0, p0) = self.poses[i];
+        let (t1, p1) = self.poses[i + 1];
+        let h = t1 - t0;
+
+        // Translation: Hermite spline with finite-difference velocities.
+        let velocity = |j: usize| {
+            let (a, b) = (self.poses[j.saturating_sub(1)], self.poses[(j + 1).min(self.poses.len() - 1)]);
+            (b.1.translation.vector - a.1.translation.vector) / (b.0 - a.0)
+        };
+        let (s2, s3) = (s * s, s * s * s);
+        let translation = p0.translation.vector * (2.0 * s3 - 3.0 * s2 + 1.0)
+            + velocity(i) * h * (s3 - 2.0 * s2 + s)
+            + p1.translation.vector * (-2.0 * s3 + 3.0 * s2)
+            + velocity(i + 1) * h * (s3 - s2);
+
+        // Rotation: squad, with control points from the neighbouring rotations.
+        let control = |j: usize| {
+            let q = self.poses[j].1.rotation;
+            if j == 0 || j + 1 == self.poses.len() {
+                return q;
+            }
+            let next = (q.inverse() * self.poses[j + 1].1.rotation).scaled_axis();
+            let prev = (q.inverse() * self.poses[j - 1].1.rotation).scaled_axis();
+            q * na::UnitQuaternion::from_scaled_axis(-(next + prev) / 4.0)
+        };
+        let outer = p0.rotation.slerp(&p1.rotation, s);
+        let inner = control(i).slerp(&control(i + 1), s);
+        let rotation = outer.slerp(&inner, 2.0 * s * (1.0 - s));
+
+        SE3::from_parts(translation.into(), rotation)
+    }
+
+    /// Poses at the given timestamps. Timestamps that cannot be extrapolated to are skipped.
+    pub fn resample(&self, timestamps: impl IntoIterator<Item = f64>) -> Self {
+        let poses = timestamps.into_iter().filter_map(|t| Some((t, self.at(t)?))).collect();
+        Self { poses, ..*self }
+    }
+
+    /// Poses at a fixed rate in Hz, starting with the first pose.
+    pub fn resample_at_rate(&self, rate: f64) -> Self {
+        let (Some(&(start, _)), Some(&(end, _))) = (self.poses.first(), self.poses.last()) else {
+            return Self::default();
+        };
+        // Tolerate rounding, e.g. a 1 s span at 10 Hz has 11 samples
+        let n = ((end - start) * rate + 1e-9).floor() as usize;
+        self.resample((0..=n).map(|i| start + i as f64 / rate))
+    }
+
+    /// The poses from `start` to `end`, both included.
+    pub fn trimmed(&self, start: f64, end: f64) -> Self {
+        let poses = self.poses.iter().copied().filter(|&(t, _)| t >= start && t <= end).collect();
+        Self { poses, ..*self }
+    }
+
+    /// Cut into consecutive windows of `span` seconds, starting at the first pose. Each pose is in exactly
+    /// one window, and empty windows are left out.
+    pub fn split_every(&self, span: f64) -> Vec<Self> {
+        let Some(&(start, _)) = self.poses.first() else {
+            return Vec::new();
+        };
+        let chunks = self.poses.chunk_by(|a, b| ((a.0 - start) / span).floor() == ((b.0 - start) / span).floor());
+        chunks.map(|poses| Self { poses: poses.to_vec(), ..*self }).collect()
+    }
+
+    /// The same poses with `offset` seconds added to every timestamp, e.g. to move a recording onto the
+    /// clock of another.
+    pub fn shifted(&self, offset: f64) -> Self {
+        let poses = self.poses.iter().map(|&(t, pose)| (t + offset, pose)).collect();
+        Self { poses, ..*self }
+    }
+
+    /// Estimate the offset to add to the timestamps of `other` to align it with this trajectory, searching
+    /// up to `max_offset` seconds either way.
+    ///
+    /// Both must record the same rigid motion, e.g. two sensors on one body, or one frame logged by two
+    /// machines, but may be expressed in different reference and moving frames: the offset is the one
+    /// that best matches their angular speed over time, at the finer of their median sample intervals.
+    /// Returns `None` if either has fewer than two poses, or if they overlap by less than half of the
+    /// shorter one at every offset.
+    pub fn clock_offset(&self, other: &Trajectory, max_offset: f64) -> Option<f64> {
+        let dt = self.median_interval()?.min(other.median_interval()?);
+        if dt <= 0.0 {
+            return None;
+        }
+        let (a, b) = (self.resample_at_rate(1.0 / dt), other.resample_at_rate(1.0 / dt));
+        let speed = |traj: &Trajectory| -> Vec<f64> {
+            traj.poses.windows(2).map(|w| w[0].1.rotation.angle_to(&w[1].1.rotation) / dt).collect()
+        };
+        let (speed_a, speed_b) = (speed(&a), speed(&b));
+        let (start_a, start_b) = (a.poses.first()?.0, b.poses.first()?.0);
+        let min_overlap = speed_a.len().min(speed_b.len()).div_ceil(2).max(1);
+
+        let steps = (max_offset / dt).floor() as i64;
+        (-steps..=steps)
+            .filter_map(|step| {
+                let offset = step as f64 * dt;
+                // Index into speed_b of the sample at the same time as speed_a[0].
+                let first = ((start_a - offset - start_b) / dt).round() as i64;
+                let pairs = speed_a.iter().enumerate().filter_map(|(i, &sa)| {
+                    let j = usize::try_from(first + i as i64).ok()?;
+                    Some(sa - speed_b.get(j)?)
+                });
+                let (n, sum) = pairs.fold((0, 0.0), |(n, sum), d| (n + 1, sum + d * d));
+                (n >= min_overlap).then(|| (start_a - start_b - first as f64 * dt, sum / n as f64))
+            })
+            .min_by(|x, y| x.1.total_cmp(&y.1))
+            .map(|(offset, _)| offset)
+    }
+
+    /// Drop poses that linear interpolation between the remaining ones reproduces within `translation` and
+    /// `rotation` (radians). The first and last poses are always kept.
+    pub fn decimated(&self, translation: f64, rotation: f64) -> Self {
+        let Some(&first) = self.poses.first() else {
+            return self.clone();
+        };
+        let within = |i: usize, j: usize| {
+            let ((t0, p0), (t1, p1)) = (self.poses[i], self.poses[j]);
+            self.poses[i + 1..j].iter().all(|&(t, pose)| {
+                let interpolated = se3::interpolate(&p0, &p1, (t - t0) / (t1 - t0));
+                (interpolated.translation.vector - pose.translation.vector).norm() <= translation
+                    && interpolated.rotation.angle_to(&pose.rotation) <= rotation
+            })
+        };
+        let mut poses = vec![first];
+        let mut kept = 0;
+        for j in 1..self.poses.len() {
+            // Keep the previous pose once the span from the last kept one can no longer be interpolated.
+            if j > kept + 1 && !within(kept, j) {
+                kept = j - 1;
+                poses.push(self.poses[kept]);
+            }
+        }
+        if self.poses.len() > 1 {
+            poses.push(*self.poses.last().unwrap());
+        }
+        Self { poses, ..*self }
+    }
+
+    /// Sample count, time span, rate, and the gaps between samples longer than `gap` seconds, e.g. dropouts
+    /// in a recorded stream.
+    pub fn timeline(&self, gap: f64) -> Timeline {
+        let (Some(&(start, _)), Some(&(end, _))) = (self.poses.first(), self.poses.last()) else {
+            return Timeline::default();
+        };
+        let span = end - start;
+        Timeline {
+            samples: self.poses.len(),
+            span,
+            rate: if span > 0.0 { (self.poses.len() - 1) as f64 / span } else { 0.0 },
+            gaps: self.poses.windows(2).map(|w| (w[0].0, w[1].0)).filter(|(a, b)| b - a > gap).collect(),
+        }
+    }
+
+    /// Median time between consecutive samples, or `None` with fewer than two samples.
+    pub fn median_interval(&self) -> Option<f64> {
+        let mut intervals: Vec<f64> = self.poses.windows(2).map(|w| w[1].0 - w[0].0).collect();
+        if intervals.is_empty() {
+            return None;
+        }
+        let mid = intervals.len() / 2;
+        Some(*intervals.select_nth_unstable_by(mid, f64::total_cmp).1)
+    }
+
+    /// Apply `tf` to every pose.
+    ///
+    /// With `Side::Left`, a trajectory expressed in frame `a` is re-expressed in frame `b`, given
+    /// the transform `a -> b`. With `Side::Right`, a trajectory of frame `b` becomes the trajectory
+    /// of frame `a`, given the transform `a -> b`.
+    pub fn transformed(&self, tf: &SE3, side: Side) -> Self {
+        let poses = self.poses.iter().map(|&(t, pose)| match side {
+            Side::Left => (t, tf * pose),
+            Side::Right => (t, pose * tf),
+        }).collect();
+        Self { poses, ..*self }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_relative_eq;
+    use nalgebra as na;
+    use super::*;
+
+    #[test]
+    fn tum() {
+        let text = "# timestamp tx ty tz qx qy qz qw\n2.0 1 2 3 0 0 0 1\n\n1.5 0 0 0 0 0 1 0\n";
+        let traj = Trajectory::read_tum(text.as_bytes()).unwrap();
+        assert_eq!(traj.poses.len(), 2);
+        assert_eq!(traj.poses[0].0, 1.5);
+
+        let mut out = Vec::new();
+        traj.write_tum(&mut out).unwrap();
+        assert_eq!(Trajectory::read_tum(out.as_slice()).unwrap(), traj);
+        assert!(Trajectory::read_tum("1 2 3".as_bytes()).is_err());
+        assert!(Trajectory::read_tum("1 0 0 0 0 0 0 0".as_bytes()).is_err());
+
+        let tf = se3::random();
+        let left = traj.transformed(&tf, Side::Left);
+        assert_relative_eq!(left.poses[1].1, tf * traj.poses[1].1);
+        let right = traj.transformed(&tf, Side::Right);
+        assert_relative_eq!(right.poses[1].1, traj.poses[1].1 * tf);
+    }
+
+    #[test]
+    fn resample() {
+        let a = se3::from_array(&[0.0, 0.0, 0.0]).unwrap();
+        let b = se3::from_array(&[1.0, 2.0, 0.0, 0.0, 0.0, 1.0, 0.0]).unwrap();
+        let traj = Trajectory::new(vec![(0.0, a), (1.0, b)]);
+        assert_eq!(traj.at(0.0), Some(a));
+        assert_eq!(traj.at(1.0), Some(b));
+        assert!(traj.at(-0.1).is_none());
+        assert!(traj.at(1.1).is_none());
+        let mid = traj.at(0.5).unwrap();
+        assert_relative_eq!(mid.translation.vector, na::Vector3::new(0.5, 1.0, 0.0));
+        assert_relative_eq!(mid.rotation.angle(), std::f64::consts::FRAC_PI_2, epsilon = 1e-12);
+
+        let resampled = traj.resample_at_rate(4.0);
+        assert_eq!(resampled.poses.iter().map(|p| p.0).collect::<Vec<_>>(), [0.0, 0.25, 0.5, 0.75, 1.0]);
+        assert_eq!(traj.resample([0.5, 2.0]).poses.len(), 1);
+    }
+
+    #[test]
+    fn trim() {
+        let traj = Trajectory::new((0..10).map(|i| (i as f64 * 0.5, se3::random())).collect());
+        let trimmed = traj.trimmed(1.0, 2.2);
+        assert_eq!(trimmed.poses, traj.poses[2..5]);
+
+        let parts = traj.split_every(2.0);
+        assert_eq!(parts.iter().map(|p| p.poses.len()).collect::<Vec<_>>(), [4, 4, 2]);
+        assert_eq!(parts[1].poses[0].0, 2.0);
+        assert_eq!(parts.iter().flat_map(|p| p.poses.clone()).collect::<Vec<_>>(), traj.poses);
+        assert!(Trajectory::default().split_every(1.0).is_empty());
+    }
+
+    #[test]
+    fn decimate() {
+        // Straight at constant speed, then turning.
+        let pose = |t: f64| match t {
+            t if t <= 5.0 => SE3::new(na::Vector3::new(t, 0.0, 0.0), na::Vector3::zeros()),
+            t => SE3::new(na::Vector3::new(5.0, t - 5.0, 0.0), na::Vector3::new(0.0, 0.0, 0.2 * (t - 5.0))),
+        };
+        let traj = Trajectory::new((0..=20).map(|i| (i as f64 * 0.5, pose(i as f64 * 0.5))).collect());
+        let decimated = traj.decimated(1e-6, 1e-6);
+        assert_eq!(decimated.poses.iter().map(|p| p.0).collect::<Vec<_>>(), [0.0, 5.0, 10.0]);
+        for t in [1.3, 4.0, 7.5] {
+            assert_relative_eq!(decimated.at(t).unwrap(), traj.at(t).unwrap(), epsilon = 1e-9);
+        }
+        assert_eq!(traj.decimated(10.0, 1.0).poses.len(), 2);
+        assert_eq!(Trajectory::default().decimated(1.0, 1.0).poses.len(), 0);
+    }
+
+    #[test]
+    fn timeline() {
+        let times = [0.0, 0.1, 0.2, 0.3, 1.3, 1.4, 1.5, 2.0];
+        let traj = Trajectory::new(times.iter().map(|&t| (t, se3::random())).collect());
+        assert_relative_eq!(traj.median_interval().unwrap(), 0.1, epsilon = 1e-12);
+        let timeline = traj.timeline(0.25);
+        assert_eq!(timeline.samples, 8);
+        assert_relative_eq!(timeline.span, 2.0);
+        assert_relative_eq!(timeline.rate, 3.5);
+        assert_eq!(timeline.gaps, [(0.3, 1.3), (1.5, 2.0)]);
+        assert_eq!(Trajectory::default().timeline(1.0), Timeline::default());
+        assert!(Trajectory::default().median_interval().is_none());
+    }
+
+    #[test]
+    fn clock_offset() {
+        let pose = |t: f64| SE3::new(
+            na::Vector3::new(t.cos(), t.sin(), 0.0),
+            na::Vector3::new(0.3 * (1.3 * t).sin(), 0.0, t.sin() + 0.4 * (2.7 * t).sin()),
+        );
+        let traj = Trajectory::new((0..500).map(|i| (i as f64 * 0.02, pose(i as f64 * 0.02))).collect());
+        // Another sensor on the same body, logged at 100 Hz by a clock 0.3 s ahead.
+        let (mount, world) = (se3::random(), se3::random());
+        let other = Trajectory::new((0..900).map(|i| {
+            let t = 0.5 + i as f64 * 0.01;
+            (t + 0.3, world * pose(t) * mount)
+        }).collect());
+        let offset = traj.clock_offset(&other, 1.0).unwrap();
+        assert_relative_eq!(offset, -0.3, epsilon = 0.011);
+        assert_relative_eq!(other.shifted(offset).poses[0].0, 0.5, epsilon = 0.011);
+        assert!(traj.clock_offset(&Trajectory::default(), 1.0).is_none());
+    }
+
+    #[test]
+    fn cubic() {
+        // Quadratic translation, constant angular velocity
+        let pose = |t: f64| SE3::new(na::Vector3::new(t * t, t, 0.0), na::Vector3::new(0.0, 0.0, 0.3 * t));
+        let traj = Trajectory::new((0..4).map(|i| (i as f64, pose(i as f64))).collect());
+        let cubic = traj.clone().with_interpolation(Interpolation::Cubic);
+
+        assert_relative_eq!(cubic.at(1.0).unwrap(), pose(1.0));
+        assert_relative_eq!(cubic.at(1.5).unwrap(), pose(1.5), epsilon = 1e-12);
+        assert_relative_eq!(traj.at(1.5).unwrap().translation.x, 2.5);
+        assert_eq!(cubic.resample([1.5]).interpolation, Interpolation::Cubic);
+    }
+
+    #[test]
+    fn extrapolate() {
+        let pose = |t: f64| SE3::new(na::Vector3::new(t, 0.0, 0.0), na::Vector3::new(0.0, 0.0, 0.3 * t));
+        let traj = Trajectory::new((0..3).map(|i| (i as f64, pose(i as f64))).collect());
+        assert!(traj.at(2.5).is_none());
+        assert!(traj.at(f64::NAN).is_none());
+
+        let clamped = traj.clone().with_extrapolation(Extrapolation::ClampToNearest);
+        assert_eq!(clamped.at(2.5), Some(pose(2.0)));
+        assert_eq!(clamped.at(-7.0), Some(pose(0.0)));
+
+        let extrapolated = traj.with_extrapolation(Extrapolation::ExtrapolateUpTo(Duration::from_millis(500)));
+        assert_relative_eq!(extrapolated.at(2.5).unwrap(), pose(2.5), epsilon = 1e-12);
+        assert_relative_eq!(extrapolated.at(-0.5).unwrap(), pose(-0.5), epsilon = 1e-12);
+        assert!(extrapolated.at(2.6).is_none());
+        assert_eq!(extrapolated.resample_at_rate(1.0).poses.len(), 3);
+    }
+}
@@ -0,0 +1,57 @@
+use nalgebra as na;
+
+use crate::se3::SE3;
+
+/// A transform type that can be stored on graph edges and chained along a path.
+///
+/// Implemented for `SE3` (rigid), `Similarity3` (rigid + uniform scale) and `Affine3`.
+pub trait TransformEdge: Sized {
+    fn identity() -> Self;
+
+    /// `self * rhs`, i.e. `rhs` is applied first.
+    fn compose(&self, rhs: &Self) -> Self;
+
+    fn inverse(&self) -> Self;
+}
+
+impl TransformEdge for SE3 {
+    fn identity() -> Self {
+        SE3::identity()
+    }
+
+    fn compose(&self, rhs: &Self) -> Self {
+        self * rhs
+    }
+
+    fn inverse(&self) -> Self {
+        self.inverse()
+    }
+}
+
+impl TransformEdge for na::Similarity3<f64> {
+    fn identity() -> Self {
+        na::Similarity3::identity()
+    }
+
+    fn compose(&self, rhs: &Self) -> Self {
+        self * rhs
+    }
+
+    fn inverse(&self) -> Self {
+        self.inverse()
+    }
+}
+
+impl TransformEdge for na::Affine3<f64> {
+    fn identity() -> Self {
+        na::Affine3::identity()
+    }
+
+    fn compose(&self, rhs: &Self) -> Self {
+        self * rhs
+    }
+
+    fn inverse(&self) -> Self {
+        na::Affine3::inverse(*self)
+    }
+}
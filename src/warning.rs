@@ -0,0 +1,133 @@
+//! Non-fatal issues found while changing a graph, for frontends to show.
+
+use std::fmt;
+
+use petgraph::graph::{EdgeIndex, NodeIndex};
+
+use crate::{Frame, TransformGraph};
+
+/// Something the graph accepted, but changed or discarded on the way. See `set_collect_warnings`.
+///
+/// In JSON: `{"warning": "overwritten", "from": "cam", "to": "base"}`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize), serde(tag = "warning", rename_all = "snake_case"))]
+#[non_exhaustive]
+pub enum Warning {
+    /// A transform replaced the one already between these frames.
+    Overwritten { from: String, to: String },
+    /// The rotation of a loaded transform was not a unit quaternion, and was normalized.
+    Renormalized { from: String, to: String },
+    /// A timed sample was older than the buffer duration allows, and was dropped right away.
+    SampleDropped { from: String, to: String, stamp: f64 },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::Overwritten { from, to } => write!(f, "replaced transform {from} -> {to}"),
+            Warning::Renormalized { from, to } => write!(f, "normalized the rotation of {from} -> {to}"),
+            Warning::SampleDropped { from, to, stamp } => write!(f, "dropped sample of {from} -> {to} at {stamp}, older than the buffer"),
+        }
+    }
+}
+
+impl<N: Frame, E> TransformGraph<N, E> {
+    /// Keep warnings until `take_warnings`, for the caller to show. Off by default, when warnings are
+    /// logged with `tracing` instead.
+    ///
+    /// Turning it off discards the warnings kept so far.
+    pub fn set_collect_warnings(&mut self, collect: bool) {
+        match (collect, &self.warnings) {
+            (true, None) => self.warnings = Some(Vec::new()),
+            (false, _) => self.warnings = None,
+            (true, Some(_)) => {}
+        }
+    }
+
+    pub fn collects_warnings(&self) -> bool {
+        self.warnings.is_some()
+    }
+
+    /// The warnings since the last call, oldest first.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        self.warnings.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    pub(crate) fn warn(&mut self, warning: Warning) {
+        #[cfg(feature = "tracing")]
+        if self.warnings.is_some() {
+            tracing::debug!("{warning}");
+        } else {
+            tracing::warn!("{warning}");
+        }
+        if let Some(warnings) = &mut self.warnings {
+            warnings.push(warning);
+        }
+    }
+
+    /// The names of `edge`, from `a` to its other end.
+    pub(crate) fn edge_names(&self, a: NodeIndex, edge: EdgeIndex) -> (String, String) {
+        let (x, y) = self.g.edge_endpoints(edge).expect("edge exists");
+        let b = if x == a { y } else { x };
+        (self.g[a].name().to_owned(), self.g[b].name().to_owned())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{se3, TfGraph};
+    use super::Warning;
+
+    #[test]
+    fn warnings() {
+        let mut g = TfGraph::new();
+        g.add_tf("cam".to_owned(), "base".to_owned(), se3::random()).unwrap();
+        g.add_tf("cam".to_owned(), "base".to_owned(), se3::random()).unwrap();
+        assert!(g.take_warnings().is_empty());
+
+        g.set_collect_warnings(true);
+        g.add_tf("cam".to_owned(), "base".to_owned(), se3::random()).unwrap();
+        g.add_tf("imu".to_owned(), "base".to_owned(), se3::random()).unwrap();
+        g.add_tf_at("odom".to_owned(), "base".to_owned(), se3::random(), 20.0).unwrap();
+        g.add_tf_at("odom".to_owned(), "base".to_owned(), se3::random(), 5.0).unwrap();
+        g.add_tfs([("imu".to_owned(), "base".to_owned(), se3::random())]);
+        assert_eq!(g.take_warnings(), [
+            Warning::Overwritten { from: "cam".to_owned(), to: "base".to_owned() },
+            Warning::SampleDropped { from: "odom".to_owned(), to: "base".to_owned(), stamp: 5.0 },
+            Warning::Overwritten { from: "imu".to_owned(), to: "base".to_owned() },
+        ]);
+        assert!(g.take_warnings().is_empty());
+
+        g.set_collect_warnings(false);
+        g.add_tf("cam".to_owned(), "base".to_owned(), se3::random()).unwrap();
+        g.set_collect_warnings(true);
+        assert!(g.take_warnings().is_empty());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn load_warnings() {
+        let mut g = TfGraph::new();
+        g.set_collect_warnings(true);
+        let file = r#"{
+            "frames": ["a", "b"],
+            "transforms": [
+                {"from": "a", "to": "b", "tf": [0, 0, 0, 0, 0, 0, 2]},
+                {"from": "a", "to": "b", "tf": [1, 0, 0, 0, 0, 0, 1]}
+            ]
+        }"#;
+        g.load_json(&mut file.as_bytes()).unwrap();
+        assert!(g.collects_warnings());
+        assert_eq!(g.take_warnings(), [
+            Warning::Renormalized { from: "a".to_owned(), to: "b".to_owned() },
+            Warning::Overwritten { from: "a".to_owned(), to: "b".to_owned() },
+        ]);
+        assert_eq!(g.query_tf("a", "b").unwrap().0, se3::from_array(&[1.0, 0.0, 0.0]).unwrap());
+
+        // Loading into a graph that does not collect them keeps none.
+        let mut g = TfGraph::new();
+        g.load_json(&mut file.as_bytes()).unwrap();
+        assert!(!g.collects_warnings());
+        assert!(g.take_warnings().is_empty());
+    }
+}
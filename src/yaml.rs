@@ -0,0 +1,62 @@
+//! Loading and saving in YAML, with the same layout as JSON files.
+
+use std::io;
+
+use crate::{file::GraphFile, Error, Frame, TfGraph};
+
+impl<N: Frame> TfGraph<N> {
+    /// Like `dump_json`, in YAML.
+    pub fn dump_yaml(&self, writer: &mut impl io::Write) -> Result<(), Error>
+    where N: serde::Serialize
+    {
+        let text = serde_yaml::to_string(&GraphFile::from_graph(self))?;
+        Ok(writer.write_all(text.as_bytes())?)
+    }
+
+    /// Like `load_json`, for files saved by `dump_yaml`, or written by hand in the same layout.
+    pub fn load_yaml(&mut self, reader: &mut impl io::Read) -> Result<(), Error>
+    where N: serde::de::DeserializeOwned
+    {
+        if self.frozen {
+            return Err(Error::Frozen);
+        }
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let file: GraphFile<N> = serde_yaml::from_str(&text)?;
+        let loaded = file.into_graph(self.allow_cycles)?;
+        self.replace_with_loaded(loaded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{se3, Error, TfGraph};
+
+    #[test]
+    fn yaml_roundtrip() {
+        let mut g = TfGraph::new();
+        g.add_tf("cam".to_owned(), "base".to_owned(), se3::random()).unwrap();
+        g.add_tf("imu".to_owned(), "base".to_owned(), se3::random()).unwrap();
+        g.set_root("base").unwrap();
+        let mut buf = Vec::new();
+        g.dump_yaml(&mut buf).unwrap();
+
+        let mut g2 = TfGraph::new();
+        g2.load_yaml(&mut buf.as_slice()).unwrap();
+        assert!(g2.nodes().eq(g.nodes()));
+        assert!(g2.edges().map(|e| (e.from, e.to, *e.tf)).eq(g.edges().map(|e| (e.from, e.to, *e.tf))));
+        assert!(g2.roots().eq(["base"]));
+
+        let by_hand = "
+frames: [base, lidar]
+transforms:
+  - from: lidar
+    to: base
+    tf: [0.5, 0, 1, 0, 0, 0, 1]
+";
+        g2.load_yaml(&mut by_hand.as_bytes()).unwrap();
+        assert_eq!(g2.query_tf("lidar", "base").unwrap().0, se3::from_array(&[0.5, 0.0, 1.0]).unwrap());
+        assert!(matches!(g2.load_yaml(&mut "frames: 3".as_bytes()), Err(Error::Parse(_))));
+        assert!(g2.nodes().eq(["base", "lidar"]));
+    }
+}